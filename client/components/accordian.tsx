@@ -10,9 +10,11 @@ import {
   Flex,
   Badge,
   HStack,
+  Text,
 } from "@chakra-ui/react";
 import { keyframes } from "@emotion/react";
 import { ChevronDown, ChevronUp } from "lucide-react";
+import type { ValidationIssue } from "../types";
 
 type AccordionProps = {
   title: string;
@@ -26,6 +28,8 @@ type AccordionProps = {
   dualBorder?: boolean;
   stagingCount?: number;
   productionCount?: number;
+  /** Validation issues to show, even when collapsed */
+  issues?: ValidationIssue[];
 };
 
 const rotate = keyframes`
@@ -45,6 +49,7 @@ export function QuestionAccordion({
   dualBorder = false,
   stagingCount,
   productionCount,
+  issues = [],
 }: AccordionProps) {
   const { open, onToggle } = useDisclosure();
 
@@ -165,6 +170,16 @@ export function QuestionAccordion({
                 dangerouslySetInnerHTML={{ __html: parseMarkdown(subtitle) }}
                 whiteSpace="pre-line"
               />
+              {issues.map((issue, i) => (
+                <Text
+                  key={i}
+                  fontSize="sm"
+                  color={issue.severity === "error" ? "red.300" : "orange.300"}
+                  title={issue.code}
+                >
+                  {issue.message} ({issue.path})
+                </Text>
+              ))}
             </Box>
             <IconButton
               aria-label={open ? "Collapse" : "Expand"}
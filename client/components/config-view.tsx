@@ -7,13 +7,19 @@ import {
   Accordion,
 } from "@chakra-ui/react";
 import { ExamEnvironmentConfig } from "@prisma/client";
+import type { ValidationIssue } from "../types";
 
 interface ConfigViewProps {
   config: ExamEnvironmentConfig;
   setConfig: (partialConfig: Partial<ExamEnvironmentConfig>) => void;
+  validationIssues?: ValidationIssue[];
 }
 
-export function ConfigView({ config, setConfig }: ConfigViewProps) {
+export function ConfigView({
+  config,
+  setConfig,
+  validationIssues = [],
+}: ConfigViewProps) {
   return (
     <>
       <Accordion.Root defaultValue={["0"]} multiple={true}>
@@ -58,6 +64,9 @@ export function ConfigView({ config, setConfig }: ConfigViewProps) {
                 >
                   <span>✕</span>
                 </IconButton>
+                <Issues
+                  issues={issuesAt(validationIssues, `$.config.tags[${index}]`)}
+                />
               </Box>
             ))}
           </Accordion.ItemContent>
@@ -105,6 +114,12 @@ export function ConfigView({ config, setConfig }: ConfigViewProps) {
                 >
                   Remove
                 </Button>
+                <Issues
+                  issues={issuesAt(
+                    validationIssues,
+                    `$.config.questionSets[${index}]`,
+                  )}
+                />
               </Box>
             ))}
           </Accordion.ItemContent>
@@ -113,3 +128,28 @@ export function ConfigView({ config, setConfig }: ConfigViewProps) {
     </>
   );
 }
+
+function Issues({ issues }: { issues: ValidationIssue[] }) {
+  return issues.map((issue, i) => (
+    <Text
+      key={i}
+      fontSize="sm"
+      color={issue.severity === "error" ? "red.300" : "orange.300"}
+      title={issue.code}
+    >
+      {issue.message}
+    </Text>
+  ));
+}
+
+/**
+ * Issues located at `path`, or within it
+ */
+function issuesAt(issues: ValidationIssue[], path: string) {
+  return issues.filter(
+    (issue) =>
+      issue.path === path ||
+      issue.path.startsWith(`${path}.`) ||
+      issue.path.startsWith(`${path}[`),
+  );
+}
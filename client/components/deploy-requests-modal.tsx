@@ -0,0 +1,244 @@
+import {
+  Badge,
+  Box,
+  Button,
+  Dialog,
+  Flex,
+  HStack,
+  Input,
+  Spinner,
+  Text,
+} from "@chakra-ui/react";
+import { useMutation, useQuery } from "@tanstack/react-query";
+import { useContext, useState } from "react";
+import { AuthContext } from "../contexts/auth";
+import type { DeployRequest } from "../types";
+import {
+  getDeployRequests,
+  putExecuteDeployRequest,
+  putReviewDeployRequest,
+  type OrphanedGenerationsAction,
+} from "../utils/fetch";
+import { DeploymentPreviewList } from "./deployment-preview";
+import { OrphanedGenerationsField } from "./seed-modal";
+
+interface DeployRequestsModalProps {
+  open: boolean;
+  onClose: () => void;
+  /** Exam names by id, for labelling requests */
+  examNames: Map<string, string>;
+  /** Called once a request is deployed */
+  onDeployed: () => void;
+}
+
+/**
+ * Lists deploy requests, for another user to approve or reject, and approved requests to be deployed
+ */
+export function DeployRequestsModal({
+  open,
+  onClose,
+  examNames,
+  onDeployed,
+}: DeployRequestsModalProps) {
+  const deployRequestsQuery = useQuery({
+    queryKey: ["deploy-requests"],
+    enabled: open,
+    queryFn: () => getDeployRequests(),
+    retry: false,
+    refetchOnWindowFocus: false,
+  });
+
+  return (
+    <Dialog.Root open={open} onOpenChange={onClose} size="lg">
+      <Dialog.Content backgroundColor={"gray.700"} color={"white"}>
+        <Dialog.Header>Deploy Requests</Dialog.Header>
+        <Dialog.CloseTrigger />
+        <Dialog.Body maxH="70vh" overflowY="auto">
+          {deployRequestsQuery.isPending ? (
+            <Spinner />
+          ) : deployRequestsQuery.isError ? (
+            <Text color="red.400">{deployRequestsQuery.error.message}</Text>
+          ) : deployRequestsQuery.data.length === 0 ? (
+            <Text color="gray.400">No deploy requests</Text>
+          ) : (
+            deployRequestsQuery.data.map((deployRequest) => (
+              <DeployRequestItem
+                key={deployRequest.id}
+                deployRequest={deployRequest}
+                examName={examNames.get(deployRequest.examId)}
+                onChange={(deployed) => {
+                  deployRequestsQuery.refetch();
+                  if (deployed) {
+                    onDeployed();
+                  }
+                }}
+              />
+            ))
+          )}
+        </Dialog.Body>
+        <Dialog.Footer>
+          <Button colorPalette="blue" onClick={onClose}>
+            Close
+          </Button>
+        </Dialog.Footer>
+      </Dialog.Content>
+    </Dialog.Root>
+  );
+}
+
+interface DeployRequestItemProps {
+  deployRequest: DeployRequest;
+  examName?: string;
+  onChange: (deployed: boolean) => void;
+}
+
+function DeployRequestItem({
+  deployRequest,
+  examName,
+  onChange,
+}: DeployRequestItemProps) {
+  const { user } = useContext(AuthContext)!;
+  const [comment, setComment] = useState("");
+  const [orphanedGenerations, setOrphanedGenerations] =
+    useState<OrphanedGenerationsAction>("block");
+
+  const reviewMutation = useMutation({
+    mutationFn: (review: "approve" | "reject") =>
+      putReviewDeployRequest(deployRequest.id, review, comment || null),
+    onSuccess() {
+      setComment("");
+      onChange(false);
+    },
+  });
+  const previewMutation = useMutation({
+    mutationFn: () =>
+      putExecuteDeployRequest(deployRequest.id, orphanedGenerations, true),
+  });
+  const executeMutation = useMutation({
+    mutationFn: () =>
+      putExecuteDeployRequest(deployRequest.id, orphanedGenerations),
+    onSuccess() {
+      onChange(true);
+    },
+  });
+
+  const isRequester = user?.email === deployRequest.requesterEmail;
+  const error =
+    reviewMutation.error ?? previewMutation.error ?? executeMutation.error;
+
+  return (
+    <Box mb={4} p={3} borderWidth={1} borderRadius="md" borderColor="gray.600">
+      <Flex justify="space-between" align="center">
+        <Text fontWeight="bold">
+          {examName ?? deployRequest.examId} at revision{" "}
+          {deployRequest.revision}
+          {deployRequest.rollbackDeploymentId &&
+            `, rolling back to deployment ${deployRequest.rollbackDeploymentId}`}
+          {deployRequest.deprecated !== null &&
+            `, to ${deployRequest.deprecated ? "deprecate" : "undeprecate"}`}
+        </Text>
+        <Badge colorPalette={statusColor(deployRequest.status)}>
+          {deployRequest.status}
+        </Badge>
+      </Flex>
+      <Box fontSize="sm" ps={4} mt={2}>
+        {deployRequest.transitions.map((transition, i) => (
+          <Text key={i}>
+            {transition.status} by {transition.actorName} on{" "}
+            {transition.createdAt.toLocaleString()}
+            {transition.comment && `: ${transition.comment}`}
+          </Text>
+        ))}
+      </Box>
+
+      {deployRequest.status === "Pending" &&
+        (isRequester ? (
+          <Text fontSize="sm" color="gray.400" mt={2}>
+            Waiting for another user to review
+          </Text>
+        ) : (
+          <HStack mt={3}>
+            <Input
+              size="sm"
+              placeholder="Comment"
+              value={comment}
+              onChange={(e) => setComment(e.target.value)}
+            />
+            <Button
+              size="sm"
+              colorPalette="green"
+              onClick={() => reviewMutation.mutate("approve")}
+              loading={reviewMutation.isPending}
+            >
+              Approve
+            </Button>
+            <Button
+              size="sm"
+              colorPalette="red"
+              variant="outline"
+              onClick={() => reviewMutation.mutate("reject")}
+              loading={reviewMutation.isPending}
+            >
+              Reject
+            </Button>
+          </HStack>
+        ))}
+
+      {deployRequest.status === "Approved" &&
+        (deployRequest.deprecated !== null ? (
+          <Text fontSize="sm" color="gray.400" mt={2}>
+            Run by scheduling the change in production
+          </Text>
+        ) : (
+          <>
+            <OrphanedGenerationsField
+              value={orphanedGenerations}
+              onChange={setOrphanedGenerations}
+            />
+            <HStack mt={3}>
+              <Button
+                size="sm"
+                variant="outline"
+                onClick={() => previewMutation.mutate()}
+                loading={previewMutation.isPending}
+                loadingText="Previewing..."
+              >
+                Preview Changes
+              </Button>
+              <Button
+                size="sm"
+                colorPalette="yellow"
+                onClick={() => executeMutation.mutate()}
+                loading={executeMutation.isPending}
+                loadingText="Deploying..."
+              >
+                Deploy to Production
+              </Button>
+            </HStack>
+            {previewMutation.data && (
+              <DeploymentPreviewList previews={[previewMutation.data]} />
+            )}
+          </>
+        ))}
+
+      {error && (
+        <Text color="red.400" mt={2}>
+          {error.message}
+        </Text>
+      )}
+    </Box>
+  );
+}
+
+function statusColor(status: DeployRequest["status"]) {
+  switch (status) {
+    case "Pending":
+      return "yellow";
+    case "Approved":
+      return "blue";
+    case "Rejected":
+      return "red";
+    case "Deployed":
+      return "green";
+  }
+}
@@ -0,0 +1,118 @@
+import { Box, Text } from "@chakra-ui/react";
+import type { DeploymentPreview, ExamDiff, SetDiff } from "../types";
+
+interface DeploymentPreviewListProps {
+  previews: DeploymentPreview[];
+}
+
+/**
+ * Summarizes what seeding each exam changes, for review before seeding
+ */
+export function DeploymentPreviewList({
+  previews,
+}: DeploymentPreviewListProps) {
+  return (
+    <Box mt={4} maxH="300px" overflowY="auto">
+      {previews.map((preview) => (
+        <Box key={preview.examId} mb={3}>
+          <Text fontWeight="bold">
+            {preview.created ? "New exam" : "Exam"} {preview.examId}
+          </Text>
+          <Box fontSize="sm" ps={4}>
+            {examChanges(preview.exam).map((change, i) => (
+              <Text key={i}>{change}</Text>
+            ))}
+            {hasChanges(preview.challenges) && (
+              <Text>
+                Challenge mappings: {setChanges(preview.challenges)}
+              </Text>
+            )}
+            {preview.orphanedGenerations.length > 0 && (
+              <Text color="orange.300">
+                {preview.orphanedGenerations.length} live generations reference
+                removed content
+              </Text>
+            )}
+          </Box>
+          {!isChanged(preview) && (
+            <Text fontSize="sm" color="gray.400">
+              No changes
+            </Text>
+          )}
+        </Box>
+      ))}
+    </Box>
+  );
+}
+
+function isChanged(preview: DeploymentPreview) {
+  return (
+    preview.created ||
+    examChanges(preview.exam).length > 0 ||
+    hasChanges(preview.challenges) ||
+    preview.orphanedGenerations.length > 0
+  );
+}
+
+function hasChanges<T>(diff: SetDiff<T>) {
+  return diff.added.length > 0 || diff.removed.length > 0;
+}
+
+function setChanges<T>(diff: SetDiff<T>) {
+  return `+${diff.added.length} -${diff.removed.length}`;
+}
+
+/**
+ * One line per changed field, config, and question
+ */
+function examChanges(diff: ExamDiff): string[] {
+  const changes: string[] = [];
+  for (const field of [...diff.fields, ...diff.config.fields]) {
+    changes.push(
+      `${field.field}: ${JSON.stringify(field.from)} → ${JSON.stringify(field.to)}`,
+    );
+  }
+  if (hasChanges(diff.prerequisites)) {
+    changes.push(`Prerequisites: ${setChanges(diff.prerequisites)}`);
+  }
+  if (hasChanges(diff.config.tags)) {
+    changes.push(`Tag configs: ${setChanges(diff.config.tags)}`);
+  }
+  if (hasChanges(diff.config.questionSets)) {
+    changes.push(`Question configs: ${setChanges(diff.config.questionSets)}`);
+  }
+
+  for (const questionSet of diff.questionSets) {
+    if (questionSet.change !== "modified") {
+      changes.push(
+        `Question set ${questionSet.id} ${questionSet.change} (${questionSet.questions.length} questions)`,
+      );
+      continue;
+    }
+    if (questionSet.fields.length > 0) {
+      changes.push(
+        `Question set ${questionSet.id}: ${questionSet.fields.map((f) => f.field).join(", ")}`,
+      );
+    }
+    for (const question of questionSet.questions) {
+      if (question.change !== "modified") {
+        changes.push(`Question ${question.id} ${question.change}`);
+        continue;
+      }
+      const parts = question.fields.map((f) => f.field);
+      if (hasChanges(question.tags)) {
+        parts.push(`tags ${setChanges(question.tags)}`);
+      }
+      const answers = question.answers.filter((a) => a.change !== "modified");
+      const modifiedAnswers = question.answers.length - answers.length;
+      if (answers.length > 0 || modifiedAnswers > 0) {
+        const added = answers.filter((a) => a.change === "added").length;
+        parts.push(
+          `answers +${added} -${answers.length - added} ~${modifiedAnswers}`,
+        );
+      }
+      changes.push(`Question ${question.id}: ${parts.join(", ")}`);
+    }
+  }
+  return changes;
+}
@@ -1,6 +1,7 @@
 import { Box, Button, useDisclosure } from "@chakra-ui/react";
 import { CodeXml, Save } from "lucide-react";
 import {
+  ExamConflictError,
   postValidateConfigByExamId,
   putExamById,
   putExamEnvironmentChallenges,
@@ -16,6 +17,11 @@ import { GenerateModal } from "./generate-modal";
 import { deserializeToPrisma } from "../utils/serde";
 import { queryClient } from "../contexts";
 import { toaster } from "./toaster";
+import type {
+  ExamConflict,
+  FeasibilityReport,
+  ValidationIssue,
+} from "../types";
 
 interface EditExamActionsProps {
   exam: ExamCreatorExam;
@@ -38,7 +44,16 @@ export function EditExamActions({
 
   const invalidConfigMutation = useMutation({
     mutationFn: async (examId: string) => {
-      await postValidateConfigByExamId(examId);
+      const validation = await postValidateConfigByExamId(examId);
+      // Highlights the issues in the editor
+      queryClient.setQueryData(["config-validation", examId], validation);
+      const problems = [
+        ...describeValidationErrors(validation.issues),
+        ...describeFeasibilityReport(validation.feasibility),
+      ];
+      if (problems.length) {
+        throw new Error(problems.join("\n"));
+      }
     },
     onError(error) {
       toaster.create({
@@ -88,6 +103,17 @@ export function EditExamActions({
     },
     onError(error: Error) {
       console.error(error);
+      if (error instanceof ExamConflictError) {
+        toaster.create({
+          title: "Exam Saved by Someone Else",
+          description: describeExamConflict(error.conflict, questionSets).join(
+            "\n",
+          ),
+          type: "error",
+          closable: true,
+        });
+        return;
+      }
       toaster.create({
         title: "Error Saving Exam",
         description: error.message || "An error occurred saving exam.",
@@ -153,3 +179,88 @@ export function EditExamActions({
     </Box>
   );
 }
+
+/**
+ * Describes the error issues, one line per issue. Warnings are only shown in the editor.
+ */
+function describeValidationErrors(issues: ValidationIssue[]): string[] {
+  return issues
+    .filter((issue) => issue.severity === "error")
+    .map((issue) => `${issue.path}: ${issue.message}`);
+}
+
+/**
+ * Describes what changed in the saved exam, and in the unsaved exam, one line per change.
+ *
+ * Question sets are numbered by their position in the saved exam, or else the unsaved exam.
+ */
+function describeExamConflict(
+  conflict: ExamConflict,
+  questionSets: ExamEnvironmentQuestionSet[],
+): string[] {
+  const lines = [
+    "Your changes were not saved, as the exam was saved by someone else since you loaded it.",
+  ];
+  if (conflict.configChanged) {
+    lines.push("The config differs.");
+  }
+  if (conflict.prerequisitesChanged) {
+    lines.push("The prerequisites differ.");
+  }
+
+  for (const qs of conflict.questionSets) {
+    const currentIndex = conflict.current.questionSets.findIndex(
+      (c) => c.id === qs.id,
+    );
+    const index =
+      currentIndex === -1
+        ? questionSets.findIndex((s) => s.id === qs.id)
+        : currentIndex;
+    const changes = [
+      qs.current && `${qs.current} by them`,
+      qs.submitted && `${qs.submitted} by you`,
+    ].filter(Boolean);
+    lines.push(
+      `Question set #${index + 1}: ${changes.length ? changes.join(", ") : "differs"}${qs.conflicting ? " (conflicting)" : ""}.`,
+    );
+  }
+
+  return lines;
+}
+
+/**
+ * Describes why a config cannot be generated, one line per problem.
+ */
+function describeFeasibilityReport(report: FeasibilityReport): string[] {
+  const problems = [...report.errors];
+
+  for (const s of report.questionSetConfigs) {
+    const configs = s.questionSetConfigs.map((i) => `#${i + 1}`).join(", ");
+    problems.push(
+      s.questionSetConfigs.length === 1
+        ? `Question set config ${configs} needs ${s.required} ${s.type} question sets, but only ${s.available} have enough questions: short ${s.short} sets (${s.shortQuestions} questions).`
+        : `Question set configs ${configs} compete for ${s.available} ${s.type} question sets, but need ${s.required}: short ${s.short} sets (${s.shortQuestions} questions).`,
+    );
+  }
+  for (const s of report.tagConfigs) {
+    problems.push(
+      `Tag group "${s.group.join(",")}" needs ${s.required} questions, but at most ${s.available} can be chosen: short ${s.short}.`,
+    );
+  }
+  for (const c of report.competingTagConfigs) {
+    problems.push(
+      `Tag groups "${c.groups[0].join(",")}" and "${c.groups[1].join(",")}" compete for the same questions (${c.sharedQuestions} shared): short ${c.short}.`,
+    );
+  }
+
+  if (report.status === "infeasible" && problems.length === 0) {
+    problems.push(
+      "No combination of questions fulfills the question set and tag configs.",
+    );
+  }
+  if (report.status === "unknown") {
+    problems.push("Unable to determine whether the config can be generated.");
+  }
+
+  return problems;
+}
@@ -1,94 +1,59 @@
 import { Box, Heading, Spinner, Table, Text } from "@chakra-ui/react";
-import { useMutation } from "@tanstack/react-query";
-import { getGenerations } from "../utils/fetch";
-import { calculateGenerationMetrics } from "../utils/question";
-import { useEffect } from "react";
+import { useQuery } from "@tanstack/react-query";
+import { getGenerationMetrics } from "../utils/fetch";
+import type { Variability } from "../types";
 import { Tooltip } from "./tooltip";
 
 interface EditExamGenerationVariabilityProps {
   examId: string;
-  generatedExamsStagingData?: Awaited<ReturnType<typeof getGenerations>>;
-  generatedExamsProductionData?: Awaited<ReturnType<typeof getGenerations>>;
 }
 
 export function EditExamGenerationVariability({
   examId,
-  generatedExamsStagingData,
-  generatedExamsProductionData,
 }: EditExamGenerationVariabilityProps) {
-  const stagingMetricsMutation = useMutation({
-    mutationKey: ["generation-metrics", examId, "Staging"],
-    mutationFn: async ({
-      generatedExamsStaging,
-    }: {
-      generatedExamsStaging: typeof generatedExamsStagingData;
-    }) => {
-      const metrics = calculateGenerationMetrics(generatedExamsStaging);
-      return metrics;
-    },
+  const stagingMetricsQuery = useQuery({
+    queryKey: ["generation-metrics", examId, "Staging"],
+    queryFn: () =>
+      getGenerationMetrics({ examId, databaseEnvironment: "Staging" }),
     retry: false,
+    refetchOnWindowFocus: false,
   });
-  const productionMetricsMutation = useMutation({
-    mutationKey: ["generation-metrics", examId, "Production"],
-    mutationFn: async ({
-      generatedExamsProduction,
-    }: {
-      generatedExamsProduction: typeof generatedExamsProductionData;
-    }) => {
-      const metrics = calculateGenerationMetrics(generatedExamsProduction);
-      return metrics;
-    },
+  const productionMetricsQuery = useQuery({
+    queryKey: ["generation-metrics", examId, "Production"],
+    queryFn: () =>
+      getGenerationMetrics({ examId, databaseEnvironment: "Production" }),
     retry: false,
+    refetchOnWindowFocus: false,
   });
 
-  useEffect(() => {
-    if (generatedExamsStagingData) {
-      stagingMetricsMutation.mutate({
-        generatedExamsStaging: generatedExamsStagingData,
-      });
-    }
-  }, [examId, generatedExamsStagingData]);
-
-  useEffect(() => {
-    if (generatedExamsProductionData) {
-      productionMetricsMutation.mutate({
-        generatedExamsProduction: generatedExamsProductionData,
-      });
-    }
-  }, [examId, generatedExamsProductionData]);
-
-  const stagingMetrics = stagingMetricsMutation.data;
-  const productionMetrics = productionMetricsMutation.data;
-  if (
-    stagingMetricsMutation.isPending ||
-    productionMetricsMutation.isPending ||
-    stagingMetrics === undefined ||
-    productionMetrics === undefined
-  ) {
+  if (stagingMetricsQuery.isError || productionMetricsQuery.isError) {
+    console.error(stagingMetricsQuery.error);
+    console.error(productionMetricsQuery.error);
     return (
       <>
         <Heading size="sm" mt={6} mb={2}>
           Exam Generations
         </Heading>
-        <Text mb={2}>This is the analysis of the exam generations:</Text>
-        <Spinner size="sm" color="teal.400" />
+        <Text color="gray.300" mb={2}>
+          This is the analysis of the exam generations:
+        </Text>
+        <Text color="red.400" fontWeight="bold">
+          Error loading exam generations. See browser console for details.
+        </Text>
       </>
     );
   }
-  if (stagingMetricsMutation.isError || productionMetricsMutation.isError) {
-    console.error(stagingMetricsMutation.error);
-    console.error(productionMetricsMutation.error);
+
+  const stagingMetrics = stagingMetricsQuery.data;
+  const productionMetrics = productionMetricsQuery.data;
+  if (stagingMetrics === undefined || productionMetrics === undefined) {
     return (
       <>
         <Heading size="sm" mt={6} mb={2}>
           Exam Generations
         </Heading>
-        <Text color="gray.300" mb={2}>
-          This is the analysis of the exam generations:
-        </Text>
-        <Text color="red.400" fontWeight="bold">
-          Error loading exam generations. See browser console for details.
-        </Text>
+        <Text mb={2}>This is the analysis of the exam generations:</Text>
+        <Spinner size="sm" color="teal.400" />
       </>
     );
   }
@@ -131,47 +96,54 @@ export function EditExamGenerationVariability({
               <Tooltip content="Overall question variability across all live generations. (sum of variabilities) / (number of comparisons)">
                 <Table.Cell fontWeight="bold">Question Total</Table.Cell>
               </Tooltip>
-              <Table.Cell>{stagingMetrics.questionVariability}</Table.Cell>
-              <Table.Cell>{productionMetrics.questionVariability}</Table.Cell>
+              <Table.Cell>{formatVariability(stagingMetrics.questionVariability, "mean")}</Table.Cell>
+              <Table.Cell>{formatVariability(productionMetrics.questionVariability, "mean")}</Table.Cell>
             </Table.Row>
             <Table.Row>
               <Tooltip content="Maximum question variability found between any two live generations">
                 <Table.Cell fontWeight="bold">Question Max</Table.Cell>
               </Tooltip>
-              <Table.Cell>{stagingMetrics.questionVariabilityMax}</Table.Cell>
+              <Table.Cell>{formatVariability(stagingMetrics.questionVariability, "max")}</Table.Cell>
               <Table.Cell>
-                {productionMetrics.questionVariabilityMax}
+                {formatVariability(productionMetrics.questionVariability, "max")}
               </Table.Cell>
             </Table.Row>
             <Table.Row>
               <Tooltip content="Minimum question variability found between any two live generations">
                 <Table.Cell fontWeight="bold">Question Min</Table.Cell>
               </Tooltip>
-              <Table.Cell>{stagingMetrics.questionVariabilityMin}</Table.Cell>
+              <Table.Cell>{formatVariability(stagingMetrics.questionVariability, "min")}</Table.Cell>
               <Table.Cell>
-                {productionMetrics.questionVariabilityMin}
+                {formatVariability(productionMetrics.questionVariability, "min")}
               </Table.Cell>
             </Table.Row>
             <Table.Row>
               <Tooltip content="Overall answer variability across all live generations. (sum of variabilities) / (number of comparisons)">
                 <Table.Cell fontWeight="bold">Answer Total</Table.Cell>
               </Tooltip>
-              <Table.Cell>{stagingMetrics.answerVariability}</Table.Cell>
-              <Table.Cell>{productionMetrics.answerVariability}</Table.Cell>
+              <Table.Cell>{formatVariability(stagingMetrics.answerVariability, "mean")}</Table.Cell>
+              <Table.Cell>{formatVariability(productionMetrics.answerVariability, "mean")}</Table.Cell>
             </Table.Row>
             <Table.Row>
               <Tooltip content="Maximum answer variability found between any two live generations">
                 <Table.Cell fontWeight="bold">Answer Max</Table.Cell>
               </Tooltip>
-              <Table.Cell>{stagingMetrics.answerVariabilityMax}</Table.Cell>
-              <Table.Cell>{productionMetrics.answerVariabilityMax}</Table.Cell>
+              <Table.Cell>{formatVariability(stagingMetrics.answerVariability, "max")}</Table.Cell>
+              <Table.Cell>{formatVariability(productionMetrics.answerVariability, "max")}</Table.Cell>
             </Table.Row>
             <Table.Row>
               <Tooltip content="Minimum answer variability found between any two live generations">
                 <Table.Cell fontWeight="bold">Answer Min</Table.Cell>
               </Tooltip>
-              <Table.Cell>{stagingMetrics.answerVariabilityMin}</Table.Cell>
-              <Table.Cell>{productionMetrics.answerVariabilityMin}</Table.Cell>
+              <Table.Cell>{formatVariability(stagingMetrics.answerVariability, "min")}</Table.Cell>
+              <Table.Cell>{formatVariability(productionMetrics.answerVariability, "min")}</Table.Cell>
+            </Table.Row>
+            <Table.Row>
+              <Tooltip content="Non-deprecated questions which appear in no live generation">
+                <Table.Cell fontWeight="bold">Unused Questions</Table.Cell>
+              </Tooltip>
+              <Table.Cell>{stagingMetrics.unusedQuestions.length}</Table.Cell>
+              <Table.Cell>{productionMetrics.unusedQuestions.length}</Table.Cell>
             </Table.Row>
           </Table.Body>
         </Table.Root>
@@ -179,3 +151,10 @@ export function EditExamGenerationVariability({
     </>
   );
 }
+
+function formatVariability(
+  variability: Variability | null,
+  stat: keyof Variability,
+) {
+  return variability ? variability[stat].toFixed(3) : "-";
+}
@@ -10,6 +10,7 @@ import {
 import { useNavigate } from "@tanstack/react-router";
 import { editExamRoute } from "../pages/edit-exam";
 import type { ExamCreatorExam } from "@prisma/client";
+import type { EnvironmentDrift, ExamDrift } from "../types";
 import { Tooltip } from "./tooltip";
 import { UsersOnPageAvatars } from "./users-on-page-avatars";
 
@@ -19,6 +20,8 @@ interface ExamCardProps {
   onSelectionChange?: (examId: string, selected: boolean) => void;
   selectionMode?: boolean;
   databaseEnvironments: ("Staging" | "Production")[];
+  /** How the seeded exams compare to this exam */
+  drift?: ExamDrift;
 }
 
 export function ExamCard({
@@ -27,6 +30,7 @@ export function ExamCard({
   onSelectionChange,
   selectionMode = false,
   databaseEnvironments,
+  drift,
 }: ExamCardProps) {
   const navigate = useNavigate();
 
@@ -111,23 +115,39 @@ export function ExamCard({
           <UsersOnPageAvatars path={`/exams/${exam.id}`} />
         </Card.Body>
         <Card.Footer padding="0" justifyContent={"space-evenly"}>
-          {databaseEnvironments.map((env) => (
-            <Tooltip
-              content={`This exam is seeded in the ${env} database`}
-              key={env}
-            >
-              <Badge
-                key={env}
-                colorPalette={env === "Production" ? "green" : "blue"}
-                minW="90px"
-                justifyContent={"center"}
-              >
-                {env}
-              </Badge>
-            </Tooltip>
-          ))}
+          {databaseEnvironments.map((env) => {
+            const envDrift =
+              env === "Production" ? drift?.production : drift?.staging;
+            const isDrifted = !!envDrift && envDrift.status !== "identical";
+            return (
+              <Tooltip content={seededDescription(env, envDrift)} key={env}>
+                <Badge
+                  key={env}
+                  colorPalette={
+                    envDrift?.status === "diverged"
+                      ? "orange"
+                      : env === "Production"
+                        ? "green"
+                        : "blue"
+                  }
+                  minW="90px"
+                  justifyContent={"center"}
+                >
+                  {isDrifted ? `${env} · ${envDrift.status}` : env}
+                </Badge>
+              </Tooltip>
+            );
+          })}
         </Card.Footer>
       </Card.Root>
     </Button>
   );
 }
+
+function seededDescription(env: string, drift?: EnvironmentDrift) {
+  const seeded = `This exam is seeded in the ${env} database`;
+  if (!drift || drift.status === "identical" || drift.status === "missing") {
+    return seeded;
+  }
+  return `${seeded} at version ${drift.version}, which is ${drift.status}. Differs in: ${drift.differences.join(", ")}`;
+}
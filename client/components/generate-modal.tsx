@@ -9,11 +9,24 @@ import {
   Stack,
   Progress,
   NativeSelect,
+  Checkbox,
 } from "@chakra-ui/react";
 import { useEffect, useRef, useState } from "react";
-import { putGenerateExam } from "../utils/fetch";
+import {
+  getGenerationJobProgress,
+  postGenerationJob,
+  putCancelGenerationJob,
+} from "../utils/fetch";
 import { queryClient } from "../contexts";
 import { toaster } from "./toaster";
+import type {
+  ExposureDistribution,
+  ExposureSummary,
+  GenerationJob,
+} from "../types";
+
+/** Must match the server's limit on generation jobs */
+const MAX_GENERATIONS = 5000;
 
 interface GenerateModalProps {
   open: boolean;
@@ -24,6 +37,11 @@ interface GenerateModalProps {
 export function GenerateModal({ open, onClose, examId }: GenerateModalProps) {
   const [val, setVal] = useState("");
   const [count, setCount] = useState<number>(1);
+  const [seed, setSeed] = useState("");
+  const [balanceExposure, setBalanceExposure] = useState(false);
+  const [exposure, setExposure] = useState<ExposureDistribution | null>(
+    null,
+  );
   const [isGenerating, setIsGenerating] = useState(false);
   const [error, setError] = useState<string | null>(null);
   const [generationAlgorithmErrors, setGenerationAlgorithmErrors] = useState<
@@ -34,6 +52,7 @@ export function GenerateModal({ open, onClose, examId }: GenerateModalProps) {
     "Staging" | "Production"
   >("Staging");
   const abortRef = useRef<AbortController | null>(null);
+  const [job, setJob] = useState<GenerationJob | null>(null);
 
   function handleInputChange(e: React.ChangeEvent<HTMLInputElement>) {
     setVal(e.target.value);
@@ -47,6 +66,10 @@ export function GenerateModal({ open, onClose, examId }: GenerateModalProps) {
       setGenerationAlgorithmErrors([]);
       setIsGenerating(false);
       setCount(1);
+      setSeed("");
+      setBalanceExposure(false);
+      setExposure(null);
+      setJob(null);
       abortRef.current = new AbortController();
     } else {
       abortRef.current?.abort();
@@ -56,7 +79,7 @@ export function GenerateModal({ open, onClose, examId }: GenerateModalProps) {
 
   function clampCount(n: number) {
     if (Number.isNaN(n)) return 1;
-    return Math.max(1, Math.min(100, Math.floor(n)));
+    return Math.max(1, Math.min(MAX_GENERATIONS, Math.floor(n)));
   }
 
   // Helper: iterate JSON NL from ReadableStream
@@ -100,45 +123,55 @@ export function GenerateModal({ open, onClose, examId }: GenerateModalProps) {
     setIsGenerating(true);
     setError(null);
     setGenerationAlgorithmErrors([]);
+    setExposure(null);
 
     const ac = new AbortController();
     abortRef.current = ac;
 
     try {
-      const stream = await putGenerateExam({
+      const queuedJob = await postGenerationJob({
         examId,
         count,
         databaseEnvironment,
+        seed: seed === "" ? undefined : Number(seed),
+        balanceExposure,
       });
-      let latest = 0;
-      const genErrors: string[] = [];
+      setJob(queuedJob);
+      // The job carries on in the background if the stream is closed
+      const stream = await getGenerationJobProgress(queuedJob);
+      let latest = queuedJob;
       for await (const msg of iterateJsonLines(stream)) {
-        // Server sends { count: i (1-based), examId, error?: string | null }
-        const soFar = msg.count;
-        latest = soFar;
-        setProgress(soFar);
-        const error = msg.error;
-        if (error) {
-          genErrors.push(error);
+        if (ac.signal.aborted) {
+          break;
+        }
+        latest = msg as GenerationJob;
+        setJob(latest);
+        setProgress(latest.generated);
+        if (latest.exposure) {
+          setExposure(latest.exposure);
         }
       }
-      // Ensure we mark complete if stream ended without last line
-      // Stream can timeout before all generations are done
-      const isAllExamsGenerated = latest >= count;
 
-      if (!isAllExamsGenerated) {
-        if (latest === 0) {
-          const uniqueErrors = Array.from(new Set(genErrors));
-          setGenerationAlgorithmErrors(uniqueErrors);
+      if (latest.status === "Failed") {
+        if (latest.error) {
+          setGenerationAlgorithmErrors([latest.error]);
         }
         toaster.create({
-          title: `Generation Timeout in ${databaseEnvironment}`,
-          description: `Generation process timed out before all exams could be generated. Please try again.`,
+          title: `Generation Failed in ${databaseEnvironment}`,
+          description: `Generated ${latest.generated} of ${latest.count} exams before generation failed.`,
           type: "warning",
           duration: 7000,
           closable: true,
         });
-      } else {
+      } else if (latest.status === "Cancelled") {
+        toaster.create({
+          title: `Generation Cancelled in ${databaseEnvironment}`,
+          description: `Generated ${latest.generated} of ${latest.count} exams before the job was cancelled.`,
+          type: "info",
+          duration: 5000,
+          closable: true,
+        });
+      } else if (latest.status === "Completed") {
         toaster.create({
           title: `Generated Exams to ${databaseEnvironment}`,
           description: `All generated exams have been seeded to the database.`,
@@ -150,6 +183,9 @@ export function GenerateModal({ open, onClose, examId }: GenerateModalProps) {
       await queryClient.refetchQueries({
         queryKey: ["generated-exams", examId, databaseEnvironment],
       });
+      await queryClient.refetchQueries({
+        queryKey: ["generation-metrics", examId, databaseEnvironment],
+      });
     } catch (e: unknown) {
       console.error(e);
       if (e instanceof DOMException && e.name === "AbortError") {
@@ -217,6 +253,12 @@ export function GenerateModal({ open, onClose, examId }: GenerateModalProps) {
               <Text fontSize="xs" color="gray.400">
                 {progress ?? 0}/{count}
               </Text>
+              {isGenerating && (
+                <Text fontSize="xs" color="gray.400">
+                  Generation continues in the background if this dialog is
+                  closed.
+                </Text>
+              )}
             </Stack>
           </Stack>
           {error && (
@@ -236,6 +278,19 @@ export function GenerateModal({ open, onClose, examId }: GenerateModalProps) {
               ))}
             </Stack>
           )}
+          {exposure && (
+            <Stack mt={3} gap={1}>
+              <Text color="gray.300">
+                Exposure across {exposure.generations} generations:
+              </Text>
+              <Text color="gray.400" fontSize="sm">
+                Questions: {describeExposure(exposure.questions)}
+              </Text>
+              <Text color="gray.400" fontSize="sm">
+                Answers: {describeExposure(exposure.answers)}
+              </Text>
+            </Stack>
+          )}
           <Field.Root disabled={isGenerating}>
             <Field.Label>Database Environment</Field.Label>
             <NativeSelect.Root>
@@ -271,7 +326,7 @@ export function GenerateModal({ open, onClose, examId }: GenerateModalProps) {
             <Field.Label>Number of Generations</Field.Label>
             <NumberInput.Root
               min={1}
-              max={100}
+              max={MAX_GENERATIONS}
               value={count.toString()}
               onValueChange={(v) => {
                 const next = clampCount(
@@ -287,9 +342,39 @@ export function GenerateModal({ open, onClose, examId }: GenerateModalProps) {
               <NumberInput.Input />
             </NumberInput.Root>
             <Field.HelperText color="#c4c8d0">
-              Enter a value between 1 and 100
+              Enter a value between 1 and {MAX_GENERATIONS}
             </Field.HelperText>
           </Field.Root>
+
+          <Field.Root
+            mt={4}
+            disabled={isGenerating}
+            invalid={!isValidSeed(seed)}
+          >
+            <Field.Label>Seed (optional)</Field.Label>
+            <Input
+              type="text"
+              inputMode="numeric"
+              value={seed}
+              onChange={(e) => setSeed(e.target.value.trim())}
+            />
+            <Field.HelperText color="#c4c8d0">
+              Generations with the same exam and seed are identical. Leave empty
+              for random seeds
+            </Field.HelperText>
+          </Field.Root>
+
+          <Checkbox.Root
+            mt={4}
+            checked={balanceExposure}
+            onCheckedChange={(d) => setBalanceExposure(!!d.checked)}
+            disabled={isGenerating}
+          >
+            <Checkbox.Control />
+            <Checkbox.Label>
+              Balance exposure against existing generations
+            </Checkbox.Label>
+          </Checkbox.Root>
         </DialogBody>
 
         <Dialog.Footer>
@@ -301,10 +386,26 @@ export function GenerateModal({ open, onClose, examId }: GenerateModalProps) {
               setCount(1);
               onClose();
             }}
-            disabled={isGenerating}
           >
             Close
           </Button>
+          {isGenerating && job && (
+            <Button
+              variant={"outline"}
+              colorPalette="red"
+              mr={3}
+              onClick={async () => {
+                try {
+                  await putCancelGenerationJob(job.id);
+                } catch (e) {
+                  console.error(e);
+                  setError("Unable to cancel generation job - See console.");
+                }
+              }}
+            >
+              Cancel Job
+            </Button>
+          )}
           <Button
             variant={"outline"}
             colorPalette="yellow"
@@ -317,7 +418,8 @@ export function GenerateModal({ open, onClose, examId }: GenerateModalProps) {
               !examId ||
               isGenerating ||
               count < 1 ||
-              count > 100
+              count > MAX_GENERATIONS ||
+              !isValidSeed(seed)
             }
             loadingText="Generating..."
             loading={isGenerating}
@@ -329,3 +431,15 @@ export function GenerateModal({ open, onClose, examId }: GenerateModalProps) {
     </Dialog.Root>
   );
 }
+
+/**
+ * Seeds are unsigned 32-bit integers
+ */
+function isValidSeed(seed: string) {
+  if (seed === "") return true;
+  return /^\d+$/.test(seed) && Number(seed) <= 0xffffffff;
+}
+
+function describeExposure({ min, max, mean, unexposed }: ExposureSummary) {
+  return `${min}-${max} per item (mean ${mean.toFixed(1)}), ${unexposed} never used`;
+}
@@ -16,6 +16,7 @@ import { QuestionAccordion } from "./accordian";
 import { UseQueryResult } from "@tanstack/react-query";
 import { MultipleChoiceForm } from "./multiple-choice-form";
 import { DialogueForm } from "./dialogue-form";
+import type { ValidationIssue } from "../types";
 
 type QuestionFormProps = {
   searchIds: string[];
@@ -29,6 +30,8 @@ type QuestionFormProps = {
     ExamEnvironmentGeneratedExam[],
     Error
   >;
+  /** Issues from the last validation of the saved exam */
+  validationIssues: ValidationIssue[];
 };
 
 export function QuestionForm({
@@ -37,6 +40,7 @@ export function QuestionForm({
   setQuestionSets,
   generatedExamsStagingQuery,
   generatedExamsProductionQuery,
+  validationIssues,
 }: QuestionFormProps) {
   const isLoading =
     generatedExamsStagingQuery.isFetching ||
@@ -76,6 +80,10 @@ export function QuestionForm({
             return true;
           })
           .map((qt) => {
+            const issues = questionSetIssues(
+              validationIssues,
+              questionSets.indexOf(qt),
+            );
             switch (qt.type) {
               case "MultipleChoice":
                 const question = qt.questions.at(0);
@@ -101,6 +109,7 @@ export function QuestionForm({
                     key={qt.id}
                     title={`Question Set ${qt.id}`}
                     subtitle={question.text}
+                    issues={issues}
                     {...questionBorderStyle}
                   >
                     <MultipleChoiceForm
@@ -135,6 +144,7 @@ export function QuestionForm({
                     key={qt.id}
                     title={`Dialogue Question Set ${qt.id}`}
                     subtitle={`${qt.context ?? "none"}`}
+                    issues={issues}
                     {...setBorderStyle}
                   >
                     <DialogueForm
@@ -181,3 +191,16 @@ export function QuestionForm({
     </Box>
   );
 }
+
+/**
+ * Issues located in the question set at `index`, including its questions and answers
+ */
+function questionSetIssues(issues: ValidationIssue[], index: number) {
+  const path = `$.questionSets[${index}]`;
+  return issues.filter(
+    (issue) =>
+      issue.path === path ||
+      issue.path.startsWith(`${path}.`) ||
+      issue.path.startsWith(`${path}[`),
+  );
+}
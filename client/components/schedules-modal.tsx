@@ -0,0 +1,363 @@
+import {
+  Badge,
+  Box,
+  Button,
+  Dialog,
+  Field,
+  Flex,
+  HStack,
+  Input,
+  NativeSelect,
+  Spinner,
+  Text,
+} from "@chakra-ui/react";
+import { useMutation, useQuery } from "@tanstack/react-query";
+import { useState } from "react";
+import type { Schedule, ScheduleAction } from "../types";
+import {
+  getDeployRequests,
+  getSchedules,
+  postDeployRequest,
+  postSchedule,
+  putCancelSchedule,
+  type OrphanedGenerationsAction,
+} from "../utils/fetch";
+import { OrphanedGenerationsField } from "./seed-modal";
+
+interface SchedulesModalProps {
+  open: boolean;
+  onClose: () => void;
+  /** Exam names by id, for choosing and labelling exams */
+  examNames: Map<string, string>;
+}
+
+/**
+ * Lists scheduled seeds and deprecations, and schedules new ones
+ */
+export function SchedulesModal({
+  open,
+  onClose,
+  examNames,
+}: SchedulesModalProps) {
+  const schedulesQuery = useQuery({
+    queryKey: ["schedules"],
+    enabled: open,
+    queryFn: () => getSchedules(),
+    retry: false,
+    refetchOnWindowFocus: false,
+  });
+
+  return (
+    <Dialog.Root open={open} onOpenChange={onClose} size="lg">
+      <Dialog.Content backgroundColor={"gray.700"} color={"white"}>
+        <Dialog.Header>Schedules</Dialog.Header>
+        <Dialog.CloseTrigger />
+        <Dialog.Body maxH="70vh" overflowY="auto">
+          <NewScheduleForm
+            examNames={examNames}
+            onScheduled={() => schedulesQuery.refetch()}
+          />
+          <Box mt={6}>
+            {schedulesQuery.isPending ? (
+              <Spinner />
+            ) : schedulesQuery.isError ? (
+              <Text color="red.400">{schedulesQuery.error.message}</Text>
+            ) : schedulesQuery.data.length === 0 ? (
+              <Text color="gray.400">No schedules</Text>
+            ) : (
+              schedulesQuery.data.map((schedule) => (
+                <ScheduleItem
+                  key={schedule.id}
+                  schedule={schedule}
+                  examName={examNames.get(schedule.examId)}
+                  onCancelled={() => schedulesQuery.refetch()}
+                />
+              ))
+            )}
+          </Box>
+        </Dialog.Body>
+        <Dialog.Footer>
+          <Button
+            variant="outline"
+            onClick={() => schedulesQuery.refetch()}
+            loading={schedulesQuery.isFetching}
+          >
+            Refresh
+          </Button>
+          <Button colorPalette="blue" onClick={onClose}>
+            Close
+          </Button>
+        </Dialog.Footer>
+      </Dialog.Content>
+    </Dialog.Root>
+  );
+}
+
+interface NewScheduleFormProps {
+  examNames: Map<string, string>;
+  onScheduled: () => void;
+}
+
+function NewScheduleForm({ examNames, onScheduled }: NewScheduleFormProps) {
+  const [examId, setExamId] = useState("");
+  const [databaseEnvironment, setDatabaseEnvironment] = useState<
+    "Staging" | "Production"
+  >("Staging");
+  const [action, setAction] = useState<ScheduleAction>("Seed");
+  const [runAt, setRunAt] = useState("");
+  const [orphanedGenerations, setOrphanedGenerations] =
+    useState<OrphanedGenerationsAction>("block");
+  const [deployRequestId, setDeployRequestId] = useState("");
+
+  // Every change to production needs an approved deploy request for the change
+  const needsDeployRequest = databaseEnvironment === "Production";
+  const deprecated =
+    action === "Seed" ? null : action === "Deprecate" ? true : false;
+  const deployRequestsQuery = useQuery({
+    queryKey: ["deploy-requests"],
+    enabled: needsDeployRequest,
+    queryFn: () => getDeployRequests(),
+    retry: false,
+    refetchOnWindowFocus: false,
+  });
+  const deployRequests = (deployRequestsQuery.data ?? []).filter(
+    (d) =>
+      d.examId === examId &&
+      d.status === "Approved" &&
+      d.deprecated === deprecated,
+  );
+
+  const requestMutation = useMutation({
+    mutationFn: () => postDeployRequest(examId, null, deprecated),
+    onSuccess() {
+      deployRequestsQuery.refetch();
+    },
+  });
+
+  const scheduleMutation = useMutation({
+    mutationFn: () =>
+      postSchedule({
+        examId,
+        databaseEnvironment,
+        action,
+        runAt: new Date(runAt),
+        orphanedGenerations,
+        deployRequestId: needsDeployRequest ? deployRequestId : null,
+      }),
+    onSuccess() {
+      setRunAt("");
+      onScheduled();
+    },
+  });
+
+  return (
+    <Box p={3} borderWidth={1} borderRadius="md" borderColor="gray.600">
+      <HStack gap={3} align="flex-end">
+        <Field.Root>
+          <Field.Label>Exam</Field.Label>
+          <NativeSelect.Root size="sm">
+            <NativeSelect.Field
+              value={examId}
+              onChange={(e) => setExamId(e.target.value)}
+            >
+              <option value="">Select an exam</option>
+              {[...examNames].map(([id, name]) => (
+                <option key={id} value={id}>
+                  {name}
+                </option>
+              ))}
+            </NativeSelect.Field>
+            <NativeSelect.Indicator />
+          </NativeSelect.Root>
+        </Field.Root>
+        <Field.Root>
+          <Field.Label>Database</Field.Label>
+          <NativeSelect.Root size="sm">
+            <NativeSelect.Field
+              value={databaseEnvironment}
+              onChange={(e) =>
+                setDatabaseEnvironment(
+                  e.target.value as "Staging" | "Production",
+                )
+              }
+            >
+              <option value="Staging">Staging</option>
+              <option value="Production">Production</option>
+            </NativeSelect.Field>
+            <NativeSelect.Indicator />
+          </NativeSelect.Root>
+        </Field.Root>
+        <Field.Root>
+          <Field.Label>Action</Field.Label>
+          <NativeSelect.Root size="sm">
+            <NativeSelect.Field
+              value={action}
+              onChange={(e) => setAction(e.target.value as ScheduleAction)}
+            >
+              <option value="Seed">Seed</option>
+              <option value="Deprecate">Deprecate</option>
+              <option value="Undeprecate">Undeprecate</option>
+            </NativeSelect.Field>
+            <NativeSelect.Indicator />
+          </NativeSelect.Root>
+        </Field.Root>
+        <Field.Root>
+          <Field.Label>Run at</Field.Label>
+          <Input
+            size="sm"
+            type="datetime-local"
+            value={runAt}
+            onChange={(e) => setRunAt(e.target.value)}
+          />
+        </Field.Root>
+      </HStack>
+
+      {needsDeployRequest && (
+        <Field.Root mt={4}>
+          <Field.Label>Deploy Request</Field.Label>
+          <NativeSelect.Root size="sm">
+            <NativeSelect.Field
+              value={deployRequestId}
+              onChange={(e) => setDeployRequestId(e.target.value)}
+            >
+              <option value="">Select a deploy request</option>
+              {deployRequests.map((d) => (
+                <option key={d.id} value={d.id}>
+                  Revision {d.revision} by {d.requesterName}
+                </option>
+              ))}
+            </NativeSelect.Field>
+            <NativeSelect.Indicator />
+          </NativeSelect.Root>
+          <Field.HelperText color="#c4c8d0">
+            Only approved requests can be scheduled. The request must still be
+            approved when the schedule is run
+          </Field.HelperText>
+          {deprecated !== null && (
+            <Button
+              mt={2}
+              size="sm"
+              variant="outline"
+              onClick={() => requestMutation.mutate()}
+              disabled={!examId}
+              loading={requestMutation.isPending}
+              loadingText="Requesting..."
+            >
+              Request Approval to {action}
+            </Button>
+          )}
+          {requestMutation.isSuccess && (
+            <Text fontSize="sm" color="gray.400" mt={2}>
+              Requested. Another user must approve it in Deploy Requests
+            </Text>
+          )}
+          {requestMutation.isError && (
+            <Text color="red.400" mt={2}>
+              {requestMutation.error.message}
+            </Text>
+          )}
+        </Field.Root>
+      )}
+
+      {action === "Seed" && (
+        <OrphanedGenerationsField
+          value={orphanedGenerations}
+          onChange={setOrphanedGenerations}
+        />
+      )}
+
+      <Button
+        mt={4}
+        size="sm"
+        colorPalette="teal"
+        onClick={() => scheduleMutation.mutate()}
+        disabled={
+          !examId || !runAt || (needsDeployRequest && !deployRequestId)
+        }
+        loading={scheduleMutation.isPending}
+        loadingText="Scheduling..."
+      >
+        Schedule
+      </Button>
+      {scheduleMutation.isError && (
+        <Text color="red.400" mt={2}>
+          {scheduleMutation.error.message}
+        </Text>
+      )}
+    </Box>
+  );
+}
+
+interface ScheduleItemProps {
+  schedule: Schedule;
+  examName?: string;
+  onCancelled: () => void;
+}
+
+function ScheduleItem({ schedule, examName, onCancelled }: ScheduleItemProps) {
+  const cancelMutation = useMutation({
+    mutationFn: () => putCancelSchedule(schedule.id),
+    onSuccess() {
+      onCancelled();
+    },
+  });
+
+  return (
+    <Box mb={3} p={3} borderWidth={1} borderRadius="md" borderColor="gray.600">
+      <Flex justify="space-between" align="center">
+        <Text fontWeight="bold">
+          {schedule.action} {examName ?? schedule.examId} in{" "}
+          {schedule.databaseEnvironment}
+        </Text>
+        <Badge colorPalette={statusColor(schedule.status)}>
+          {schedule.status}
+        </Badge>
+      </Flex>
+      <Box fontSize="sm" ps={4} mt={2}>
+        <Text>
+          {schedule.status === "Scheduled" && schedule.attempts > 0
+            ? "Retrying at"
+            : "Runs at"}{" "}
+          {schedule.runAt.toLocaleString()}
+        </Text>
+        <Text>
+          Scheduled by {schedule.createdBy}, {schedule.attempts} attempts so
+          far
+        </Text>
+        {schedule.error && <Text color="red.400">{schedule.error}</Text>}
+      </Box>
+      {schedule.status === "Scheduled" && (
+        <Button
+          mt={2}
+          size="sm"
+          variant="outline"
+          colorPalette="red"
+          onClick={() => cancelMutation.mutate()}
+          loading={cancelMutation.isPending}
+        >
+          Cancel
+        </Button>
+      )}
+      {cancelMutation.isError && (
+        <Text color="red.400" mt={2}>
+          {cancelMutation.error.message}
+        </Text>
+      )}
+    </Box>
+  );
+}
+
+function statusColor(status: Schedule["status"]) {
+  switch (status) {
+    case "Scheduled":
+      return "blue";
+    case "Running":
+      return "yellow";
+    case "Completed":
+      return "green";
+    case "Failed":
+      return "red";
+    case "Cancelled":
+      return "gray";
+  }
+}
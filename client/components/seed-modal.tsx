@@ -5,29 +5,56 @@ import {
   Input,
   Dialog,
   DialogCloseTrigger,
+  NativeSelect,
 } from "@chakra-ui/react";
-import { UseMutationResult } from "@tanstack/react-query";
+import { UseMutationResult, useMutation } from "@tanstack/react-query";
 import { useState } from "react";
+import {
+  putExamByIdToProduction,
+  putExamByIdToStaging,
+  type OrphanedGenerationsAction,
+} from "../utils/fetch";
+import type { DeployRequest, DeploymentPreview } from "../types";
+import { DeploymentPreviewList } from "./deployment-preview";
+
+export interface SeedExams {
+  examIds: string[];
+  orphanedGenerations: OrphanedGenerationsAction;
+}
 
 interface SeedStagingModalProps {
   open: boolean;
+  examIds: string[];
   onClose: () => void;
-  handleSeedSelectedToStaging: () => void;
+  handleSeedSelectedToStaging: (
+    orphanedGenerations: OrphanedGenerationsAction,
+  ) => void;
   seedExamToStagingMutation: UseMutationResult<
-    Response[],
+    DeploymentPreview[],
     Error,
-    string[],
+    SeedExams,
     unknown
   >;
 }
 
 export function SeedStagingModal({
   open,
+  examIds,
   onClose,
   handleSeedSelectedToStaging,
   seedExamToStagingMutation,
 }: SeedStagingModalProps) {
   const [val, setVal] = useState("");
+  const [orphanedGenerations, setOrphanedGenerations] =
+    useState<OrphanedGenerationsAction>("block");
+  const previewMutation = useMutation({
+    mutationFn: () =>
+      Promise.all(
+        examIds.map((id) =>
+          putExamByIdToStaging(id, orphanedGenerations, true),
+        ),
+      ),
+  });
 
   function handleInputChange(e: React.ChangeEvent<HTMLInputElement>) {
     setVal(e.target.value);
@@ -38,6 +65,7 @@ export function SeedStagingModal({
       open={open}
       onOpenChange={() => {
         setVal("");
+        previewMutation.reset();
         onClose();
       }}
     >
@@ -61,6 +89,19 @@ export function SeedStagingModal({
               Type "seed staging" to confirm
             </Field.HelperText>
           </Field.Root>
+
+          <OrphanedGenerationsField
+            value={orphanedGenerations}
+            onChange={setOrphanedGenerations}
+          />
+
+          <PreviewSection previewMutation={previewMutation} />
+
+          {seedExamToStagingMutation.isError && (
+            <Text color="red.400" mt={4}>
+              {seedExamToStagingMutation.error.message}
+            </Text>
+          )}
         </Dialog.Body>
 
         <Dialog.Footer>
@@ -79,7 +120,7 @@ export function SeedStagingModal({
             colorPalette="yellow"
             onClick={() => {
               setVal("");
-              handleSeedSelectedToStaging();
+              handleSeedSelectedToStaging(orphanedGenerations);
             }}
             disabled={val !== "seed staging"}
             loadingText="Seeding..."
@@ -93,35 +134,54 @@ export function SeedStagingModal({
   );
 }
 
+export interface RequestDeploys {
+  examIds: string[];
+  comment: string | null;
+}
+
 interface SeedProductionModalProps {
   open: boolean;
+  examIds: string[];
   onClose: () => void;
-  handleSeedSelectedToProduction: () => void;
-  seedExamToProductionMutation: UseMutationResult<
-    Response[],
+  handleRequestSelectedToProduction: (comment: string | null) => void;
+  requestProductionDeployMutation: UseMutationResult<
+    DeployRequest[],
     Error,
-    string[],
+    RequestDeploys,
     unknown
   >;
 }
 
+/**
+ * Requests the selected exams' latest revisions be deployed to production.
+ *
+ * Another user must approve each request before it can be deployed.
+ */
 export function SeedProductionModal({
   open,
+  examIds,
   onClose,
-  handleSeedSelectedToProduction,
-  seedExamToProductionMutation,
+  handleRequestSelectedToProduction,
+  requestProductionDeployMutation,
 }: SeedProductionModalProps) {
-  const [val, setVal] = useState("");
+  const [comment, setComment] = useState("");
+  const previewMutation = useMutation({
+    mutationFn: () =>
+      Promise.all(
+        examIds.map((id) => putExamByIdToProduction(id, "block", true)),
+      ),
+  });
 
   function handleInputChange(e: React.ChangeEvent<HTMLInputElement>) {
-    setVal(e.target.value);
+    setComment(e.target.value);
   }
 
   return (
     <Dialog.Root
       open={open}
       onOpenChange={() => {
-        setVal("");
+        setComment("");
+        previewMutation.reset();
         onClose();
       }}
     >
@@ -129,22 +189,26 @@ export function SeedProductionModal({
         <Dialog.Header>Modal Title</Dialog.Header>
         <Dialog.CloseTrigger />
         <Dialog.Body>
-          {seedExamToProductionMutation.isPending ? (
-            <Text>Seeding exams to production in progress...</Text>
-          ) : (
-            <Text>
-              This is a potentially destructive action. Are you sure you want to
-              seed the production database with the selected exams?
-            </Text>
-          )}
+          <Text>
+            Deploying to production must be approved by another user. Request
+            the latest revision of the selected exams be deployed?
+          </Text>
 
-          <Field.Root invalid={val !== "seed production"} mt={4}>
-            <Field.Label>Confirmation</Field.Label>
-            <Input type="text" value={val} onChange={handleInputChange} />
+          <Field.Root mt={4}>
+            <Field.Label>Comment</Field.Label>
+            <Input type="text" value={comment} onChange={handleInputChange} />
             <Field.HelperText color="#c4c8d0">
-              Type "seed production" to confirm
+              What changed, for the reviewer
             </Field.HelperText>
           </Field.Root>
+
+          <PreviewSection previewMutation={previewMutation} />
+
+          {requestProductionDeployMutation.isError && (
+            <Text color="red.400" mt={4}>
+              {requestProductionDeployMutation.error.message}
+            </Text>
+          )}
         </Dialog.Body>
 
         <Dialog.Footer>
@@ -152,7 +216,7 @@ export function SeedProductionModal({
             colorPalette="blue"
             mr={3}
             onClick={() => {
-              setVal("");
+              setComment("");
               onClose();
             }}
           >
@@ -162,17 +226,86 @@ export function SeedProductionModal({
             variant={"outline"}
             colorPalette="yellow"
             onClick={() => {
-              setVal("");
-              handleSeedSelectedToProduction();
+              setComment("");
+              handleRequestSelectedToProduction(comment || null);
             }}
-            disabled={val !== "seed production"}
-            loadingText="Seeding..."
-            loading={seedExamToProductionMutation.isPending}
+            loadingText="Requesting..."
+            loading={requestProductionDeployMutation.isPending}
           >
-            Seed Production
+            Request Deploy
           </Button>
         </Dialog.Footer>
       </Dialog.Content>
     </Dialog.Root>
   );
 }
+
+interface PreviewSectionProps {
+  previewMutation: UseMutationResult<
+    DeploymentPreview[],
+    Error,
+    void,
+    unknown
+  >;
+}
+
+/**
+ * Dry run of the seed, showing what would change without writing anything
+ */
+function PreviewSection({ previewMutation }: PreviewSectionProps) {
+  return (
+    <>
+      <Button
+        mt={4}
+        size="sm"
+        variant="outline"
+        onClick={() => previewMutation.mutate()}
+        loading={previewMutation.isPending}
+        loadingText="Previewing..."
+      >
+        Preview Changes
+      </Button>
+      {previewMutation.isError && (
+        <Text color="red.400" mt={4}>
+          {previewMutation.error.message}
+        </Text>
+      )}
+      {previewMutation.data && (
+        <DeploymentPreviewList previews={previewMutation.data} />
+      )}
+    </>
+  );
+}
+
+interface OrphanedGenerationsFieldProps {
+  value: OrphanedGenerationsAction;
+  onChange: (value: OrphanedGenerationsAction) => void;
+}
+
+export function OrphanedGenerationsField({
+  value,
+  onChange,
+}: OrphanedGenerationsFieldProps) {
+  return (
+    <Field.Root mt={4}>
+      <Field.Label>Orphaned Generations</Field.Label>
+      <NativeSelect.Root size="sm">
+        <NativeSelect.Field
+          value={value}
+          onChange={(e) =>
+            onChange(e.target.value as OrphanedGenerationsAction)
+          }
+        >
+          <option value="block">Do not seed</option>
+          <option value="deprecate">Deprecate them</option>
+          <option value="ignore">Keep them live</option>
+        </NativeSelect.Field>
+        <NativeSelect.Indicator />
+      </NativeSelect.Root>
+      <Field.HelperText color="#c4c8d0">
+        What to do with live generations which reference questions or answers
+        removed from the exam
+      </Field.HelperText>
+    </Field.Root>
+  );
+}
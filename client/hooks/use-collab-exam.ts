@@ -1,48 +1,113 @@
 import type { ExamCreatorExam } from "@prisma/client";
-import { useEffect, useRef } from "react";
+import { useEffect, useRef, useState } from "react";
 import { useImmer } from "use-immer";
+import type { ExamMergeConflict, ExamOperation } from "../types";
+import { getSessionUser } from "../utils/fetch";
+import { applyExamOperation } from "../utils/patch";
 import { deserializeToPrisma, serializeFromPrisma } from "../utils/serde";
 
-// Simple WebSocket wrapper for collaborative editing
-// TODO: Does not work reliably
+// WebSocket wrapper for collaborative editing
+//
+// The server echoes every merged patch, including our own, in the order it merged them.
+// So, applying received patches in order converges with all co-editors.
+// Each patch is sent with the sequence number of the last sync or patch received,
+// so the server can tell which co-editors' changes it was made on top of.
+// Patches already included in the last sync are ignored, and if a patch is missed,
+// the exam is resynced from the server.
 export function useCollabExam(examId: string, initialExam: ExamCreatorExam) {
   const [exam, setExam] = useImmer<ExamCreatorExam>(initialExam);
+  const [conflicts, setConflicts] = useState<ExamMergeConflict[]>([]);
   const wsRef = useRef<WebSocket | null>(null);
-  const debounceRef = useRef<number | null>(null);
+  // `null` until the first sync
+  const seqRef = useRef<number | null>(null);
+  // Whether a patch was missed, and the exam is waiting to be resynced
+  const resyncingRef = useRef(false);
 
   // Connect to WebSocket server
   useEffect(() => {
-    // Replace with your actual WebSocket server URL
-    const ws = new WebSocket(`/ws/exam/${examId}`);
-    wsRef.current = ws;
-
-    ws.onmessage = (event) => {
-      const msg = JSON.parse(event.data);
-      if (msg.type === "exam-update" && msg.data) {
-        setExam(deserializeToPrisma(msg.data));
-      }
-    };
+    let ws: WebSocket | null = null;
+    let cancelled = false;
+
+    // Each WebSocket connection consumes a one-time token
+    getSessionUser().then(({ email, webSocketToken }) => {
+      if (cancelled) return;
+      ws = new WebSocket(`/ws/exam/${examId}?token=${webSocketToken}`);
+      wsRef.current = ws;
+
+      ws.onmessage = (event) => {
+        const msg = JSON.parse(event.data);
+        if (msg.type === "exam-sync" && msg.data) {
+          seqRef.current = msg.data.seq;
+          resyncingRef.current = false;
+          setExam(deserializeToPrisma<ExamCreatorExam>(msg.data.exam));
+        }
+        if (msg.type === "exam-patch" && msg.data) {
+          const last = seqRef.current;
+          // Waiting for a sync, or already included in the last sync
+          if (
+            last === null ||
+            resyncingRef.current ||
+            msg.data.seq <= last
+          ) {
+            return;
+          }
+          if (msg.data.seq !== last + 1) {
+            resyncingRef.current = true;
+            ws?.send(JSON.stringify({ type: "exam-resync" }));
+            return;
+          }
+          seqRef.current = msg.data.seq;
+          const operations = deserializeToPrisma<ExamOperation[]>(
+            msg.data.operations,
+          );
+          setExam((draft) => {
+            for (const operation of operations) {
+              applyExamOperation(draft, operation);
+            }
+          });
+        }
+        if (msg.type === "exam-merge-conflict" && msg.data) {
+          const conflict = deserializeToPrisma<ExamMergeConflict>(msg.data);
+          if (conflict.users.includes(email)) {
+            setConflicts((prev) => [...prev, conflict]);
+          }
+        }
+      };
+    });
 
     return () => {
-      ws.close();
+      cancelled = true;
+      ws?.close();
     };
   }, [examId, setExam]);
 
-  // Send exam updates over WebSocket, debounced
-  const sendExamUpdate = (nextExam: ExamCreatorExam) => {
-    setExam(nextExam);
-    if (debounceRef.current) clearTimeout(debounceRef.current);
-    debounceRef.current = window.setTimeout(() => {
-      if (wsRef.current && wsRef.current.readyState === WebSocket.OPEN) {
-        wsRef.current.send(
-          JSON.stringify({
-            type: "exam-update",
-            data: serializeFromPrisma(nextExam),
-          })
-        );
+  // Apply operations locally, and send them to co-editors
+  const sendExamPatch = (operations: ExamOperation[]) => {
+    setExam((draft) => {
+      for (const operation of operations) {
+        applyExamOperation(draft, operation);
       }
-    }, 300); // 300ms debounce
+    });
+    if (wsRef.current && wsRef.current.readyState === WebSocket.OPEN) {
+      wsRef.current.send(
+        JSON.stringify({
+          type: "exam-patch",
+          data: {
+            operations: serializeFromPrisma(operations, -1),
+            base: seqRef.current ?? 0,
+          },
+        }),
+      );
+    }
   };
 
-  return [exam, sendExamUpdate, setExam] as const;
+  const dismissConflicts = () => setConflicts([]);
+
+  return [
+    exam,
+    sendExamPatch,
+    setExam,
+    conflicts,
+    dismissConflicts,
+  ] as const;
 }
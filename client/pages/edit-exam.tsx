@@ -26,6 +26,7 @@ import {
   getExamById,
   getExamChallengeByExamId,
   getGenerations,
+  postValidateConfigByExamId,
 } from "../utils/fetch";
 import { TagConfigForm } from "../components/tag-config-form";
 import { ProtectedRoute } from "../components/protected-route";
@@ -173,6 +174,14 @@ function EditExam({ exam: examData }: EditExamProps) {
     refetchOnWindowFocus: false,
   });
 
+  // Validated as saved, so issues may be out of date until the exam is saved again
+  const configValidationQuery = useQuery({
+    queryKey: ["config-validation", exam.id],
+    queryFn: () => postValidateConfigByExamId(exam.id),
+    retry: false,
+    refetchOnWindowFocus: false,
+  });
+
   useEffect(() => {
     updateActivity({
       page: new URL(window.location.href),
@@ -293,11 +302,7 @@ function EditExam({ exam: examData }: EditExamProps) {
                 </Table.Root>
               </Box>
             </Box>
-            <EditExamGenerationVariability
-              examId={exam.id}
-              generatedExamsStagingData={generatedExamsStagingQuery.data}
-              generatedExamsProductionData={generatedExamsProductionQuery.data}
-            />
+            <EditExamGenerationVariability examId={exam.id} />
             <Separator my={4} />
             <HStack justifyContent={"space-evenly"} alignItems={"start"}>
               <TagConfigForm
@@ -318,7 +323,10 @@ function EditExam({ exam: examData }: EditExamProps) {
               These are the current configs which the algorithm will select
               random questions from:
             </Text>
-            <ConfigView {...{ config, setConfig }} />
+            <ConfigView
+              {...{ config, setConfig }}
+              validationIssues={configValidationQuery.data?.issues ?? []}
+            />
 
             <Separator my={4} />
             <Heading size="md" mt={8} mb={2} id="exam-questions">
@@ -335,6 +343,7 @@ function EditExam({ exam: examData }: EditExamProps) {
                 setQuestionSets={setQuestionSets}
                 generatedExamsStagingQuery={generatedExamsStagingQuery}
                 generatedExamsProductionQuery={generatedExamsProductionQuery}
+                validationIssues={configValidationQuery.data?.issues ?? []}
               />
             </Box>
           </form>
@@ -31,9 +31,11 @@ import { ExamCard } from "../components/exam-card";
 import {
   getExamById,
   getExams,
+  getExamsDrift,
+  postDeployRequest,
   postExam,
-  putExamByIdToProduction,
   putExamByIdToStaging,
+  type OrphanedGenerationsAction,
 } from "../utils/fetch";
 import { ProtectedRoute } from "../components/protected-route";
 import { editExamRoute } from "./edit-exam";
@@ -44,7 +46,11 @@ import { serializeFromPrisma } from "../utils/serde";
 import {
   SeedProductionModal,
   SeedStagingModal,
+  type RequestDeploys,
+  type SeedExams,
 } from "../components/seed-modal";
+import { DeployRequestsModal } from "../components/deploy-requests-modal";
+import { SchedulesModal } from "../components/schedules-modal";
 import { toaster } from "../components/toaster";
 import { Header } from "../components/ui/header";
 
@@ -66,6 +72,16 @@ export function Exams() {
     onOpen: productionOnOpen,
     onClose: productionOnClose,
   } = useDisclosure();
+  const {
+    open: deployRequestsIsOpen,
+    onOpen: deployRequestsOnOpen,
+    onClose: deployRequestsOnClose,
+  } = useDisclosure();
+  const {
+    open: schedulesIsOpen,
+    onOpen: schedulesOnOpen,
+    onClose: schedulesOnClose,
+  } = useDisclosure();
 
   const examsQuery = useQuery({
     queryKey: ["exams"],
@@ -74,6 +90,16 @@ export function Exams() {
     retry: false,
     refetchOnWindowFocus: false,
   });
+  const examNames = new Map(
+    examsQuery.data?.map(({ exam }) => [exam.id, exam.config.name]),
+  );
+  const examsDriftQuery = useQuery({
+    queryKey: ["exams-drift"],
+    enabled: !!user,
+    queryFn: () => getExamsDrift(),
+    retry: false,
+    refetchOnWindowFocus: false,
+  });
   const createExamMutation = useMutation({
     mutationFn: () => postExam(),
     onSuccess(data, _variables, _context) {
@@ -109,14 +135,17 @@ export function Exams() {
   });
 
   const seedExamToStagingMutation = useMutation({
-    mutationFn: (examIds: string[]) => {
-      const promises = examIds.map((id) => putExamByIdToStaging(id));
+    mutationFn: ({ examIds, orphanedGenerations }: SeedExams) => {
+      const promises = examIds.map((id) =>
+        putExamByIdToStaging(id, orphanedGenerations),
+      );
       return Promise.all(promises);
     },
     onSuccess(_data, _variables, _context) {
       stagingOnClose();
       handleDeselectAll();
       examsQuery.refetch();
+      examsDriftQuery.refetch();
       toaster.create({
         title: "Exams seeded to staging",
         description: "The selected exams have been seeded to staging.",
@@ -127,18 +156,18 @@ export function Exams() {
     },
   });
 
-  const seedExamToProductionMutation = useMutation({
-    mutationFn: (examIds: string[]) => {
-      const promises = examIds.map((id) => putExamByIdToProduction(id));
+  const requestProductionDeployMutation = useMutation({
+    mutationFn: ({ examIds, comment }: RequestDeploys) => {
+      const promises = examIds.map((id) => postDeployRequest(id, comment));
       return Promise.all(promises);
     },
     onSuccess(_data, _variables, _context) {
       productionOnClose();
       handleDeselectAll();
-      examsQuery.refetch();
       toaster.create({
-        title: "Exams seeded to production",
-        description: "The selected exams have been seeded to production.",
+        title: "Production deploys requested",
+        description:
+          "The selected exams can be deployed once another user approves them.",
         type: "success",
         duration: 5000,
         closable: true,
@@ -176,20 +205,22 @@ export function Exams() {
     examByIdMutation.mutate(examIds);
   }
 
-  function handleSeedSelectedToStaging() {
+  function handleSeedSelectedToStaging(
+    orphanedGenerations: OrphanedGenerationsAction,
+  ) {
     if (!examsQuery.data || selectedExams.size === 0) return;
 
     const examIds = [...selectedExams];
 
-    seedExamToStagingMutation.mutate(examIds);
+    seedExamToStagingMutation.mutate({ examIds, orphanedGenerations });
   }
 
-  function handleSeedSelectedToProduction() {
+  function handleRequestSelectedToProduction(comment: string | null) {
     if (!examsQuery.data || selectedExams.size === 0) return;
 
     const examIds = [...selectedExams];
 
-    seedExamToProductionMutation.mutate(examIds);
+    requestProductionDeployMutation.mutate({ examIds, comment });
   }
 
   function toggleSelectionMode() {
@@ -241,6 +272,24 @@ export function Exams() {
                 {selectionMode ? <X size={18} /> : null}
                 {selectionMode ? "Cancel Selection" : "Select Exams"}
               </Button>
+              <Button
+                colorPalette="yellow"
+                variant="outline"
+                px={6}
+                fontWeight="bold"
+                onClick={deployRequestsOnOpen}
+              >
+                Deploy Requests
+              </Button>
+              <Button
+                colorPalette="yellow"
+                variant="outline"
+                px={6}
+                fontWeight="bold"
+                onClick={schedulesOnOpen}
+              >
+                Schedules
+              </Button>
               <Button
                 colorPalette="teal"
                 variant="solid"
@@ -385,14 +434,14 @@ export function Exams() {
                         <Button
                           disabled={
                             selectedExams.size === 0 ||
-                            seedExamToProductionMutation.isPending
+                            requestProductionDeployMutation.isPending
                           }
-                          loading={seedExamToProductionMutation.isPending}
-                          loadingText={"Seed in progress"}
+                          loading={requestProductionDeployMutation.isPending}
+                          loadingText={"Requesting deploy"}
                           onClick={productionOnOpen}
                         >
                           <AppWindow size={18} />
-                          Seed to Production
+                          Request Production Deploy
                         </Button>
                       </Menu.Item>
                       {/* TODO: Probably never going to create such functionality */}
@@ -437,6 +486,9 @@ export function Exams() {
                     key={exam.id}
                     exam={exam}
                     databaseEnvironments={databaseEnvironments}
+                    drift={examsDriftQuery.data?.exams.find(
+                      (d) => d.examId === exam.id,
+                    )}
                     isSelected={selectedExams.has(exam.id)}
                     onSelectionChange={handleExamSelection}
                     selectionMode={selectionMode}
@@ -449,15 +501,31 @@ export function Exams() {
       </Center>
       <SeedStagingModal
         open={stagingIsOpen}
+        examIds={[...selectedExams]}
         onClose={stagingOnClose}
         handleSeedSelectedToStaging={handleSeedSelectedToStaging}
         seedExamToStagingMutation={seedExamToStagingMutation}
       />
       <SeedProductionModal
         open={productionIsOpen}
+        examIds={[...selectedExams]}
         onClose={productionOnClose}
-        handleSeedSelectedToProduction={handleSeedSelectedToProduction}
-        seedExamToProductionMutation={seedExamToProductionMutation}
+        handleRequestSelectedToProduction={handleRequestSelectedToProduction}
+        requestProductionDeployMutation={requestProductionDeployMutation}
+      />
+      <DeployRequestsModal
+        open={deployRequestsIsOpen}
+        onClose={deployRequestsOnClose}
+        examNames={examNames}
+        onDeployed={() => {
+          examsQuery.refetch();
+          examsDriftQuery.refetch();
+        }}
+      />
+      <SchedulesModal
+        open={schedulesIsOpen}
+        onClose={schedulesOnClose}
+        examNames={examNames}
       />
     </Box>
   );
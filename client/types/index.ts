@@ -1,6 +1,8 @@
 import type {
   ExamCreatorExam,
   ExamCreatorUser,
+  ExamEnvironmentAnswer,
+  ExamEnvironmentConfig,
   ExamEnvironmentExamAttempt,
   ExamEnvironmentGeneratedMultipleChoiceQuestion,
   ExamEnvironmentMultipleChoiceQuestion,
@@ -91,3 +93,440 @@ export interface Event {
   meta: Meta | null;
   attempt_id: string;
 }
+
+type Field<N extends string, V> = { name: N; value: V };
+
+/**
+ * A granular change to an exam, as applied by the server in `server/patch.rs`.
+ */
+export type ExamOperation =
+  | {
+      op: "set-exam";
+      field:
+        | Field<"prerequisites", ExamCreatorExam["prerequisites"]>
+        | Field<"deprecated", boolean>;
+    }
+  | {
+      op: "set-config";
+      field:
+        | Field<"name", string>
+        | Field<"note", string>
+        | Field<"tags", ExamEnvironmentConfig["tags"]>
+        | Field<"totalTimeInS", number>
+        | Field<"questionSets", ExamEnvironmentConfig["questionSets"]>
+        | Field<"retakeTimeInS", number>
+        | Field<"passingPercent", number>;
+    }
+  | { op: "add-question-set"; questionSet: ExamEnvironmentQuestionSet }
+  | { op: "remove-question-set"; questionSetId: string }
+  | {
+      op: "set-question-set";
+      questionSetId: string;
+      field:
+        | Field<"type", ExamEnvironmentQuestionSet["type"]>
+        | Field<"context", string | null>;
+    }
+  | {
+      op: "add-question";
+      questionSetId: string;
+      question: ExamEnvironmentMultipleChoiceQuestion;
+    }
+  | { op: "remove-question"; questionSetId: string; questionId: string }
+  | {
+      op: "set-question";
+      questionSetId: string;
+      questionId: string;
+      field:
+        | Field<"text", string>
+        | Field<"tags", string[]>
+        | Field<"audio", ExamEnvironmentMultipleChoiceQuestion["audio"]>
+        | Field<"deprecated", boolean>;
+    }
+  | {
+      op: "add-answer";
+      questionSetId: string;
+      questionId: string;
+      answer: ExamEnvironmentAnswer;
+    }
+  | {
+      op: "remove-answer";
+      questionSetId: string;
+      questionId: string;
+      answerId: string;
+    }
+  | {
+      op: "set-answer";
+      questionSetId: string;
+      questionId: string;
+      answerId: string;
+      field: Field<"text", string> | Field<"isCorrect", boolean>;
+    };
+
+/**
+ * Concurrent edits which could not both be kept, as merged by the server in `server/merge.rs`.
+ */
+export interface ExamMergeConflict {
+  kind: "sameField" | "removed";
+  field: {
+    questionSetId: string | null;
+    questionId: string | null;
+    answerId: string | null;
+    /** `null` if the item itself was added or removed */
+    field: string | null;
+  };
+  /** Emails of the users whose edits conflicted */
+  users: string[];
+  /** Email of the user whose edit was kept */
+  kept: string;
+}
+
+/**
+ * A problem with an exam, as checked by the server in `server/validation.rs`.
+ */
+export interface ValidationIssue {
+  severity: "error" | "warning";
+  /** Stable identifier of the kind of issue, such as `EMPTY_ANSWER_TEXT` */
+  code: string;
+  /** JSON path into the exam, such as `$.questionSets[0].questions[1].answers[2].text` */
+  path: string;
+  message: string;
+}
+
+export interface ConfigValidation {
+  /** Every problem found with the exam's config and content */
+  issues: ValidationIssue[];
+  /** Whether the exam config can be generated */
+  feasibility: FeasibilityReport;
+}
+
+export interface SetDiff<T> {
+  added: T[];
+  removed: T[];
+}
+
+export type ChangeKind = "added" | "removed" | "modified";
+
+/**
+ * A changed field, with the value before and after the change
+ */
+export interface FieldDiff {
+  field: string;
+  from: unknown;
+  to: unknown;
+}
+
+/**
+ * Difference between two exams, as computed by the server in `server/diff.rs`.
+ */
+export interface ExamDiff {
+  fields: FieldDiff[];
+  prerequisites: SetDiff<string>;
+  config: {
+    fields: FieldDiff[];
+    tags: SetDiff<unknown>;
+    questionSets: SetDiff<unknown>;
+  };
+  questionSets: {
+    id: string;
+    change: ChangeKind;
+    fields: FieldDiff[];
+    questions: {
+      id: string;
+      change: ChangeKind;
+      fields: FieldDiff[];
+      tags: SetDiff<string>;
+      answers: { id: string; change: ChangeKind; fields: FieldDiff[] }[];
+    }[];
+  }[];
+}
+
+/**
+ * Response body for a save based on an outdated version of the exam
+ */
+export interface ExamConflict {
+  /** The exam as it is currently saved */
+  current: ExamCreatorExam;
+  /** Question sets which differ between the saved exam and the submitted exam */
+  questionSets: QuestionSetConflict[];
+  configChanged: boolean;
+  prerequisitesChanged: boolean;
+}
+
+export interface QuestionSetConflict {
+  id: string;
+  /** How the saved exam changed the question set, if known */
+  current: ChangeKind | null;
+  /** How the submitted exam changed the question set, if known */
+  submitted: ChangeKind | null;
+  /** Whether both sides changed the question set differently */
+  conflicting: boolean;
+}
+
+export interface OrphanedGeneration {
+  generatedExamId: string;
+  questionSets: string[];
+  questions: string[];
+  answers: string[];
+}
+
+/**
+ * What seeding an exam changes in a database
+ */
+export interface DeploymentPreview {
+  examId: string;
+  /** Whether the exam is not yet in the database */
+  created: boolean;
+  exam: ExamDiff;
+  /** Challenge ids mapped to the exam */
+  challenges: SetDiff<string>;
+  /** Live generations referencing content removed from the exam */
+  orphanedGenerations: OrphanedGeneration[];
+}
+
+/**
+ * How an environment's deployed exam compares to the exam creator's
+ */
+export type DriftStatus =
+  | "missing"
+  | "identical"
+  | "behind"
+  | "ahead"
+  | "diverged";
+
+export interface EnvironmentDrift {
+  status: DriftStatus;
+  /** Version of the deployed exam */
+  version: number | null;
+  /** Parts of the deployed exam which differ from the exam creator's */
+  differences: ("questionSets" | "config" | "prerequisites" | "challenges")[];
+}
+
+export interface ExamDrift {
+  examId: string;
+  name: string;
+  /** Version of the exam in the exam creator */
+  version: number;
+  staging: EnvironmentDrift;
+  production: EnvironmentDrift;
+}
+
+export interface DriftReport {
+  createdAt: Date;
+  exams: ExamDrift[];
+}
+
+export type DeployRequestStatus =
+  | "Pending"
+  | "Approved"
+  | "Rejected"
+  | "Deployed";
+
+export interface DeployRequestTransition {
+  status: DeployRequestStatus;
+  actorId: string;
+  actorName: string;
+  actorEmail: string;
+  comment: string | null;
+  createdAt: Date;
+}
+
+/**
+ * A request to deploy a revision of an exam to production, or roll production back, which another user must approve
+ */
+export interface DeployRequest {
+  id: string;
+  examId: string;
+  revisionId: string;
+  revision: number;
+  status: DeployRequestStatus;
+  requesterId: string;
+  requesterName: string;
+  requesterEmail: string;
+  /** Every change of status, oldest first, starting with the request being made */
+  transitions: DeployRequestTransition[];
+  /** Production deployment rolled back to, if the request is a rollback */
+  rollbackDeploymentId: string | null;
+  /** `deprecated` to set on the production exam, if the request is to deprecate or undeprecate it */
+  deprecated: boolean | null;
+  deploymentId: string | null;
+  createdAt: Date;
+  updatedAt: Date;
+}
+
+export type ScheduleAction = "Seed" | "Deprecate" | "Undeprecate";
+
+export type ScheduleStatus =
+  | "Scheduled"
+  | "Running"
+  | "Completed"
+  | "Failed"
+  | "Cancelled";
+
+/**
+ * A change to an exam in a database environment, made by the server at a set time
+ */
+export interface Schedule {
+  id: string;
+  examId: string;
+  databaseEnvironment: "Staging" | "Production";
+  action: ScheduleAction;
+  orphanedGenerations: "block" | "deprecate" | "ignore";
+  /** Deploy request deployed when seeding production */
+  deployRequestId: string | null;
+  /** When the schedule is next run, including to be retried */
+  runAt: Date;
+  status: ScheduleStatus;
+  /** Number of times the schedule has been run, including failures */
+  attempts: number;
+  /** Why the last run failed */
+  error: string | null;
+  createdById: string;
+  createdBy: string;
+  createdAt: Date;
+  updatedAt: Date;
+}
+
+export interface TagUsage {
+  tag: string;
+  /** Number of questions with the tag, including deprecated questions */
+  questions: number;
+  /** Number of tag configs with the tag in their group */
+  tagConfigs: number;
+  /** Number of exams using the tag */
+  exams: number;
+  /** Whether the tag is registered */
+  registered: boolean;
+}
+
+/** A tag registered for use in exams */
+export interface ExamCreatorTag {
+  id: string;
+  name: string;
+  description: string | null;
+  /** Email of the user who registered the tag */
+  createdBy: string;
+  createdAt: Date;
+}
+
+export interface RenamedTags {
+  /** Exams saved with the tags renamed */
+  exams: string[];
+  questions: number;
+  tagConfigs: number;
+  /** Exams using the tags, which were not renamed because they have unsaved edits */
+  skipped: string[];
+}
+
+/**
+ * Why an exam config can or cannot be generated, as checked by the server in `server/generate.rs`.
+ */
+export interface FeasibilityReport {
+  status: "feasible" | "infeasible" | "unknown";
+  /** Problems with the config itself, such as a missing name */
+  errors: string[];
+  /** Groups of question set configs which need more question sets than are eligible */
+  questionSetConfigs: {
+    questionSetConfigs: number[];
+    type: ExamEnvironmentQuestionSet["type"];
+    required: number;
+    available: number;
+    short: number;
+    shortQuestions: number;
+  }[];
+  /** Tag configs which cannot be fulfilled, even if every question counted towards them */
+  tagConfigs: {
+    tagConfig: number;
+    group: string[];
+    required: number;
+    available: number;
+    short: number;
+  }[];
+  /** Pairs of tag configs which cannot both be fulfilled */
+  competingTagConfigs: {
+    tagConfigs: [number, number];
+    groups: [string[], string[]];
+    sharedQuestions: number;
+    required: number;
+    available: number;
+    short: number;
+  }[];
+}
+
+export interface ExposureSummary {
+  min: number;
+  max: number;
+  mean: number;
+  stdDev: number;
+  /** Number of items which appear in no generation */
+  unexposed: number;
+  /** Exposure of each item, least exposed first */
+  items: { id: string; count: number }[];
+}
+
+/** Number of generations each question and answer of an exam appears in */
+export interface ExposureDistribution {
+  generations: number;
+  questions: ExposureSummary;
+  answers: ExposureSummary;
+}
+
+/** (number of items in A, but not in B) / (number of items in A), over every pair of generations */
+export interface Variability {
+  mean: number;
+  min: number;
+  max: number;
+}
+
+export interface GenerationMetrics {
+  totalGenerations: number;
+  deprecatedGenerations: number;
+  /** `null` if there are fewer than two generations to compare */
+  questionVariability: Variability | null;
+  answerVariability: Variability | null;
+  questions: {
+    questionSetId: string;
+    questionId: string;
+    deprecated: boolean;
+    generations: number;
+    answers: {
+      answerId: string;
+      isCorrect: boolean;
+      generations: number;
+      /** Number of generations the answer appears at each position of its question */
+      positions: number[];
+    }[];
+  }[];
+  /** Non-deprecated questions which appear in no generation */
+  unusedQuestions: string[];
+}
+
+export type GenerationJobStatus =
+  | "Queued"
+  | "Running"
+  | "Completed"
+  | "Failed"
+  | "Cancelled";
+
+/** A request to generate a number of exams, worked through in the background */
+export interface GenerationJob {
+  id: string;
+  examId: string;
+  databaseEnvironment: "Staging" | "Production";
+  /** Revision of the exam to generate from */
+  revision: number | null;
+  count: number;
+  /** Number of exams generated so far */
+  generated: number;
+  seed: number | null;
+  balanceExposure: boolean;
+  /** Live generations of the exam when the job was created, which a balanced job's exposure is counted from */
+  balancedAgainst: string[];
+  status: GenerationJobStatus;
+  /** Why the job failed */
+  error: string | null;
+  /** Exposure across the exam's live generations, once a balanced job has completed */
+  exposure: ExposureDistribution | null;
+  createdBy: string;
+  createdAt: Date;
+  updatedAt: Date;
+}
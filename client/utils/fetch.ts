@@ -9,16 +9,33 @@ import {
 import type {
   Attempt,
   ClientSync,
+  ConfigValidation,
+  DeployRequest,
+  DeployRequestStatus,
+  DeploymentPreview,
+  DriftReport,
   Event,
+  ExamConflict,
+  ExamCreatorTag,
+  GenerationJob,
+  GenerationMetrics,
+  RenamedTags,
+  Schedule,
+  ScheduleAction,
   SessionUser,
   Settings,
+  TagUsage,
   User,
 } from "../types";
 import { deserializeToPrisma, serializeFromPrisma } from "./serde";
 
+/**
+ * @param handledStatuses Error statuses returned to the caller to handle, instead of thrown
+ */
 async function authorizedFetch(
   url: string | URL,
   options?: RequestInit,
+  handledStatuses: number[] = [],
 ): Promise<Response> {
   const headers = {
     ...options?.headers,
@@ -29,7 +46,7 @@ async function authorizedFetch(
     headers,
   });
 
-  if (!res.ok) {
+  if (!res.ok && !handledStatuses.includes(res.status)) {
     const errorData = await res.text();
     console.debug(res.status, url, errorData);
     if (res.status === 401) {
@@ -110,6 +127,13 @@ export async function getExams(): Promise<GetExam[]> {
   return deserialized;
 }
 
+export async function getExamsDrift(refresh = false): Promise<DriftReport> {
+  const res = await authorizedFetch(`/api/exams/drift?refresh=${refresh}`);
+  const json = await res.json();
+  const deserialized = deserializeToPrisma<DriftReport>(json);
+  return deserialized;
+}
+
 export async function getExamById(examId: string): Promise<ExamCreatorExam> {
   if (import.meta.env.VITE_MOCK_DATA === "true") {
     await delayForTesting(300);
@@ -139,18 +163,38 @@ export async function putExamById(
     return exam;
   }
 
-  const res = await authorizedFetch(`/api/exams/${exam.id}`, {
-    method: "PUT",
-    body: JSON.stringify(serializeFromPrisma(exam)),
-    headers: {
-      "Content-Type": "application/json",
+  const res = await authorizedFetch(
+    `/api/exams/${exam.id}`,
+    {
+      method: "PUT",
+      body: JSON.stringify(serializeFromPrisma(exam)),
+      headers: {
+        "Content-Type": "application/json",
+      },
     },
-  });
+    [409],
+  );
   const json = await res.json();
+  if (res.status === 409) {
+    throw new ExamConflictError(deserializeToPrisma<ExamConflict>(json));
+  }
   const deserialized = deserializeToPrisma<ExamCreatorExam>(json);
   return deserialized;
 }
 
+/**
+ * Thrown when saving an exam which someone else saved since it was loaded
+ */
+export class ExamConflictError extends Error {
+  conflict: ExamConflict;
+
+  constructor(conflict: ExamConflict) {
+    super("The exam was saved by someone else since it was loaded.");
+    this.name = "ExamConflictError";
+    this.conflict = conflict;
+  }
+}
+
 /**
  * Server creates a new exam
  */
@@ -211,42 +255,117 @@ export async function getGenerations({
   return deserializeToPrisma(json);
 }
 
-export interface PutGenerateExam {
+/**
+ * Get the variability of an exam's generations, and how evenly they use its questions and answers
+ */
+export async function getGenerationMetrics({
+  examId,
+  databaseEnvironment,
+}: GetGenerationsArg): Promise<GenerationMetrics> {
+  if (import.meta.env.VITE_MOCK_DATA === "true") {
+    await delayForTesting(300);
+    return {
+      totalGenerations: 0,
+      deprecatedGenerations: 0,
+      questionVariability: null,
+      answerVariability: null,
+      questions: [],
+      unusedQuestions: [],
+    };
+  }
+
+  const res = await authorizedFetch(
+    `/api/exams/${examId}/generations/${databaseEnvironment}/metrics`,
+  );
+  const json = await res.json();
+  return deserializeToPrisma<GenerationMetrics>(json);
+}
+
+export interface PostGenerationJob {
   examId: ExamCreatorExam["id"];
   count: number;
   databaseEnvironment: "Staging" | "Production";
+  /**
+   * Seed of the first generation, incremented for each subsequent generation.
+   * Each generation is given a random seed, if not provided.
+   */
+  seed?: number;
+  /** Prefer the questions and answers least exposed by existing generations */
+  balanceExposure?: boolean;
 }
 
 /**
- * Generate an exam based on the exam configuration
+ * Queue a job to generate exams based on the exam configuration
  */
-export async function putGenerateExam({
+export async function postGenerationJob({
   examId,
   count,
   databaseEnvironment,
-}: PutGenerateExam): Promise<ReadableStream<Uint8Array<ArrayBuffer>>> {
-  // }: PutGenerateExam): Promise<ReadableStream<Uint8Array>> {
+  seed,
+  balanceExposure,
+}: PostGenerationJob): Promise<GenerationJob> {
   if (import.meta.env.VITE_MOCK_DATA === "true") {
     await delayForTesting(300);
 
-    const generatedExam = {
+    const now = new Date();
+    return {
+      id: crypto.randomUUID(),
       examId,
+      databaseEnvironment,
+      revision: null,
       count,
+      generated: 0,
+      seed: seed ?? null,
+      balanceExposure: balanceExposure ?? false,
+      balancedAgainst: [],
+      status: "Queued",
+      error: null,
+      exposure: null,
+      createdBy: "mock@freecodecamp.org",
+      createdAt: now,
+      updatedAt: now,
     };
+  }
 
-    // Mock readable stream with delayed chunks of two `generatedExam` objects. This should return a JSON New Line stream
+  const res = await authorizedFetch(`/api/generation-jobs`, {
+    method: "POST",
+    body: JSON.stringify({
+      examId,
+      databaseEnvironment,
+      count,
+      seed,
+      balanceExposure,
+    }),
+    headers: {
+      "Content-Type": "application/json",
+    },
+  });
+  const json = await res.json();
+  return deserializeToPrisma<GenerationJob>(json);
+}
+
+/**
+ * Stream the job each time it is updated, as newline-delimited JSON, until it finishes
+ */
+export async function getGenerationJobProgress(
+  job: GenerationJob,
+): Promise<ReadableStream<Uint8Array<ArrayBuffer>>> {
+  if (import.meta.env.VITE_MOCK_DATA === "true") {
+    await delayForTesting(300);
+
+    // Mock readable stream with the job updated every 500ms. This should return a JSON New Line stream
     return new ReadableStream<Uint8Array<ArrayBuffer>>({
       start(controller) {
-        let sent = 0;
+        let generated = 0;
         const interval = setInterval(() => {
-          if (sent < count) {
-            controller.enqueue(
-              new TextEncoder().encode(
-                JSON.stringify(generatedExam) + (sent < count - 1 ? "\n" : ""),
-              ),
-            );
-            sent++;
-          } else {
+          generated++;
+          const status = generated < job.count ? "Running" : "Completed";
+          controller.enqueue(
+            new TextEncoder().encode(
+              JSON.stringify({ ...job, generated, status }) + "\n",
+            ),
+          );
+          if (status === "Completed") {
             clearInterval(interval);
             controller.close();
           }
@@ -255,38 +374,56 @@ export async function putGenerateExam({
     });
   }
 
-  const res = await authorizedFetch(
-    `/api/exams/${examId}/generations/${databaseEnvironment}`,
-    {
-      method: "PUT",
-      body: JSON.stringify({ count }),
-      headers: {
-        "Content-Type": "application/json",
-      },
-    },
-  );
+  const res = await authorizedFetch(`/api/generation-jobs/${job.id}/progress`);
 
   if (!res.body) {
-    throw new Error("Failed to generate exam");
+    throw new Error("Failed to get generation job progress");
   }
 
   return res.body;
 }
 
+/**
+ * Stop a queued or running job. Exams already generated are kept.
+ */
+export async function putCancelGenerationJob(
+  jobId: GenerationJob["id"],
+): Promise<GenerationJob> {
+  if (import.meta.env.VITE_MOCK_DATA === "true") {
+    await delayForTesting(300);
+    throw new Error("Cancelling generation jobs is not mocked");
+  }
+
+  const res = await authorizedFetch(`/api/generation-jobs/${jobId}/cancel`, {
+    method: "PUT",
+  });
+  const json = await res.json();
+  return deserializeToPrisma<GenerationJob>(json);
+}
+
 export async function postValidateConfigByExamId(
   examId: ExamCreatorExam["id"],
-): Promise<Response> {
+): Promise<ConfigValidation> {
   if (import.meta.env.VITE_MOCK_DATA === "true") {
     await delayForTesting(300);
 
-    return new Response();
+    return {
+      issues: [],
+      feasibility: {
+        status: "feasible",
+        errors: [],
+        questionSetConfigs: [],
+        tagConfigs: [],
+        competingTagConfigs: [],
+      },
+    };
   }
 
   const res = await authorizedFetch(`/api/exams/${examId}/config/validate`, {
     method: "POST",
   });
-
-  return res;
+  const json = await res.json();
+  return json as ConfigValidation;
 }
 
 export async function getUsers(): Promise<User[]> {
@@ -726,18 +863,240 @@ export async function putExamEnvironmentChallenges(
 //   return deserialized;
 // }
 
-export async function putExamByIdToStaging(examId: string) {
-  return await authorizedFetch(`/api/exams/${examId}/seed/staging`, {
-    method: "PUT",
-  });
+/**
+ * What to do with live generations which reference content removed from the exam:
+ * - `block`: refuse to seed the exam
+ * - `deprecate`: deprecate the generations, then seed the exam
+ * - `ignore`: seed the exam, keeping the generations live
+ */
+export type OrphanedGenerationsAction = "block" | "deprecate" | "ignore";
+
+/**
+ * Seeds the exam to staging, returning what was changed.
+ * With `dryRun`, nothing is written, and what would be changed is returned.
+ */
+export async function putExamByIdToStaging(
+  examId: string,
+  orphanedGenerations: OrphanedGenerationsAction = "block",
+  dryRun = false,
+): Promise<DeploymentPreview> {
+  const res = await authorizedFetch(
+    `/api/exams/${examId}/seed/staging?orphanedGenerations=${orphanedGenerations}&dryRun=${dryRun}`,
+    {
+      method: "PUT",
+    },
+  );
+  const json = await res.json();
+  const deserialized = deserializeToPrisma<DeploymentPreview>(json);
+  return deserialized;
 }
 
+/**
+ * Seeds the exam to production, returning what was changed.
+ * With `dryRun`, nothing is written, and what would be changed is returned.
+ * Otherwise, `deployRequestId` must be approved for the exam's latest revision.
+ */
 export async function putExamByIdToProduction(
   examId: string,
-): Promise<Response> {
-  return await authorizedFetch(`/api/exams/${examId}/seed/production`, {
+  orphanedGenerations: OrphanedGenerationsAction = "block",
+  dryRun = false,
+  deployRequestId: string | null = null,
+): Promise<DeploymentPreview> {
+  const deployRequest = deployRequestId
+    ? `&deployRequestId=${deployRequestId}`
+    : "";
+  const res = await authorizedFetch(
+    `/api/exams/${examId}/seed/production?orphanedGenerations=${orphanedGenerations}&dryRun=${dryRun}${deployRequest}`,
+    {
+      method: "PUT",
+    },
+  );
+  const json = await res.json();
+  const deserialized = deserializeToPrisma<DeploymentPreview>(json);
+  return deserialized;
+}
+
+/**
+ * Request the exam's latest revision be deployed to production, for another user to approve.
+ *
+ * With `deprecated`, the request is instead to set `deprecated` on the production exam, by a schedule.
+ */
+export async function postDeployRequest(
+  examId: string,
+  comment: string | null,
+  deprecated: boolean | null = null,
+): Promise<DeployRequest> {
+  const res = await authorizedFetch(`/api/exams/${examId}/deploy-requests`, {
+    method: "POST",
+    headers: {
+      "Content-Type": "application/json",
+    },
+    body: JSON.stringify({ comment, deprecated }),
+  });
+  const json = await res.json();
+  const deserialized = deserializeToPrisma<DeployRequest>(json);
+  return deserialized;
+}
+
+export async function getDeployRequests(
+  status?: DeployRequestStatus,
+): Promise<DeployRequest[]> {
+  const query = status ? `?status=${status}` : "";
+  const res = await authorizedFetch(`/api/deploy-requests${query}`, {
+    method: "GET",
+  });
+  const json = await res.json();
+  const deserialized = deserializeToPrisma<DeployRequest[]>(json);
+  return deserialized;
+}
+
+export async function putReviewDeployRequest(
+  deployRequestId: string,
+  review: "approve" | "reject",
+  comment: string | null,
+): Promise<DeployRequest> {
+  const res = await authorizedFetch(
+    `/api/deploy-requests/${deployRequestId}/${review}`,
+    {
+      method: "PUT",
+      headers: {
+        "Content-Type": "application/json",
+      },
+      body: JSON.stringify({ comment }),
+    },
+  );
+  const json = await res.json();
+  const deserialized = deserializeToPrisma<DeployRequest>(json);
+  return deserialized;
+}
+
+/**
+ * Deploy an approved request's revision to production
+ */
+export async function putExecuteDeployRequest(
+  deployRequestId: string,
+  orphanedGenerations: OrphanedGenerationsAction = "block",
+  dryRun = false,
+): Promise<DeploymentPreview> {
+  const res = await authorizedFetch(
+    `/api/deploy-requests/${deployRequestId}/execute?orphanedGenerations=${orphanedGenerations}&dryRun=${dryRun}`,
+    {
+      method: "PUT",
+    },
+  );
+  const json = await res.json();
+  const deserialized = deserializeToPrisma<DeploymentPreview>(json);
+  return deserialized;
+}
+
+export interface NewSchedule {
+  examId: string;
+  databaseEnvironment: "Staging" | "Production";
+  action: ScheduleAction;
+  runAt: Date;
+  orphanedGenerations: OrphanedGenerationsAction;
+  /** Approved deploy request for the action, required to change production */
+  deployRequestId: string | null;
+}
+
+/**
+ * Schedule an exam to be seeded, deprecated, or undeprecated at a future time
+ */
+export async function postSchedule(schedule: NewSchedule): Promise<Schedule> {
+  const res = await authorizedFetch(`/api/schedules`, {
+    method: "POST",
+    headers: {
+      "Content-Type": "application/json",
+    },
+    body: JSON.stringify({
+      ...schedule,
+      runAt: schedule.runAt.toISOString(),
+    }),
+  });
+  const json = await res.json();
+  const deserialized = deserializeToPrisma<Schedule>(json);
+  return deserialized;
+}
+
+export async function getSchedules(): Promise<Schedule[]> {
+  const res = await authorizedFetch(`/api/schedules`, {
+    method: "GET",
+  });
+  const json = await res.json();
+  const deserialized = deserializeToPrisma<Schedule[]>(json);
+  return deserialized;
+}
+
+export async function putCancelSchedule(
+  scheduleId: string,
+): Promise<Schedule> {
+  const res = await authorizedFetch(`/api/schedules/${scheduleId}/cancel`, {
     method: "PUT",
   });
+  const json = await res.json();
+  const deserialized = deserializeToPrisma<Schedule>(json);
+  return deserialized;
+}
+
+/**
+ * Usage of every tag, in all exams or only `examId`
+ */
+export async function getTags(examId?: string): Promise<TagUsage[]> {
+  const query = examId ? `?examId=${examId}` : "";
+  const res = await authorizedFetch(`/api/tags${query}`, {
+    method: "GET",
+  });
+  const json = await res.json();
+  return json;
+}
+
+/**
+ * Registers a tag. Rejected if it is already registered, or is similar to a registered tag.
+ */
+export async function postTag(
+  name: string,
+  description?: string,
+): Promise<ExamCreatorTag> {
+  const res = await authorizedFetch(`/api/tags`, {
+    method: "POST",
+    headers: {
+      "Content-Type": "application/json",
+    },
+    body: JSON.stringify({ name, description }),
+  });
+  const json = await res.json();
+  const deserialized = deserializeToPrisma<ExamCreatorTag>(json);
+  return deserialized;
+}
+
+export async function deleteTag(tagId: string): Promise<ExamCreatorTag> {
+  const res = await authorizedFetch(`/api/tags/${tagId}`, {
+    method: "DELETE",
+  });
+  const json = await res.json();
+  const deserialized = deserializeToPrisma<ExamCreatorTag>(json);
+  return deserialized;
+}
+
+/**
+ * Renames each of `from` to `to`, in all exams or only `examId`.
+ * Renaming several tags to the same tag merges them.
+ */
+export async function putRenameTags(
+  from: string[],
+  to: string,
+  examId?: string,
+): Promise<RenamedTags> {
+  const res = await authorizedFetch(`/api/tags/rename`, {
+    method: "PUT",
+    headers: {
+      "Content-Type": "application/json",
+    },
+    body: JSON.stringify({ from, to, examId }),
+  });
+  const json = await res.json();
+  const deserialized = deserializeToPrisma<RenamedTags>(json);
+  return deserialized;
 }
 
 export async function getExamsMetrics() {
@@ -0,0 +1,98 @@
+import type { ExamCreatorExam } from "@prisma/client";
+import type { ExamOperation } from "../types";
+
+/**
+ * Applies an operation to a (draft) exam in place.
+ *
+ * Mirrors `ExamOperation::apply` on the server. Operations whose target no longer exists are ignored,
+ * as the server will follow up with the authoritative state.
+ */
+export function applyExamOperation(
+  exam: ExamCreatorExam,
+  operation: ExamOperation,
+): void {
+  const questionSet = (id: string) =>
+    exam.questionSets.find((qs) => qs.id === id);
+  const question = (questionSetId: string, questionId: string) =>
+    questionSet(questionSetId)?.questions.find((q) => q.id === questionId);
+
+  switch (operation.op) {
+    case "set-exam": {
+      const { name, value } = operation.field;
+      if (name === "prerequisites") exam.prerequisites = value;
+      else exam.deprecated = value;
+      break;
+    }
+    case "set-config": {
+      // SAFETY: The field name and value types are paired in `ExamOperation`
+      (exam.config as Record<string, unknown>)[operation.field.name] =
+        operation.field.value;
+      break;
+    }
+    case "add-question-set": {
+      if (!questionSet(operation.questionSet.id)) {
+        exam.questionSets.push(operation.questionSet);
+      }
+      break;
+    }
+    case "remove-question-set": {
+      exam.questionSets = exam.questionSets.filter(
+        (qs) => qs.id !== operation.questionSetId,
+      );
+      break;
+    }
+    case "set-question-set": {
+      const qs = questionSet(operation.questionSetId);
+      if (!qs) break;
+      const { name, value } = operation.field;
+      if (name === "type") qs.type = value;
+      else qs.context = value;
+      break;
+    }
+    case "add-question": {
+      const qs = questionSet(operation.questionSetId);
+      if (qs && !qs.questions.some((q) => q.id === operation.question.id)) {
+        qs.questions.push(operation.question);
+      }
+      break;
+    }
+    case "remove-question": {
+      const qs = questionSet(operation.questionSetId);
+      if (!qs) break;
+      qs.questions = qs.questions.filter((q) => q.id !== operation.questionId);
+      break;
+    }
+    case "set-question": {
+      const q = question(operation.questionSetId, operation.questionId);
+      if (!q) break;
+      // SAFETY: The field name and value types are paired in `ExamOperation`
+      (q as Record<string, unknown>)[operation.field.name] =
+        operation.field.value;
+      break;
+    }
+    case "add-answer": {
+      const q = question(operation.questionSetId, operation.questionId);
+      if (q && !q.answers.some((a) => a.id === operation.answer.id)) {
+        q.answers.push(operation.answer);
+      }
+      break;
+    }
+    case "remove-answer": {
+      const q = question(operation.questionSetId, operation.questionId);
+      if (!q) break;
+      q.answers = q.answers.filter((a) => a.id !== operation.answerId);
+      break;
+    }
+    case "set-answer": {
+      const a = question(
+        operation.questionSetId,
+        operation.questionId,
+      )?.answers.find((a) => a.id === operation.answerId);
+      if (!a) break;
+      const { name, value } = operation.field;
+      if (name === "text") a.text = value;
+      else a.isCorrect = value;
+      break;
+    }
+  }
+}
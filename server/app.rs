@@ -13,7 +13,9 @@ use http::header::AUTHORIZATION;
 use http::header::ORIGIN;
 use http::header::SET_COOKIE;
 use http::header::X_CONTENT_TYPE_OPTIONS;
-use mongodb::options::ClientOptions;
+use mongodb::IndexModel;
+use mongodb::bson::doc;
+use mongodb::options::{ClientOptions, IndexOptions};
 use oauth2::{AuthUrl, ClientId, ClientSecret, RedirectUrl, TokenUrl, basic::BasicClient};
 use reqwest::Method;
 use std::sync::{Arc, Mutex};
@@ -32,7 +34,7 @@ use tracing::warn;
 use crate::errors::Error;
 use crate::state::Cache;
 use crate::{
-    database, extractor, routes,
+    database, drift, extractor, jobs, routes, scheduler,
     state::{self, ClientSync, ServerState},
 };
 
@@ -66,12 +68,21 @@ pub async fn app(env_vars: EnvVars) -> Result<Router, Error> {
         .with_expiry(Expiry::OnInactivity(time::Duration::seconds(10)));
 
     let production_database = database::Database {
+        client: production_client.clone(),
         user: production_database.collection("user"),
         exam_creator_exam: production_database.collection("ExamCreatorExam"),
+        exam_creator_exam_revision: production_database.collection("ExamCreatorExamRevision"),
+        exam_creator_exam_draft: production_database.collection("ExamCreatorExamDraft"),
+        exam_creator_exam_deployment: production_database.collection("ExamCreatorExamDeployment"),
+        exam_creator_deploy_request: production_database.collection("ExamCreatorDeployRequest"),
+        exam_creator_tag: production_database.collection("ExamCreatorTag"),
         exam: production_database.collection("ExamEnvironmentExam"),
         exam_attempt: production_database.collection("ExamEnvironmentExamAttempt"),
         exam_environment_challenge: production_database.collection("ExamEnvironmentChallenge"),
         generated_exam: production_database.collection("ExamEnvironmentGeneratedExam"),
+        generated_exam_seed: production_database.collection("ExamCreatorGeneratedExamSeed"),
+        generation_job: production_database.collection("ExamCreatorGenerationJob"),
+        schedule: production_database.collection("ExamCreatorSchedule"),
         exam_creator_user: production_database.collection("ExamCreatorUser"),
         exam_creator_session: production_database.collection("ExamCreatorSession"),
         exam_environment_exam_moderation: production_database
@@ -79,12 +90,28 @@ pub async fn app(env_vars: EnvVars) -> Result<Router, Error> {
     };
 
     let staging_database = database::Database {
+        client: staging_client.clone(),
         user: staging_database.collection("user"),
         exam_creator_exam: staging_database.collection("ExamCreatorExam"),
+        // Should not be used
+        exam_creator_exam_revision: staging_database.collection("ExamCreatorExamRevision"),
+        // Should not be used
+        exam_creator_exam_draft: staging_database.collection("ExamCreatorExamDraft"),
+        // Should not be used
+        exam_creator_exam_deployment: staging_database.collection("ExamCreatorExamDeployment"),
+        // Should not be used
+        exam_creator_deploy_request: staging_database.collection("ExamCreatorDeployRequest"),
+        // Should not be used
+        exam_creator_tag: staging_database.collection("ExamCreatorTag"),
         exam: staging_database.collection("ExamEnvironmentExam"),
         exam_attempt: staging_database.collection("ExamEnvironmentExamAttempt"),
         exam_environment_challenge: staging_database.collection("ExamEnvironmentChallenge"),
         generated_exam: staging_database.collection("ExamEnvironmentGeneratedExam"),
+        generated_exam_seed: staging_database.collection("ExamCreatorGeneratedExamSeed"),
+        // Should not be used
+        generation_job: staging_database.collection("ExamCreatorGenerationJob"),
+        // Should not be used
+        schedule: staging_database.collection("ExamCreatorSchedule"),
         // Should not be used
         exam_creator_user: staging_database.collection("ExamCreatorUser"),
         // Should not be used
@@ -93,13 +120,28 @@ pub async fn app(env_vars: EnvVars) -> Result<Router, Error> {
             .collection("ExamEnvironmentExamModeration"),
     };
 
+    // Revision numbers are unique per exam, so concurrent saves cannot record the same revision
+    production_database
+        .exam_creator_exam_revision
+        .create_index(
+            IndexModel::builder()
+                .keys(doc! {"examId": 1, "revision": -1})
+                .options(IndexOptions::builder().unique(true).build())
+                .build(),
+        )
+        .await?;
+
+    // Unsaved edits from before the server restarted
+    let exams = database::draft::load_drafts(&production_database).await?;
     let client_sync = Arc::new(Mutex::new(ClientSync {
         users: Vec::new(),
-        exams: Vec::new(),
+        exams,
     }));
 
     let exam_metrics_by_id_cache = Arc::new(Mutex::new(vec![]));
     let attempt_metrics_cache = Arc::new(Mutex::new(Cache::new()));
+    let (generation_jobs, _) = tokio::sync::broadcast::channel(64);
+    let drift_report = Arc::new(Mutex::new(None));
 
     let supabase_url = &env_vars.supabase_url;
     let supabase_key = &env_vars.supabase_key;
@@ -114,12 +156,35 @@ pub async fn app(env_vars: EnvVars) -> Result<Router, Error> {
         env_vars: env_vars.clone(),
         exam_metrics_by_id_cache,
         attempt_metrics_cache,
+        generation_jobs,
+        drift_report,
     };
 
     tokio::spawn(state::cleanup_online_users(
         Arc::clone(&server_state.client_sync),
         std::time::Duration::from_secs(5 * 60),
     ));
+    tokio::spawn(database::draft::persist_drafts(
+        server_state.production_database.clone(),
+        Arc::clone(&server_state.client_sync),
+        std::time::Duration::from_secs(1),
+    ));
+    tokio::spawn(jobs::run_generation_jobs(
+        server_state.production_database.clone(),
+        server_state.staging_database.clone(),
+        server_state.generation_jobs.clone(),
+        std::time::Duration::from_secs(1),
+    ));
+    tokio::spawn(scheduler::run_schedules(
+        server_state.clone(),
+        std::time::Duration::from_secs(10),
+    ));
+    tokio::spawn(drift::run_drift_reports(
+        server_state.production_database.clone(),
+        server_state.staging_database.clone(),
+        Arc::clone(&server_state.drift_report),
+        std::time::Duration::from_secs(60 * 60),
+    ));
 
     let cors = CorsLayer::new()
         .allow_methods([
@@ -169,8 +234,41 @@ pub async fn app(env_vars: EnvVars) -> Result<Router, Error> {
     let app = app
         .route("/api/exams", get(routes::exams::get_exams))
         .route("/api/exams", post(routes::exams::post_exam))
+        .route("/api/exams/drift", get(routes::exams::get_exams_drift))
         .route("/api/exams/{exam_id}", get(routes::exams::get_exam_by_id))
         .route("/api/exams/{exam_id}", put(routes::exams::put_exam))
+        .route(
+            "/api/exams/{exam_id}/diff",
+            get(routes::exams::get_exam_diff),
+        )
+        .route(
+            "/api/exams/{exam_id}/deployments",
+            get(routes::deployments::get_deployments_by_exam_id),
+        )
+        .route(
+            "/api/exams/{exam_id}/deployments/{deployment_id}",
+            get(routes::deployments::get_deployment_by_id),
+        )
+        .route(
+            "/api/exams/{exam_id}/deployments/{deployment_id}/rollback",
+            put(routes::deployments::put_rollback_deployment),
+        )
+        .route(
+            "/api/exams/{exam_id}/revisions",
+            get(routes::revisions::get_revisions_by_exam_id),
+        )
+        .route(
+            "/api/exams/{exam_id}/revisions/{revision_id}",
+            get(routes::revisions::get_revision_by_id),
+        )
+        .route(
+            "/api/exams/{exam_id}/revisions/{revision_id}/restore",
+            put(routes::revisions::put_restore_revision),
+        )
+        .route(
+            "/api/exams/{exam_id}/draft",
+            get(routes::drafts::get_draft_by_exam_id).delete(routes::discard_exam_state_by_id),
+        )
         .route(
             "/api/exams/{exam_id}/seed/staging",
             put(routes::exams::put_exam_by_id_to_staging),
@@ -179,15 +277,101 @@ pub async fn app(env_vars: EnvVars) -> Result<Router, Error> {
             "/api/exams/{exam_id}/seed/production",
             put(routes::exams::put_exam_by_id_to_production),
         )
+        .route(
+            "/api/exams/{exam_id}/promote",
+            put(routes::exams::put_promote_exam_by_id),
+        )
+        .route(
+            "/api/exams/{exam_id}/deploy-requests",
+            post(routes::deploy_requests::post_deploy_request),
+        )
+        .route(
+            "/api/deploy-requests",
+            get(routes::deploy_requests::get_deploy_requests),
+        )
+        .route(
+            "/api/deploy-requests/{deploy_request_id}",
+            get(routes::deploy_requests::get_deploy_request_by_id),
+        )
+        .route(
+            "/api/deploy-requests/{deploy_request_id}/approve",
+            put(routes::deploy_requests::put_approve_deploy_request),
+        )
+        .route(
+            "/api/deploy-requests/{deploy_request_id}/reject",
+            put(routes::deploy_requests::put_reject_deploy_request),
+        )
+        .route(
+            "/api/deploy-requests/{deploy_request_id}/execute",
+            put(routes::deploy_requests::put_execute_deploy_request),
+        )
         .route(
             "/api/exams/{exam_id}/generations/{database_environment}",
-            get(routes::exams::get_generations_by_exam_id_with_database_environment)
-                .put(routes::exams::put_generations_by_exam_id_with_database_environment),
+            get(routes::exams::get_generations_by_exam_id_with_database_environment),
+        )
+        .route(
+            "/api/exams/{exam_id}/generations/{database_environment}/metrics",
+            get(routes::metrics::get_generation_metrics_by_exam_id),
+        )
+        .route(
+            "/api/exams/{exam_id}/generations/{database_environment}/metrics/overlaps",
+            get(routes::metrics::get_generation_overlaps_by_exam_id),
+        )
+        .route(
+            "/api/exams/{exam_id}/generations/{database_environment}/orphans",
+            get(routes::exams::get_orphaned_generations_by_exam_id),
+        )
+        .route(
+            "/api/exams/{exam_id}/generations/{database_environment}/deprecate",
+            put(routes::exams::put_deprecate_generations_by_exam_id),
+        )
+        .route(
+            "/api/exams/{exam_id}/generations/{database_environment}/{generated_exam_id}/reproduce",
+            get(routes::exams::get_reproduced_generation_by_id),
+        )
+        .route(
+            "/api/generation-jobs",
+            get(routes::generation_jobs::get_generation_jobs)
+                .post(routes::generation_jobs::post_generation_job),
+        )
+        .route(
+            "/api/generation-jobs/{job_id}",
+            get(routes::generation_jobs::get_generation_job_by_id),
+        )
+        .route(
+            "/api/generation-jobs/{job_id}/cancel",
+            put(routes::generation_jobs::put_cancel_generation_job),
+        )
+        .route(
+            "/api/generation-jobs/{job_id}/resume",
+            put(routes::generation_jobs::put_resume_generation_job),
+        )
+        .route(
+            "/api/generation-jobs/{job_id}/progress",
+            get(routes::generation_jobs::get_generation_job_progress),
+        )
+        .route(
+            "/api/schedules",
+            get(routes::schedules::get_schedules).post(routes::schedules::post_schedule),
+        )
+        .route(
+            "/api/schedules/{schedule_id}",
+            get(routes::schedules::get_schedule_by_id),
+        )
+        .route(
+            "/api/schedules/{schedule_id}/cancel",
+            put(routes::schedules::put_cancel_schedule),
         )
         .route(
             "/api/exams/{exam_id}/config/validate",
             post(routes::exams::post_validate_config_by_exam_id),
         )
+        .route(
+            "/api/tags",
+            get(routes::tags::get_tags).post(routes::tags::post_tag),
+        )
+        .route("/api/tags/{tag_id}", delete(routes::tags::delete_tag))
+        .route("/api/tags/rename", put(routes::tags::put_rename_tags))
         // .route("/api/attempts", get(routes::attempts::get_attempts))
         .route(
             "/api/metrics/exams",
@@ -238,6 +422,10 @@ pub async fn app(env_vars: EnvVars) -> Result<Router, Error> {
             "/api/users/session/settings",
             put(routes::users::put_user_settings),
         )
+        .route(
+            "/api/drafts",
+            get(routes::drafts::get_drafts).delete(routes::drafts::delete_drafts_by_editor),
+        )
         .route(
             "/api/state/exams/{exam_id}",
             put(routes::discard_exam_state_by_id),
@@ -0,0 +1,212 @@
+use std::collections::{HashMap, HashSet};
+
+use mongodb::bson::oid::ObjectId;
+use serde::{Deserialize, Serialize};
+
+use crate::database::prisma::{ExamCreatorExam, ExamEnvironmentGeneratedExam};
+
+/// A generation which references question sets, questions, or answers no longer in its exam.
+///
+/// Attempts made with the generation silently lose the orphaned content.
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct OrphanedGeneration {
+    pub generated_exam_id: ObjectId,
+    pub question_sets: Vec<ObjectId>,
+    pub questions: Vec<ObjectId>,
+    pub answers: Vec<ObjectId>,
+}
+
+/// What to do with live generations orphaned by deploying an exam
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum OrphanedGenerationsAction {
+    /// Refuse to deploy the exam
+    #[default]
+    Block,
+    /// Deprecate the orphaned generations, then deploy the exam
+    Deprecate,
+    /// Deploy the exam, keeping the orphaned generations live
+    Ignore,
+}
+
+/// Finds the generations with references the exam does not contain.
+///
+/// References are resolved the same way as `config::construct_attempt`:
+/// questions within their question set, and answers within their question.
+pub fn orphaned_generations(
+    exam: &ExamCreatorExam,
+    generated_exams: &[ExamEnvironmentGeneratedExam],
+) -> Vec<OrphanedGeneration> {
+    // Answer ids by question id, by question set id
+    let content: HashMap<ObjectId, HashMap<ObjectId, HashSet<ObjectId>>> = exam
+        .question_sets
+        .iter()
+        .map(|question_set| {
+            let questions = question_set
+                .questions
+                .iter()
+                .map(|question| (question.id, question.answers.iter().map(|a| a.id).collect()))
+                .collect();
+            (question_set.id, questions)
+        })
+        .collect();
+
+    let mut orphaned_generations = vec![];
+    for generated_exam in generated_exams {
+        let mut orphaned = OrphanedGeneration {
+            generated_exam_id: generated_exam.id,
+            question_sets: vec![],
+            questions: vec![],
+            answers: vec![],
+        };
+
+        for generated_question_set in &generated_exam.question_sets {
+            let Some(questions) = content.get(&generated_question_set.id) else {
+                orphaned.question_sets.push(generated_question_set.id);
+                continue;
+            };
+
+            for generated_question in &generated_question_set.questions {
+                let Some(answers) = questions.get(&generated_question.id) else {
+                    orphaned.questions.push(generated_question.id);
+                    continue;
+                };
+
+                orphaned.answers.extend(
+                    generated_question
+                        .answers
+                        .iter()
+                        .filter(|answer_id| !answers.contains(answer_id)),
+                );
+            }
+        }
+
+        if !orphaned.question_sets.is_empty()
+            || !orphaned.questions.is_empty()
+            || !orphaned.answers.is_empty()
+        {
+            orphaned_generations.push(orphaned);
+        }
+    }
+
+    orphaned_generations
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::database::prisma::{
+        ExamEnvironmentAnswer, ExamEnvironmentGeneratedMultipleChoiceQuestion,
+        ExamEnvironmentGeneratedQuestionSet, ExamEnvironmentMultipleChoiceQuestion,
+        ExamEnvironmentQuestionSet, ExamEnvironmentQuestionType,
+    };
+
+    fn exam() -> ExamCreatorExam {
+        let question = |answers: usize| ExamEnvironmentMultipleChoiceQuestion {
+            id: ObjectId::new(),
+            text: "Question".to_string(),
+            tags: vec![],
+            audio: None,
+            answers: (0..answers)
+                .map(|i| ExamEnvironmentAnswer {
+                    id: ObjectId::new(),
+                    is_correct: i == 0,
+                    text: "Answer".to_string(),
+                })
+                .collect(),
+            deprecated: false,
+        };
+        ExamCreatorExam {
+            question_sets: (0..2)
+                .map(|_| ExamEnvironmentQuestionSet {
+                    id: ObjectId::new(),
+                    _type: ExamEnvironmentQuestionType::MultipleChoice,
+                    context: None,
+                    questions: vec![question(2), question(2)],
+                })
+                .collect(),
+            ..Default::default()
+        }
+    }
+
+    /// Generation with every question and answer of the exam
+    fn generated_exam(exam: &ExamCreatorExam) -> ExamEnvironmentGeneratedExam {
+        ExamEnvironmentGeneratedExam {
+            id: ObjectId::new(),
+            exam_id: exam.id,
+            question_sets: exam
+                .question_sets
+                .iter()
+                .map(|question_set| ExamEnvironmentGeneratedQuestionSet {
+                    id: question_set.id,
+                    questions: question_set
+                        .questions
+                        .iter()
+                        .map(|question| ExamEnvironmentGeneratedMultipleChoiceQuestion {
+                            id: question.id,
+                            answers: question.answers.iter().map(|a| a.id).collect(),
+                        })
+                        .collect(),
+                })
+                .collect(),
+            deprecated: false,
+            version: 1,
+        }
+    }
+
+    #[test]
+    fn generations_of_the_exam_are_not_orphaned() {
+        let exam = exam();
+        let generated_exams = [generated_exam(&exam), generated_exam(&exam)];
+
+        assert!(orphaned_generations(&exam, &generated_exams).is_empty());
+    }
+
+    #[test]
+    fn removed_content_is_orphaned() {
+        let mut exam = exam();
+        let generated_exam = generated_exam(&exam);
+        let removed_question_set = exam.question_sets.remove(0).id;
+        let removed_question = exam.question_sets[0].questions.remove(0).id;
+        let removed_answer = exam.question_sets[0].questions[0].answers.remove(1).id;
+
+        let orphaned = orphaned_generations(&exam, std::slice::from_ref(&generated_exam));
+
+        assert_eq!(orphaned.len(), 1);
+        assert_eq!(orphaned[0].generated_exam_id, generated_exam.id);
+        assert_eq!(orphaned[0].question_sets, vec![removed_question_set]);
+        assert_eq!(orphaned[0].questions, vec![removed_question]);
+        assert_eq!(orphaned[0].answers, vec![removed_answer]);
+    }
+
+    #[test]
+    fn question_moved_to_another_question_set_is_orphaned() {
+        let mut exam = exam();
+        let generated_exam = generated_exam(&exam);
+        let moved = exam.question_sets[0].questions.remove(0);
+        let moved_id = moved.id;
+        exam.question_sets[1].questions.push(moved);
+
+        let orphaned = orphaned_generations(&exam, &[generated_exam]);
+
+        assert_eq!(orphaned.len(), 1);
+        assert!(orphaned[0].question_sets.is_empty());
+        assert_eq!(orphaned[0].questions, vec![moved_id]);
+    }
+
+    #[test]
+    fn added_content_orphans_nothing() {
+        let mut exam = exam();
+        let generated_exam = generated_exam(&exam);
+        exam.question_sets[0].questions[0]
+            .answers
+            .push(ExamEnvironmentAnswer {
+                id: ObjectId::new(),
+                is_correct: false,
+                text: "Added".to_string(),
+            });
+
+        assert!(orphaned_generations(&exam, &[generated_exam]).is_empty());
+    }
+}
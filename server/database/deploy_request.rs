@@ -0,0 +1,87 @@
+use mongodb::bson::{DateTime, oid::ObjectId};
+use serde::{Deserialize, Serialize};
+
+use crate::database::prisma;
+
+/// A request to deploy a revision of an exam to production, which another user must approve.
+///
+/// The request can instead be to roll production back to an earlier deployment of the exam,
+/// or to deprecate or undeprecate the production exam, which a schedule does once approved.
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct ExamCreatorDeployRequest {
+    #[serde(rename = "_id")]
+    pub id: ObjectId,
+    /// Foreign key to `ExamCreatorExam`
+    pub exam_id: ObjectId,
+    /// Foreign key to `ExamCreatorExamRevision`
+    pub revision_id: ObjectId,
+    pub revision: i64,
+    pub status: DeployRequestStatus,
+    /// Foreign key to `ExamCreatorUser`
+    pub requester_id: ObjectId,
+    pub requester_name: String,
+    pub requester_email: String,
+    /// Every change of status, oldest first, starting with the request being made
+    pub transitions: Vec<DeployRequestTransition>,
+    /// Foreign key to the production `ExamCreatorExamDeployment` rolled back to, if a rollback
+    #[serde(default)]
+    pub rollback_deployment_id: Option<ObjectId>,
+    /// `deprecated` to set on the production exam, if the request is to deprecate or undeprecate it
+    #[serde(default)]
+    pub deprecated: Option<bool>,
+    /// Foreign key to `ExamCreatorExamDeployment`, once deployed
+    pub deployment_id: Option<ObjectId>,
+    pub created_at: DateTime,
+    pub updated_at: DateTime,
+}
+
+impl ExamCreatorDeployRequest {
+    /// What the request changes in production, such as "deploy revision 3"
+    pub fn describe(&self) -> String {
+        match (self.deprecated, self.rollback_deployment_id) {
+            (Some(true), _) => "deprecate the exam".to_string(),
+            (Some(false), _) => "undeprecate the exam".to_string(),
+            (None, Some(deployment_id)) => format!("roll back to deployment {deployment_id}"),
+            (None, None) => format!("deploy revision {}", self.revision),
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, Serialize, Deserialize, PartialEq, Eq)]
+pub enum DeployRequestStatus {
+    Pending,
+    Approved,
+    Rejected,
+    Deployed,
+}
+
+/// A change of a deploy request's status, and who made it
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct DeployRequestTransition {
+    pub status: DeployRequestStatus,
+    /// Foreign key to `ExamCreatorUser`
+    pub actor_id: ObjectId,
+    pub actor_name: String,
+    pub actor_email: String,
+    pub comment: Option<String>,
+    pub created_at: DateTime,
+}
+
+impl DeployRequestTransition {
+    pub fn new(
+        status: DeployRequestStatus,
+        actor: &prisma::ExamCreatorUser,
+        comment: Option<String>,
+    ) -> Self {
+        Self {
+            status,
+            actor_id: actor.id,
+            actor_name: actor.name.clone(),
+            actor_email: actor.email.clone(),
+            comment,
+            created_at: DateTime::now(),
+        }
+    }
+}
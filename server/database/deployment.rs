@@ -0,0 +1,56 @@
+use mongodb::bson::{DateTime, oid::ObjectId};
+use serde::{Deserialize, Serialize};
+
+use crate::database::prisma;
+
+/// A record of an exam being deployed to a database environment, by seeding or rolling back.
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct ExamCreatorExamDeployment {
+    #[serde(rename = "_id")]
+    pub id: ObjectId,
+    /// Foreign key to `ExamCreatorExam`
+    pub exam_id: ObjectId,
+    pub database_environment: prisma::ExamCreatorDatabaseEnvironment,
+    /// Foreign key to `ExamCreatorUser`
+    pub actor_id: ObjectId,
+    pub actor_name: String,
+    pub actor_email: String,
+    pub created_at: DateTime,
+    /// Revision the deployed exam was saved as, if the exam has revisions
+    pub revision_id: Option<ObjectId>,
+    /// Deployment this deployment rolled back to, if it was created by a rollback
+    pub rolled_back_to: Option<ObjectId>,
+    /// Approved deploy request the deployment was made for, if deployed to production
+    #[serde(default)]
+    pub deploy_request_id: Option<ObjectId>,
+    /// Exam deployed
+    pub exam: prisma::ExamCreatorExam,
+    /// Challenge mappings deployed with the exam.
+    ///
+    /// `None` if the deployment kept the database's mappings,
+    /// as seeding production does, where mappings are edited in place.
+    pub challenges: Option<Vec<prisma::ExamEnvironmentChallenge>>,
+    /// Exam deployed before this deployment, if the exam was already deployed
+    pub previous_exam: Option<prisma::ExamEnvironmentExam>,
+}
+
+/// An `ExamCreatorExamDeployment` without the exam documents and challenge mappings.
+///
+/// Used for listing deployments, where they are projected out.
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct ExamCreatorExamDeploymentSummary {
+    #[serde(rename = "_id")]
+    pub id: ObjectId,
+    pub exam_id: ObjectId,
+    pub database_environment: prisma::ExamCreatorDatabaseEnvironment,
+    pub actor_id: ObjectId,
+    pub actor_name: String,
+    pub actor_email: String,
+    pub created_at: DateTime,
+    pub revision_id: Option<ObjectId>,
+    pub rolled_back_to: Option<ObjectId>,
+    #[serde(default)]
+    pub deploy_request_id: Option<ObjectId>,
+}
@@ -0,0 +1,246 @@
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use futures_util::TryStreamExt;
+use mongodb::bson::{DateTime, doc, oid::ObjectId};
+use serde::{Deserialize, Serialize};
+use tracing::{error, info};
+
+use crate::{
+    database::{Database, prisma},
+    errors::Error,
+    merge::SyncedExam,
+    state::ClientSync,
+};
+
+/// Unsaved edits to an exam, persisted so they survive a server restart.
+///
+/// There is at most one draft per exam, shared by all of its editors.
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct ExamCreatorExamDraft {
+    /// Same as the exam's id
+    #[serde(rename = "_id")]
+    pub id: ObjectId,
+    /// Emails of the users who edited the exam since it was last saved
+    pub editors: Vec<String>,
+    /// Email of the user who last edited the exam
+    pub updated_by: String,
+    pub updated_at: DateTime,
+    pub exam: prisma::ExamCreatorExam,
+}
+
+/// Unsaved edits of an exam in `ClientSync.exams`
+#[derive(Clone, Debug, Default)]
+pub struct DraftState {
+    /// Emails of the users who edited the exam since it was last saved
+    pub editors: Vec<String>,
+    /// Email of the user who last edited the exam, and when
+    ///
+    /// `None` if the exam has no unsaved edits
+    pub updated: Option<(String, DateTime)>,
+    /// When the draft first changed since it was last persisted
+    ///
+    /// `None` if the draft has not changed
+    pub dirty_since: Option<DateTime>,
+}
+
+impl DraftState {
+    pub fn edited(&mut self, author: &str) {
+        if !self.editors.iter().any(|e| e == author) {
+            self.editors.push(author.to_string());
+        }
+        self.updated = Some((author.to_string(), DateTime::now()));
+        self.changed();
+    }
+
+    /// The exam was saved or discarded, so the draft is no longer needed
+    pub fn cleared(&mut self) {
+        let dirty_since = self.dirty_since;
+        *self = DraftState {
+            dirty_since,
+            ..Default::default()
+        };
+        self.changed();
+    }
+
+    /// The draft is to be persisted
+    pub fn changed(&mut self) {
+        self.dirty_since.get_or_insert_with(DateTime::now);
+    }
+
+    /// Whether the changed draft is to be persisted at `now`.
+    ///
+    /// Drafts are persisted once edits stop for `debounce`,
+    /// or once `max_wait` has passed since the draft first changed, so continuous edits are still persisted.
+    fn is_due(&self, now: DateTime, debounce: Duration, max_wait: Duration) -> bool {
+        let Some(dirty_since) = self.dirty_since else {
+            return false;
+        };
+        let elapsed = |since: DateTime, duration: Duration| {
+            now.timestamp_millis() - since.timestamp_millis() >= duration.as_millis() as i64
+        };
+        match &self.updated {
+            Some((_, updated_at)) => {
+                elapsed(*updated_at, debounce) || elapsed(dirty_since, max_wait)
+            }
+            None => true,
+        }
+    }
+}
+
+impl ExamCreatorExamDraft {
+    /// Returns `None` if the exam has no unsaved edits
+    pub fn new(synced_exam: &SyncedExam) -> Option<Self> {
+        let (updated_by, updated_at) = synced_exam.draft.updated.clone()?;
+        Some(Self {
+            id: synced_exam.exam.id,
+            editors: synced_exam.draft.editors.clone(),
+            updated_by,
+            updated_at,
+            exam: synced_exam.exam.clone(),
+        })
+    }
+}
+
+impl From<ExamCreatorExamDraft> for SyncedExam {
+    fn from(draft: ExamCreatorExamDraft) -> Self {
+        let mut synced_exam = SyncedExam::new(draft.exam);
+        synced_exam.draft = DraftState {
+            editors: draft.editors,
+            updated: Some((draft.updated_by, draft.updated_at)),
+            dirty_since: None,
+        };
+        synced_exam
+    }
+}
+
+/// Loads all persisted drafts, to be edited again
+pub async fn load_drafts(database: &Database) -> Result<Vec<SyncedExam>, Error> {
+    let drafts: Vec<ExamCreatorExamDraft> = database
+        .exam_creator_exam_draft
+        .find(doc! {})
+        .await?
+        .try_collect()
+        .await?;
+
+    info!("Loaded {} exam drafts", drafts.len());
+    Ok(drafts.into_iter().map(SyncedExam::from).collect())
+}
+
+pub async fn persist_drafts(
+    database: Database,
+    client_sync: Arc<Mutex<ClientSync>>,
+    // How often to check for changed drafts
+    interval: Duration,
+) {
+    // Length of time without edits after which a draft is persisted
+    let debounce = Duration::from_secs(2);
+    // Length of time after which a changed draft is persisted, even if it is still being edited
+    let max_wait = Duration::from_secs(10);
+
+    loop {
+        tokio::time::sleep(interval).await;
+
+        // Lock must not be held across await.
+        let changed: Vec<(ObjectId, DateTime, Option<ExamCreatorExamDraft>)> = {
+            let mut client_sync = client_sync.lock().unwrap();
+            let now = DateTime::now();
+
+            client_sync
+                .exams
+                .iter_mut()
+                .filter(|synced_exam| synced_exam.draft.is_due(now, debounce, max_wait))
+                .filter_map(|synced_exam| {
+                    let dirty_since = synced_exam.draft.dirty_since.take()?;
+                    Some((
+                        synced_exam.exam.id,
+                        dirty_since,
+                        ExamCreatorExamDraft::new(synced_exam),
+                    ))
+                })
+                .collect()
+        };
+
+        for (exam_id, dirty_since, draft) in changed {
+            let res = match draft {
+                Some(draft) => database
+                    .exam_creator_exam_draft
+                    .replace_one(doc! { "_id": exam_id }, &draft)
+                    .upsert(true)
+                    .await
+                    .map(|_| ()),
+                None => database
+                    .exam_creator_exam_draft
+                    .delete_one(doc! { "_id": exam_id })
+                    .await
+                    .map(|_| ()),
+            };
+
+            if let Err(e) = res {
+                error!("Failed to persist draft of exam {exam_id}: {e}");
+                // Retry on the next interval
+                let mut client_sync = client_sync.lock().unwrap();
+                if let Some(synced_exam) = client_sync
+                    .exams
+                    .iter_mut()
+                    .find(|synced_exam| synced_exam.exam.id == exam_id)
+                {
+                    // Any later change is after `dirty_since`
+                    synced_exam.draft.dirty_since = Some(dirty_since);
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const DEBOUNCE: Duration = Duration::from_secs(2);
+    const MAX_WAIT: Duration = Duration::from_secs(10);
+
+    fn at(seconds: i64) -> DateTime {
+        DateTime::from_millis(seconds * 1000)
+    }
+
+    fn draft(dirty_since: i64, updated_at: i64) -> DraftState {
+        DraftState {
+            editors: vec!["alice".to_string()],
+            updated: Some(("alice".to_string(), at(updated_at))),
+            dirty_since: Some(at(dirty_since)),
+        }
+    }
+
+    #[test]
+    fn unchanged_draft_is_not_due() {
+        let draft = DraftState::default();
+
+        assert!(!draft.is_due(at(100), DEBOUNCE, MAX_WAIT));
+    }
+
+    #[test]
+    fn draft_is_due_once_edits_stop() {
+        let draft = draft(0, 5);
+
+        assert!(!draft.is_due(at(6), DEBOUNCE, MAX_WAIT));
+        assert!(draft.is_due(at(7), DEBOUNCE, MAX_WAIT));
+    }
+
+    #[test]
+    fn continuously_edited_draft_is_due_after_max_wait() {
+        let draft = draft(0, 10);
+
+        assert!(!draft.is_due(at(9), DEBOUNCE, MAX_WAIT));
+        assert!(draft.is_due(at(10), DEBOUNCE, MAX_WAIT));
+    }
+
+    #[test]
+    fn cleared_draft_is_due() {
+        let mut draft = draft(0, 10);
+        draft.cleared();
+
+        assert!(draft.is_due(at(10), DEBOUNCE, MAX_WAIT));
+    }
+}
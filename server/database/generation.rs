@@ -0,0 +1,90 @@
+use mongodb::bson::{DateTime, oid::ObjectId};
+use serde::{Deserialize, Serialize};
+
+use crate::{database::prisma, generate::ExposureDistribution};
+
+/// How an `ExamEnvironmentGeneratedExam` was generated, so the generation can be reproduced.
+///
+/// Recorded in the same database as the generated exam.
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct ExamCreatorGeneratedExamSeed {
+    /// Same as the generated exam's id
+    #[serde(rename = "_id")]
+    pub id: ObjectId,
+    /// Foreign key to `ExamCreatorExam`
+    pub exam_id: ObjectId,
+    /// Revision of the exam the generation was made from
+    ///
+    /// `None` if the exam had not been saved since revisions were introduced
+    pub revision: Option<i64>,
+    pub seed: u32,
+    /// Foreign key to `ExamCreatorGenerationJob`
+    #[serde(default)]
+    pub job_id: Option<ObjectId>,
+    /// Position of the generation in its job
+    #[serde(default)]
+    pub job_index: Option<u32>,
+    /// Number of its job's earlier generations the generation was balanced against,
+    /// in addition to the job's `balanced_against`
+    ///
+    /// `None` if the generation was not balanced.
+    #[serde(default)]
+    pub balanced_before: Option<u32>,
+    pub created_at: DateTime,
+}
+
+/// A request to generate a number of exams, worked through in the background.
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct ExamCreatorGenerationJob {
+    #[serde(rename = "_id")]
+    pub id: ObjectId,
+    /// Foreign key to `ExamCreatorExam`
+    pub exam_id: ObjectId,
+    /// Database the exams are generated into
+    pub database_environment: prisma::ExamCreatorDatabaseEnvironment,
+    /// Revision of the exam to generate from
+    ///
+    /// `None` if the exam had not been saved since revisions were introduced, in which case the saved exam is used.
+    pub revision: Option<i64>,
+    /// Number of exams to generate
+    pub count: u32,
+    /// Number of exams generated so far
+    pub generated: u32,
+    /// Seed of the first generation, incremented for each subsequent generation
+    pub seed: Option<u32>,
+    pub balance_exposure: bool,
+    /// Live generations of the exam when the job was created, which a balanced job's exposure is counted from
+    pub balanced_against: Vec<ObjectId>,
+    pub status: GenerationJobStatus,
+    /// Why the job failed
+    pub error: Option<String>,
+    /// Exposure across the exam's live generations, once a balanced job has completed
+    pub exposure: Option<ExposureDistribution>,
+    /// Email of the user who created the job
+    pub created_by: String,
+    pub created_at: DateTime,
+    pub updated_at: DateTime,
+}
+
+#[derive(Clone, Copy, Debug, Serialize, Deserialize, PartialEq, Eq)]
+pub enum GenerationJobStatus {
+    Queued,
+    Running,
+    Completed,
+    Failed,
+    Cancelled,
+}
+
+impl GenerationJobStatus {
+    /// Whether the job will make no more progress, unless resumed
+    pub fn is_finished(&self) -> bool {
+        matches!(
+            self,
+            GenerationJobStatus::Completed
+                | GenerationJobStatus::Failed
+                | GenerationJobStatus::Cancelled
+        )
+    }
+}
@@ -3,16 +3,33 @@ use mongodb::Collection;
 
 use crate::state::{Activity, ServerState, User};
 
+pub mod deploy_request;
+pub mod deployment;
+pub mod draft;
+pub mod generation;
 pub mod prisma;
+pub mod revision;
+pub mod schedule;
+pub mod tag;
 
 #[derive(Clone, Debug)]
 pub struct Database {
+    /// Client of the database, for sessions
+    pub client: mongodb::Client,
     pub user: Collection<Document>,
     pub exam_creator_exam: Collection<prisma::ExamCreatorExam>,
+    pub exam_creator_exam_revision: Collection<revision::ExamCreatorExamRevision>,
+    pub exam_creator_exam_draft: Collection<draft::ExamCreatorExamDraft>,
+    pub exam_creator_exam_deployment: Collection<deployment::ExamCreatorExamDeployment>,
+    pub exam_creator_deploy_request: Collection<deploy_request::ExamCreatorDeployRequest>,
+    pub exam_creator_tag: Collection<tag::ExamCreatorTag>,
     pub exam: Collection<prisma::ExamEnvironmentExam>,
     pub exam_environment_challenge: Collection<prisma::ExamEnvironmentChallenge>,
     pub exam_attempt: Collection<prisma::ExamEnvironmentExamAttempt>,
     pub generated_exam: Collection<prisma::ExamEnvironmentGeneratedExam>,
+    pub generated_exam_seed: Collection<generation::ExamCreatorGeneratedExamSeed>,
+    pub generation_job: Collection<generation::ExamCreatorGenerationJob>,
+    pub schedule: Collection<schedule::ExamCreatorSchedule>,
     pub exam_creator_user: Collection<prisma::ExamCreatorUser>,
     pub exam_creator_session: Collection<prisma::ExamCreatorSession>,
     pub exam_environment_exam_moderation: Collection<prisma::ExamEnvironmentExamModeration>,
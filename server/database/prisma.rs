@@ -49,6 +49,51 @@ impl Default for ExamCreatorExam {
     }
 }
 
+/// `ExamCreatorExam` is kept in sync with `ExamEnvironmentExam`, so the conversion is lossless
+impl From<ExamCreatorExam> for ExamEnvironmentExam {
+    fn from(exam: ExamCreatorExam) -> Self {
+        let ExamCreatorExam {
+            id,
+            question_sets,
+            config,
+            prerequisites,
+            deprecated,
+            version,
+        } = exam;
+
+        ExamEnvironmentExam {
+            id,
+            question_sets,
+            config,
+            prerequisites,
+            deprecated,
+            version,
+        }
+    }
+}
+
+impl From<ExamEnvironmentExam> for ExamCreatorExam {
+    fn from(exam: ExamEnvironmentExam) -> Self {
+        let ExamEnvironmentExam {
+            id,
+            question_sets,
+            config,
+            prerequisites,
+            deprecated,
+            version,
+        } = exam;
+
+        ExamCreatorExam {
+            id,
+            question_sets,
+            config,
+            prerequisites,
+            deprecated,
+            version,
+        }
+    }
+}
+
 impl Default for ExamEnvironmentConfig {
     fn default() -> Self {
         ExamEnvironmentConfig {
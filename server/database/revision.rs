@@ -0,0 +1,179 @@
+use mongodb::ClientSession;
+use mongodb::bson::{DateTime, doc, oid::ObjectId};
+use serde::{Deserialize, Serialize};
+use tracing::warn;
+
+use crate::{
+    database::{Database, prisma},
+    errors::Error,
+};
+
+/// An immutable snapshot of an `ExamCreatorExam`, recorded every time the exam is saved.
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct ExamCreatorExamRevision {
+    #[serde(rename = "_id")]
+    pub id: ObjectId,
+    /// Foreign key to `ExamCreatorExam`
+    pub exam_id: ObjectId,
+    /// Incrementing number per exam, starting at 1
+    pub revision: i64,
+    /// Foreign key to `ExamCreatorUser`
+    pub author_id: ObjectId,
+    pub author_name: String,
+    pub author_email: String,
+    pub created_at: DateTime,
+    /// Revision this revision was restored from, if it was created by a restore
+    pub restored_from: Option<ObjectId>,
+    /// Full exam document at the time of the save
+    pub exam: prisma::ExamCreatorExam,
+}
+
+/// An `ExamCreatorExamRevision` without the exam document.
+///
+/// Used for listing revisions, where the exam is projected out.
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct ExamCreatorExamRevisionSummary {
+    #[serde(rename = "_id")]
+    pub id: ObjectId,
+    pub exam_id: ObjectId,
+    pub revision: i64,
+    pub author_id: ObjectId,
+    pub author_name: String,
+    pub author_email: String,
+    pub created_at: DateTime,
+    pub restored_from: Option<ObjectId>,
+}
+
+impl ExamCreatorExamRevision {
+    pub fn new(
+        exam: &prisma::ExamCreatorExam,
+        author: &prisma::ExamCreatorUser,
+        revision: i64,
+        restored_from: Option<ObjectId>,
+    ) -> Self {
+        Self {
+            id: ObjectId::new(),
+            exam_id: exam.id,
+            revision,
+            author_id: author.id,
+            author_name: author.name.clone(),
+            author_email: author.email.clone(),
+            created_at: DateTime::now(),
+            restored_from,
+            exam: exam.clone(),
+        }
+    }
+}
+
+/// Returns the latest revision for an exam, without the exam document.
+pub async fn latest_revision(
+    database: &Database,
+    exam_id: ObjectId,
+) -> Result<Option<ExamCreatorExamRevisionSummary>, Error> {
+    let latest = database
+        .exam_creator_exam_revision
+        .clone_with_type::<ExamCreatorExamRevisionSummary>()
+        .find_one(doc! { "examId": exam_id })
+        .projection(doc! { "exam": false })
+        .sort(doc! { "revision": -1 })
+        .await?;
+
+    Ok(latest)
+}
+
+/// Returns the latest revision number for an exam, or `0` if the exam has no revisions.
+pub async fn latest_revision_number(database: &Database, exam_id: ObjectId) -> Result<i64, Error> {
+    let latest = latest_revision(database, exam_id).await?;
+
+    Ok(latest.map(|r| r.revision).unwrap_or(0))
+}
+
+/// Saves the exam, and records it as the next revision.
+///
+/// Both are written in one transaction, so an exam is never saved without its revision.
+/// `base_version` is the version of the saved exam being replaced, or `None` to insert a new exam.
+/// Returns `None`, without writing anything, if the saved exam is no longer at `base_version`.
+pub async fn save_exam(
+    database: &Database,
+    exam: &prisma::ExamCreatorExam,
+    base_version: Option<i64>,
+    author: &prisma::ExamCreatorUser,
+    restored_from: Option<ObjectId>,
+) -> Result<Option<ExamCreatorExamRevision>, Error> {
+    let mut session = database.client.start_session().await?;
+    session.start_transaction().await?;
+
+    let revision = match write_save(
+        database,
+        &mut session,
+        exam,
+        base_version,
+        author,
+        restored_from,
+    )
+    .await
+    {
+        Ok(revision) => revision,
+        Err(e) => {
+            if let Err(abort_error) = session.abort_transaction().await {
+                warn!("Failed to abort save of exam {}: {abort_error}", exam.id);
+            }
+            return Err(e);
+        }
+    };
+    session.commit_transaction().await?;
+
+    Ok(revision)
+}
+
+async fn write_save(
+    database: &Database,
+    session: &mut ClientSession,
+    exam: &prisma::ExamCreatorExam,
+    base_version: Option<i64>,
+    author: &prisma::ExamCreatorUser,
+    restored_from: Option<ObjectId>,
+) -> Result<Option<ExamCreatorExamRevision>, Error> {
+    match base_version {
+        Some(base_version) => {
+            let update_result = database
+                .exam_creator_exam
+                .replace_one(doc! { "_id": exam.id, "version": base_version }, exam)
+                .session(&mut *session)
+                .await?;
+            if update_result.matched_count == 0 {
+                return Ok(None);
+            }
+        }
+        None => {
+            database
+                .exam_creator_exam
+                .insert_one(exam)
+                .session(&mut *session)
+                .await?;
+        }
+    }
+
+    // Read in the transaction after writing the exam,
+    // so a concurrent save of the exam conflicts rather than taking the same revision number
+    let latest = database
+        .exam_creator_exam_revision
+        .clone_with_type::<ExamCreatorExamRevisionSummary>()
+        .find_one(doc! { "examId": exam.id })
+        .projection(doc! { "exam": false })
+        .sort(doc! { "revision": -1 })
+        .session(&mut *session)
+        .await?;
+    let revision_number = latest.map(|r| r.revision).unwrap_or(0) + 1;
+    let revision = ExamCreatorExamRevision::new(exam, author, revision_number, restored_from);
+
+    database
+        .exam_creator_exam_revision
+        .insert_one(&revision)
+        .session(&mut *session)
+        .await?;
+
+    Ok(Some(revision))
+}
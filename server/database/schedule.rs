@@ -0,0 +1,65 @@
+use mongodb::bson::{DateTime, oid::ObjectId};
+use serde::{Deserialize, Serialize};
+
+use crate::{compatibility::OrphanedGenerationsAction, database::prisma};
+
+/// A change to an exam in a database environment, made by the scheduler at a set time.
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct ExamCreatorSchedule {
+    #[serde(rename = "_id")]
+    pub id: ObjectId,
+    /// Foreign key to `ExamCreatorExam`
+    pub exam_id: ObjectId,
+    pub database_environment: prisma::ExamCreatorDatabaseEnvironment,
+    pub action: ScheduleAction,
+    /// What to do with live generations orphaned by seeding the exam
+    pub orphaned_generations: OrphanedGenerationsAction,
+    /// Foreign key to `ExamCreatorDeployRequest`, which must be approved for the action to change production
+    pub deploy_request_id: Option<ObjectId>,
+    /// When the schedule is next run
+    pub run_at: DateTime,
+    pub status: ScheduleStatus,
+    /// Number of times the schedule has been run, including failures
+    pub attempts: u32,
+    /// Why the last run failed
+    pub error: Option<String>,
+    /// Foreign key to `ExamCreatorUser`, who the schedule is run as
+    pub created_by_id: ObjectId,
+    /// Email of the user who created the schedule
+    pub created_by: String,
+    pub created_at: DateTime,
+    pub updated_at: DateTime,
+}
+
+#[derive(Clone, Copy, Debug, Serialize, Deserialize, PartialEq, Eq)]
+pub enum ScheduleAction {
+    /// Seed the exam, as seeding from the exam creator does
+    Seed,
+    /// Set `deprecated` on the deployed `ExamEnvironmentExam`
+    Deprecate,
+    /// Unset `deprecated` on the deployed `ExamEnvironmentExam`
+    Undeprecate,
+}
+
+impl ScheduleAction {
+    /// `deprecated` set by the action, or `None` for seeding
+    pub fn deprecated(self) -> Option<bool> {
+        match self {
+            ScheduleAction::Seed => None,
+            ScheduleAction::Deprecate => Some(true),
+            ScheduleAction::Undeprecate => Some(false),
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, Serialize, Deserialize, PartialEq, Eq)]
+pub enum ScheduleStatus {
+    /// Waiting for `run_at`, including to be retried
+    Scheduled,
+    Running,
+    Completed,
+    /// Failed on every attempt
+    Failed,
+    Cancelled,
+}
@@ -0,0 +1,19 @@
+use mongodb::bson::{DateTime, oid::ObjectId};
+use serde::{Deserialize, Serialize};
+
+/// A tag registered for use in exam questions and tag configs.
+///
+/// Registered tags are listed with the tags in use, even before any exam uses them,
+/// and new tags are checked against them for near-duplicates.
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct ExamCreatorTag {
+    #[serde(rename = "_id")]
+    pub id: ObjectId,
+    /// Unique
+    pub name: String,
+    pub description: Option<String>,
+    /// Email of the user who registered the tag
+    pub created_by: String,
+    pub created_at: DateTime,
+}
@@ -0,0 +1,166 @@
+use mongodb::bson::oid::ObjectId;
+use serde::Serialize;
+
+use crate::{
+    compatibility::{self, OrphanedGeneration},
+    database::{
+        deployment::ExamCreatorExamDeployment,
+        prisma::{
+            ExamCreatorExam, ExamEnvironmentChallenge, ExamEnvironmentExam,
+            ExamEnvironmentGeneratedExam,
+        },
+    },
+    diff::{self, ExamDiff, SetDiff},
+};
+
+/// What deploying an exam changes in a database's `ExamEnvironmentExam` and `ExamEnvironmentChallenge` collections
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DeploymentPreview {
+    pub exam_id: ObjectId,
+    /// Whether the exam is not yet deployed to the database
+    pub created: bool,
+    /// Changes to the deployed exam. An exam not yet deployed is compared to an empty exam.
+    pub exam: ExamDiff,
+    /// Challenge ids mapped to the exam
+    pub challenges: SetDiff<ObjectId>,
+    /// Live generations in the database which reference content removed from the exam
+    pub orphaned_generations: Vec<OrphanedGeneration>,
+}
+
+/// What to deploy, and where it came from, as recorded in its `ExamCreatorExamDeployment`
+pub struct DeploymentPlan {
+    pub exam_creator_exam: ExamCreatorExam,
+    /// Replaces the database's mappings of the exam, if given
+    pub exam_environment_challenges: Option<Vec<ExamEnvironmentChallenge>>,
+    /// Generations inserted with the exam
+    pub generated_exams: Vec<ExamEnvironmentGeneratedExam>,
+    pub revision_id: Option<ObjectId>,
+    pub rolled_back_to: Option<ObjectId>,
+    /// Approved deploy request being deployed, required to deploy to production.
+    ///
+    /// A rollback's request must be to roll back to `rolled_back_to`.
+    pub deploy_request_id: Option<ObjectId>,
+}
+
+/// Compares the exam and its challenge mappings with those deployed.
+///
+/// `generated_exams` are the live generations of the exam in the database deployed to.
+pub fn preview_deployment(
+    exam: &ExamCreatorExam,
+    deployed_exam: Option<ExamEnvironmentExam>,
+    challenges: &[ExamEnvironmentChallenge],
+    deployed_challenges: &[ExamEnvironmentChallenge],
+    generated_exams: &[ExamEnvironmentGeneratedExam],
+) -> DeploymentPreview {
+    let created = deployed_exam.is_none();
+    let deployed_exam = deployed_exam.unwrap_or_else(|| {
+        ExamCreatorExam {
+            id: exam.id,
+            ..Default::default()
+        }
+        .into()
+    });
+
+    let challenge_ids = |challenges: &[ExamEnvironmentChallenge]| -> Vec<ObjectId> {
+        challenges.iter().map(|c| c.challenge_id).collect()
+    };
+
+    DeploymentPreview {
+        exam_id: exam.id,
+        created,
+        exam: diff::diff_exams(&deployed_exam, &exam.clone().into()),
+        challenges: diff::diff_set(
+            &challenge_ids(deployed_challenges),
+            &challenge_ids(challenges),
+        ),
+        orphaned_generations: compatibility::orphaned_generations(exam, generated_exams),
+    }
+}
+
+/// Plan to deploy the deployment's exam, and challenge mappings, again
+pub fn rollback_plan(
+    deployment: ExamCreatorExamDeployment,
+    deploy_request_id: Option<ObjectId>,
+) -> DeploymentPlan {
+    DeploymentPlan {
+        exam_creator_exam: deployment.exam,
+        exam_environment_challenges: deployment.challenges,
+        generated_exams: vec![],
+        revision_id: deployment.revision_id,
+        rolled_back_to: Some(deployment.id),
+        deploy_request_id,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use mongodb::bson::DateTime;
+
+    use super::*;
+    use crate::database::prisma::ExamCreatorDatabaseEnvironment;
+
+    fn deployment(challenges: Option<Vec<ExamEnvironmentChallenge>>) -> ExamCreatorExamDeployment {
+        let exam = ExamCreatorExam {
+            version: 3,
+            ..Default::default()
+        };
+        ExamCreatorExamDeployment {
+            id: ObjectId::new(),
+            exam_id: exam.id,
+            database_environment: ExamCreatorDatabaseEnvironment::Production,
+            actor_id: ObjectId::new(),
+            actor_name: "Camper".to_string(),
+            actor_email: "camper@freecodecamp.org".to_string(),
+            created_at: DateTime::now(),
+            revision_id: Some(ObjectId::new()),
+            rolled_back_to: None,
+            deploy_request_id: None,
+            previous_exam: None,
+            exam,
+            challenges,
+        }
+    }
+
+    #[test]
+    fn rollback_plan_deploys_the_deployment_again() {
+        let challenge = ExamEnvironmentChallenge {
+            id: ObjectId::new(),
+            exam_id: ObjectId::new(),
+            challenge_id: ObjectId::new(),
+            version: 1,
+        };
+        let deployment = deployment(Some(vec![challenge.clone()]));
+        let deploy_request_id = ObjectId::new();
+
+        let plan = rollback_plan(deployment.clone(), Some(deploy_request_id));
+
+        assert_eq!(plan.exam_creator_exam, deployment.exam);
+        assert_eq!(plan.exam_environment_challenges, Some(vec![challenge]));
+        assert!(plan.generated_exams.is_empty());
+        assert_eq!(plan.revision_id, deployment.revision_id);
+        assert_eq!(plan.rolled_back_to, Some(deployment.id));
+        assert_eq!(plan.deploy_request_id, Some(deploy_request_id));
+    }
+
+    #[test]
+    fn rollback_plan_keeps_mappings_the_deployment_kept() {
+        let deployment = deployment(None);
+
+        let plan = rollback_plan(deployment.clone(), None);
+
+        assert_eq!(plan.exam_environment_challenges, None);
+        assert_eq!(plan.rolled_back_to, Some(deployment.id));
+        assert_eq!(plan.deploy_request_id, None);
+    }
+
+    #[test]
+    fn rollback_plan_rolls_back_to_the_deployment_not_its_target() {
+        let mut rolled_back = deployment(None);
+        rolled_back.rolled_back_to = Some(ObjectId::new());
+
+        let plan = rollback_plan(rolled_back.clone(), None);
+
+        assert_eq!(plan.rolled_back_to, Some(rolled_back.id));
+    }
+}
@@ -0,0 +1,451 @@
+use mongodb::bson::oid::ObjectId;
+use serde::Serialize;
+
+use crate::database::prisma::{
+    ExamEnvironmentAnswer, ExamEnvironmentConfig, ExamEnvironmentExam,
+    ExamEnvironmentMultipleChoiceQuestion, ExamEnvironmentQuestionSet,
+    ExamEnvironmentQuestionSetConfig, ExamEnvironmentTagConfig,
+};
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub enum ChangeKind {
+    Added,
+    Removed,
+    Modified,
+}
+
+/// Structured difference between two exams.
+///
+/// Question sets, questions, and answers are matched by id. Ordering is not considered a change.
+#[derive(Debug, Clone, Serialize, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct ExamDiff {
+    /// Top-level fields such as `deprecated`
+    pub fields: Vec<FieldDiff>,
+    pub prerequisites: SetDiff<ObjectId>,
+    pub config: ConfigDiff,
+    pub question_sets: Vec<QuestionSetDiff>,
+}
+
+#[derive(Debug, Clone, Serialize, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct ConfigDiff {
+    /// Scalar fields such as `name` and `passingPercent`
+    pub fields: Vec<FieldDiff>,
+    pub tags: SetDiff<ExamEnvironmentTagConfig>,
+    pub question_sets: SetDiff<ExamEnvironmentQuestionSetConfig>,
+}
+
+#[derive(Debug, Clone, Serialize, PartialEq)]
+pub struct SetDiff<T> {
+    pub added: Vec<T>,
+    pub removed: Vec<T>,
+}
+
+/// A changed field, with the serialized value before and after the change
+#[derive(Debug, Clone, Serialize, PartialEq)]
+pub struct FieldDiff {
+    pub field: String,
+    pub from: serde_json::Value,
+    pub to: serde_json::Value,
+}
+
+/// Added and removed question sets include all of their questions and answers as added or removed
+#[derive(Debug, Clone, Serialize, PartialEq)]
+pub struct QuestionSetDiff {
+    pub id: ObjectId,
+    pub change: ChangeKind,
+    pub fields: Vec<FieldDiff>,
+    pub questions: Vec<QuestionDiff>,
+}
+
+#[derive(Debug, Clone, Serialize, PartialEq)]
+pub struct QuestionDiff {
+    pub id: ObjectId,
+    pub change: ChangeKind,
+    pub fields: Vec<FieldDiff>,
+    pub tags: SetDiff<String>,
+    pub answers: Vec<AnswerDiff>,
+}
+
+#[derive(Debug, Clone, Serialize, PartialEq)]
+pub struct AnswerDiff {
+    pub id: ObjectId,
+    pub change: ChangeKind,
+    pub fields: Vec<FieldDiff>,
+}
+
+impl<T> SetDiff<T> {
+    pub fn is_empty(&self) -> bool {
+        self.added.is_empty() && self.removed.is_empty()
+    }
+}
+
+/// Computes the changes needed to go from `from` to `to`
+pub fn diff_exams(from: &ExamEnvironmentExam, to: &ExamEnvironmentExam) -> ExamDiff {
+    let mut fields = vec![];
+    push_field(&mut fields, "deprecated", &from.deprecated, &to.deprecated);
+
+    ExamDiff {
+        fields,
+        prerequisites: diff_set(&from.prerequisites, &to.prerequisites),
+        config: diff_config(&from.config, &to.config),
+        question_sets: diff_by_id(
+            &from.question_sets,
+            &to.question_sets,
+            |qs| qs.id,
+            diff_question_set,
+        ),
+    }
+}
+
+fn diff_config(from: &ExamEnvironmentConfig, to: &ExamEnvironmentConfig) -> ConfigDiff {
+    let mut fields = vec![];
+    push_field(&mut fields, "name", &from.name, &to.name);
+    push_field(&mut fields, "note", &from.note, &to.note);
+    push_field(
+        &mut fields,
+        "totalTimeInS",
+        &from.total_time_in_s,
+        &to.total_time_in_s,
+    );
+    push_field(
+        &mut fields,
+        "retakeTimeInS",
+        &from.retake_time_in_s,
+        &to.retake_time_in_s,
+    );
+    push_field(
+        &mut fields,
+        "passingPercent",
+        &from.passing_percent,
+        &to.passing_percent,
+    );
+
+    ConfigDiff {
+        fields,
+        tags: diff_set(&from.tags, &to.tags),
+        question_sets: diff_set(&from.question_sets, &to.question_sets),
+    }
+}
+
+fn diff_question_set(
+    from: Option<&ExamEnvironmentQuestionSet>,
+    to: Option<&ExamEnvironmentQuestionSet>,
+) -> Option<QuestionSetDiff> {
+    let (id, change, fields) = match (from, to) {
+        (Some(from), Some(to)) => {
+            let mut fields = vec![];
+            push_field(&mut fields, "type", &from._type, &to._type);
+            push_field(&mut fields, "context", &from.context, &to.context);
+            (to.id, ChangeKind::Modified, fields)
+        }
+        (None, Some(to)) => (to.id, ChangeKind::Added, vec![]),
+        (Some(from), None) => (from.id, ChangeKind::Removed, vec![]),
+        (None, None) => return None,
+    };
+
+    let no_questions = vec![];
+    let questions = diff_by_id(
+        from.map(|qs| &qs.questions).unwrap_or(&no_questions),
+        to.map(|qs| &qs.questions).unwrap_or(&no_questions),
+        |q| q.id,
+        diff_question,
+    );
+
+    if change == ChangeKind::Modified && fields.is_empty() && questions.is_empty() {
+        return None;
+    }
+
+    Some(QuestionSetDiff {
+        id,
+        change,
+        fields,
+        questions,
+    })
+}
+
+fn diff_question(
+    from: Option<&ExamEnvironmentMultipleChoiceQuestion>,
+    to: Option<&ExamEnvironmentMultipleChoiceQuestion>,
+) -> Option<QuestionDiff> {
+    let (id, change, fields) = match (from, to) {
+        (Some(from), Some(to)) => {
+            let mut fields = vec![];
+            push_field(&mut fields, "text", &from.text, &to.text);
+            push_field(&mut fields, "audio", &from.audio, &to.audio);
+            push_field(&mut fields, "deprecated", &from.deprecated, &to.deprecated);
+            (to.id, ChangeKind::Modified, fields)
+        }
+        (None, Some(to)) => (to.id, ChangeKind::Added, vec![]),
+        (Some(from), None) => (from.id, ChangeKind::Removed, vec![]),
+        (None, None) => return None,
+    };
+
+    let no_tags = vec![];
+    let tags = diff_set(
+        from.map(|q| &q.tags).unwrap_or(&no_tags),
+        to.map(|q| &q.tags).unwrap_or(&no_tags),
+    );
+    let no_answers = vec![];
+    let answers = diff_by_id(
+        from.map(|q| &q.answers).unwrap_or(&no_answers),
+        to.map(|q| &q.answers).unwrap_or(&no_answers),
+        |a| a.id,
+        diff_answer,
+    );
+
+    if change == ChangeKind::Modified && fields.is_empty() && tags.is_empty() && answers.is_empty()
+    {
+        return None;
+    }
+
+    Some(QuestionDiff {
+        id,
+        change,
+        fields,
+        tags,
+        answers,
+    })
+}
+
+fn diff_answer(
+    from: Option<&ExamEnvironmentAnswer>,
+    to: Option<&ExamEnvironmentAnswer>,
+) -> Option<AnswerDiff> {
+    match (from, to) {
+        (Some(from), Some(to)) => {
+            let mut fields = vec![];
+            push_field(&mut fields, "text", &from.text, &to.text);
+            push_field(&mut fields, "isCorrect", &from.is_correct, &to.is_correct);
+            if fields.is_empty() {
+                return None;
+            }
+            Some(AnswerDiff {
+                id: to.id,
+                change: ChangeKind::Modified,
+                fields,
+            })
+        }
+        (None, Some(to)) => Some(AnswerDiff {
+            id: to.id,
+            change: ChangeKind::Added,
+            fields: vec![],
+        }),
+        (Some(from), None) => Some(AnswerDiff {
+            id: from.id,
+            change: ChangeKind::Removed,
+            fields: vec![],
+        }),
+        (None, None) => None,
+    }
+}
+
+/// Matches items by id, and diffs each pair.
+///
+/// Items only in `to` are passed as `(None, Some)`, and items only in `from` as `(Some, None)`.
+fn diff_by_id<T, D>(
+    from: &[T],
+    to: &[T],
+    id: impl Fn(&T) -> ObjectId,
+    diff: impl Fn(Option<&T>, Option<&T>) -> Option<D>,
+) -> Vec<D> {
+    let changed = from
+        .iter()
+        .filter_map(|f| diff(Some(f), to.iter().find(|t| id(t) == id(f))));
+    let added = to
+        .iter()
+        .filter(|t| !from.iter().any(|f| id(f) == id(t)))
+        .filter_map(|t| diff(None, Some(t)));
+
+    changed.chain(added).collect()
+}
+
+/// Items of `to` not in `from`, and items of `from` not in `to`
+pub fn diff_set<T: PartialEq + Clone>(from: &[T], to: &[T]) -> SetDiff<T> {
+    SetDiff {
+        added: to.iter().filter(|t| !from.contains(t)).cloned().collect(),
+        removed: from.iter().filter(|f| !to.contains(f)).cloned().collect(),
+    }
+}
+
+fn push_field<T: Serialize + PartialEq>(
+    fields: &mut Vec<FieldDiff>,
+    field: &str,
+    from: &T,
+    to: &T,
+) {
+    if from != to {
+        fields.push(FieldDiff {
+            field: field.to_string(),
+            from: serde_json::to_value(from).unwrap_or_default(),
+            to: serde_json::to_value(to).unwrap_or_default(),
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::database::prisma::{ExamCreatorExam, ExamEnvironmentQuestionType};
+
+    fn answer(text: &str) -> ExamEnvironmentAnswer {
+        ExamEnvironmentAnswer {
+            id: ObjectId::new(),
+            is_correct: false,
+            text: text.to_string(),
+        }
+    }
+
+    fn question(text: &str) -> ExamEnvironmentMultipleChoiceQuestion {
+        ExamEnvironmentMultipleChoiceQuestion {
+            id: ObjectId::new(),
+            text: text.to_string(),
+            tags: vec!["a".to_string()],
+            audio: None,
+            answers: vec![answer("Answer")],
+            deprecated: false,
+        }
+    }
+
+    fn question_set(questions: usize) -> ExamEnvironmentQuestionSet {
+        ExamEnvironmentQuestionSet {
+            id: ObjectId::new(),
+            _type: ExamEnvironmentQuestionType::MultipleChoice,
+            context: None,
+            questions: (0..questions).map(|_| question("Question")).collect(),
+        }
+    }
+
+    fn exam() -> ExamEnvironmentExam {
+        let mut exam = ExamEnvironmentExam::from(ExamCreatorExam::default());
+        exam.question_sets = vec![question_set(2), question_set(1)];
+        exam
+    }
+
+    fn tag_config(tags: &[&str], number_of_questions: i64) -> ExamEnvironmentTagConfig {
+        ExamEnvironmentTagConfig {
+            group: tags.iter().map(|t| t.to_string()).collect(),
+            number_of_questions,
+        }
+    }
+
+    #[test]
+    fn same_exam_has_no_changes() {
+        let exam = exam();
+
+        let diff = diff_exams(&exam, &exam);
+
+        assert!(diff.fields.is_empty());
+        assert!(diff.prerequisites.is_empty());
+        assert!(diff.config.fields.is_empty());
+        assert!(diff.config.tags.is_empty());
+        assert!(diff.config.question_sets.is_empty());
+        assert!(diff.question_sets.is_empty());
+    }
+
+    #[test]
+    fn reordered_question_sets_and_questions_are_not_changes() {
+        let from = exam();
+        let mut to = from.clone();
+        to.question_sets.reverse();
+        to.question_sets[1].questions.reverse();
+
+        assert!(diff_exams(&from, &to).question_sets.is_empty());
+    }
+
+    #[test]
+    fn added_question_includes_its_tags_and_answers() {
+        let from = exam();
+        let mut to = from.clone();
+        let added = question("Added");
+        to.question_sets[0].questions.push(added.clone());
+
+        let diff = diff_exams(&from, &to);
+
+        assert_eq!(diff.question_sets.len(), 1);
+        let question_set = &diff.question_sets[0];
+        assert_eq!(question_set.id, from.question_sets[0].id);
+        assert_eq!(question_set.change, ChangeKind::Modified);
+        assert_eq!(question_set.questions.len(), 1);
+        let question = &question_set.questions[0];
+        assert_eq!(question.id, added.id);
+        assert_eq!(question.change, ChangeKind::Added);
+        assert_eq!(question.tags.added, added.tags);
+        assert_eq!(question.answers.len(), 1);
+        assert_eq!(question.answers[0].change, ChangeKind::Added);
+    }
+
+    #[test]
+    fn removed_question_set_includes_its_questions() {
+        let from = exam();
+        let mut to = from.clone();
+        let removed = to.question_sets.remove(0);
+
+        let diff = diff_exams(&from, &to);
+
+        assert_eq!(diff.question_sets.len(), 1);
+        assert_eq!(diff.question_sets[0].id, removed.id);
+        assert_eq!(diff.question_sets[0].change, ChangeKind::Removed);
+        assert_eq!(diff.question_sets[0].questions.len(), 2);
+        assert!(
+            diff.question_sets[0]
+                .questions
+                .iter()
+                .all(|q| q.change == ChangeKind::Removed)
+        );
+    }
+
+    #[test]
+    fn changed_question_lists_only_changed_fields() {
+        let from = exam();
+        let mut to = from.clone();
+        let question = &mut to.question_sets[1].questions[0];
+        question.text = "Changed".to_string();
+        question.tags = vec!["b".to_string()];
+        question.answers[0].is_correct = true;
+
+        let diff = diff_exams(&from, &to);
+
+        assert_eq!(diff.question_sets.len(), 1);
+        let question = &diff.question_sets[0].questions[0];
+        assert_eq!(question.change, ChangeKind::Modified);
+        assert_eq!(
+            question.fields,
+            vec![FieldDiff {
+                field: "text".to_string(),
+                from: "Question".into(),
+                to: "Changed".into(),
+            }]
+        );
+        assert_eq!(question.tags.added, vec!["b".to_string()]);
+        assert_eq!(question.tags.removed, vec!["a".to_string()]);
+        assert_eq!(question.answers.len(), 1);
+        assert_eq!(question.answers[0].change, ChangeKind::Modified);
+        assert_eq!(question.answers[0].fields[0].field, "isCorrect");
+    }
+
+    #[test]
+    fn config_tags_are_compared_as_a_set() {
+        let mut from = exam();
+        from.config.tags = vec![tag_config(&["a"], 1), tag_config(&["b"], 2)];
+        let mut to = from.clone();
+        to.config.tags = vec![tag_config(&["b"], 2), tag_config(&["a"], 2)];
+
+        let diff = diff_exams(&from, &to);
+
+        assert_eq!(diff.config.tags.added, vec![tag_config(&["a"], 2)]);
+        assert_eq!(diff.config.tags.removed, vec![tag_config(&["a"], 1)]);
+        assert!(diff.config.fields.is_empty());
+    }
+
+    #[test]
+    fn diff_set_ignores_order() {
+        let diff = diff_set(&[1, 2, 3], &[3, 4, 1]);
+
+        assert_eq!(diff.added, vec![4]);
+        assert_eq!(diff.removed, vec![2]);
+        assert!(diff_set(&[1, 2], &[2, 1]).is_empty());
+    }
+}
@@ -0,0 +1,405 @@
+use std::{
+    collections::BTreeSet,
+    sync::{Arc, Mutex},
+};
+
+use futures_util::TryStreamExt;
+use mongodb::bson::{DateTime, doc, oid::ObjectId};
+use serde::Serialize;
+use tracing::{error, warn};
+
+use crate::{
+    database::{
+        Database,
+        prisma::{ExamCreatorExam, ExamEnvironmentChallenge, ExamEnvironmentExam},
+    },
+    errors::Error,
+};
+
+/// How an environment's deployed exam compares to the `ExamCreatorExam`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub enum DriftStatus {
+    /// The exam is not deployed to the environment
+    Missing,
+    Identical,
+    /// An earlier version of the exam is deployed
+    Behind,
+    /// A later version of the exam is deployed than is in the creator
+    Ahead,
+    /// The deployed exam has changes which are not from any version of the exam
+    Diverged,
+}
+
+/// A part of an exam compared for drift
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub enum ExamPart {
+    QuestionSets,
+    Config,
+    Prerequisites,
+    Challenges,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct EnvironmentDrift {
+    pub status: DriftStatus,
+    /// Version of the deployed exam
+    pub version: Option<i64>,
+    /// Parts of the deployed exam which differ from the creator's
+    pub differences: Vec<ExamPart>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ExamDrift {
+    pub exam_id: ObjectId,
+    pub name: String,
+    /// Version of the `ExamCreatorExam`
+    pub version: i64,
+    pub staging: EnvironmentDrift,
+    pub production: EnvironmentDrift,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DriftReport {
+    pub created_at: DateTime,
+    pub exams: Vec<ExamDrift>,
+}
+
+/// Compares an environment's deployed exam to the `ExamCreatorExam`.
+///
+/// `deployed_revision` is the revision of the exam with the deployed exam's version, if recorded.
+/// An exam deployed from an earlier version without a recorded revision is assumed to be behind.
+///
+/// Challenge mappings are not versioned, so an exam only differing in its mappings is behind.
+pub fn environment_drift(
+    exam: &ExamCreatorExam,
+    deployed_exam: Option<&ExamEnvironmentExam>,
+    deployed_revision: Option<&ExamCreatorExam>,
+    challenges_differ: bool,
+) -> EnvironmentDrift {
+    let Some(deployed_exam) = deployed_exam else {
+        return EnvironmentDrift {
+            status: DriftStatus::Missing,
+            version: None,
+            differences: vec![],
+        };
+    };
+
+    let mut differences = vec![];
+    if exam.question_sets != deployed_exam.question_sets {
+        differences.push(ExamPart::QuestionSets);
+    }
+    if exam.config != deployed_exam.config {
+        differences.push(ExamPart::Config);
+    }
+    if exam.prerequisites != deployed_exam.prerequisites {
+        differences.push(ExamPart::Prerequisites);
+    }
+    let content_differs = !differences.is_empty();
+    if challenges_differ {
+        differences.push(ExamPart::Challenges);
+    }
+
+    let matches_revision = deployed_revision.is_none_or(|revision| {
+        revision.question_sets == deployed_exam.question_sets
+            && revision.config == deployed_exam.config
+            && revision.prerequisites == deployed_exam.prerequisites
+    });
+
+    let status = if differences.is_empty() {
+        DriftStatus::Identical
+    } else if !content_differs {
+        DriftStatus::Behind
+    } else if deployed_exam.version > exam.version {
+        DriftStatus::Ahead
+    } else if deployed_exam.version < exam.version && matches_revision {
+        DriftStatus::Behind
+    } else {
+        DriftStatus::Diverged
+    };
+
+    EnvironmentDrift {
+        status,
+        version: Some(deployed_exam.version),
+        differences,
+    }
+}
+
+/// Compares every `ExamCreatorExam` with the exams deployed to staging and production.
+///
+/// Staging's challenge mappings are compared to production's, from which they are seeded.
+/// Production's mappings are edited in place, so never differ.
+pub async fn drift_report(
+    production_database: &Database,
+    staging_database: &Database,
+) -> Result<DriftReport, Error> {
+    let exams: Vec<ExamCreatorExam> = production_database
+        .exam_creator_exam
+        .find(doc! {})
+        .await?
+        .try_collect()
+        .await?;
+    let exam_ids: Vec<ObjectId> = exams.iter().map(|exam| exam.id).collect();
+
+    let production_exams: Vec<ExamEnvironmentExam> = production_database
+        .exam
+        .find(doc! { "_id": { "$in": exam_ids.clone() } })
+        .await?
+        .try_collect()
+        .await?;
+    let staging_exams: Vec<ExamEnvironmentExam> = staging_database
+        .exam
+        .find(doc! { "_id": { "$in": exam_ids.clone() } })
+        .await?
+        .try_collect()
+        .await?;
+    let production_challenges: Vec<ExamEnvironmentChallenge> = production_database
+        .exam_environment_challenge
+        .find(doc! { "examId": { "$in": exam_ids.clone() } })
+        .await?
+        .try_collect()
+        .await?;
+    let staging_challenges: Vec<ExamEnvironmentChallenge> = staging_database
+        .exam_environment_challenge
+        .find(doc! { "examId": { "$in": exam_ids } })
+        .await?
+        .try_collect()
+        .await?;
+
+    let mut exam_drifts = Vec::with_capacity(exams.len());
+    for exam in &exams {
+        let production_exam = production_exams.iter().find(|e| e.id == exam.id);
+        let production_revision =
+            deployed_revision(production_database, exam, production_exam).await?;
+        let production =
+            environment_drift(exam, production_exam, production_revision.as_ref(), false);
+
+        let staging_exam = staging_exams.iter().find(|e| e.id == exam.id);
+        let staging_revision = deployed_revision(production_database, exam, staging_exam).await?;
+        let challenges_differ = challenge_ids(&staging_challenges, exam.id)
+            != challenge_ids(&production_challenges, exam.id);
+        let staging = environment_drift(
+            exam,
+            staging_exam,
+            staging_revision.as_ref(),
+            challenges_differ,
+        );
+
+        exam_drifts.push(ExamDrift {
+            exam_id: exam.id,
+            name: exam.config.name.clone(),
+            version: exam.version,
+            staging,
+            production,
+        });
+    }
+
+    Ok(DriftReport {
+        created_at: DateTime::now(),
+        exams: exam_drifts,
+    })
+}
+
+/// The exam's revision with the same version as the deployed exam.
+///
+/// Only looked up for an earlier version, as it is otherwise not needed to classify drift.
+async fn deployed_revision(
+    production_database: &Database,
+    exam: &ExamCreatorExam,
+    deployed_exam: Option<&ExamEnvironmentExam>,
+) -> Result<Option<ExamCreatorExam>, Error> {
+    let Some(deployed_exam) = deployed_exam else {
+        return Ok(None);
+    };
+    if deployed_exam.version >= exam.version {
+        return Ok(None);
+    }
+
+    let revision = production_database
+        .exam_creator_exam_revision
+        .find_one(doc! { "examId": exam.id, "exam.version": deployed_exam.version })
+        .await?;
+
+    Ok(revision.map(|revision| revision.exam))
+}
+
+fn challenge_ids(challenges: &[ExamEnvironmentChallenge], exam_id: ObjectId) -> BTreeSet<ObjectId> {
+    challenges
+        .iter()
+        .filter(|challenge| challenge.exam_id == exam_id)
+        .map(|challenge| challenge.challenge_id)
+        .collect()
+}
+
+/// Regularly replaces `drift_report` with a new report, warning of exams which have diverged
+pub async fn run_drift_reports(
+    production_database: Database,
+    staging_database: Database,
+    drift_report: Arc<Mutex<Option<DriftReport>>>,
+    // How often to create a report
+    interval: std::time::Duration,
+) {
+    loop {
+        match self::drift_report(&production_database, &staging_database).await {
+            Ok(report) => {
+                let diverged = report
+                    .exams
+                    .iter()
+                    .filter(|exam| {
+                        exam.staging.status == DriftStatus::Diverged
+                            || exam.production.status == DriftStatus::Diverged
+                    })
+                    .count();
+                if diverged > 0 {
+                    warn!("{diverged} exams have diverged from the exam creator");
+                }
+                *drift_report.lock().unwrap() = Some(report);
+            }
+            Err(e) => {
+                error!("Failed to create drift report: {e:?}");
+            }
+        }
+
+        tokio::time::sleep(interval).await;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// An exam at `version`, with `prerequisites` standing in for its content
+    fn exam(id: ObjectId, version: i64, prerequisites: &[ObjectId]) -> ExamCreatorExam {
+        ExamCreatorExam {
+            id,
+            version,
+            prerequisites: prerequisites.to_vec(),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn undeployed_exam_is_missing() {
+        let exam = exam(ObjectId::new(), 1, &[]);
+
+        let drift = environment_drift(&exam, None, None, false);
+
+        assert_eq!(drift.status, DriftStatus::Missing);
+        assert_eq!(drift.version, None);
+        assert!(drift.differences.is_empty());
+    }
+
+    #[test]
+    fn same_exam_is_identical() {
+        let exam = exam(ObjectId::new(), 2, &[ObjectId::new()]);
+        let deployed = ExamEnvironmentExam::from(exam.clone());
+
+        let drift = environment_drift(&exam, Some(&deployed), None, false);
+
+        assert_eq!(drift.status, DriftStatus::Identical);
+        assert_eq!(drift.version, Some(2));
+        assert!(drift.differences.is_empty());
+    }
+
+    #[test]
+    fn differing_challenges_only_is_behind() {
+        let exam = exam(ObjectId::new(), 2, &[]);
+        let deployed = ExamEnvironmentExam::from(exam.clone());
+
+        let drift = environment_drift(&exam, Some(&deployed), None, true);
+
+        assert_eq!(drift.status, DriftStatus::Behind);
+        assert_eq!(drift.differences, vec![ExamPart::Challenges]);
+    }
+
+    #[test]
+    fn earlier_version_matching_its_revision_is_behind() {
+        let id = ObjectId::new();
+        let revision = exam(id, 1, &[ObjectId::new()]);
+        let exam = exam(id, 2, &[ObjectId::new()]);
+        let deployed = ExamEnvironmentExam::from(revision.clone());
+
+        let drift = environment_drift(&exam, Some(&deployed), Some(&revision), true);
+
+        assert_eq!(drift.status, DriftStatus::Behind);
+        assert_eq!(drift.version, Some(1));
+        assert_eq!(
+            drift.differences,
+            vec![ExamPart::Prerequisites, ExamPart::Challenges]
+        );
+    }
+
+    #[test]
+    fn earlier_version_without_a_revision_is_behind() {
+        let id = ObjectId::new();
+        let exam = exam(id, 2, &[ObjectId::new()]);
+        let deployed = ExamEnvironmentExam::from(self::exam(id, 1, &[]));
+
+        let drift = environment_drift(&exam, Some(&deployed), None, false);
+
+        assert_eq!(drift.status, DriftStatus::Behind);
+        assert_eq!(drift.differences, vec![ExamPart::Prerequisites]);
+    }
+
+    #[test]
+    fn earlier_version_edited_since_its_revision_is_diverged() {
+        let id = ObjectId::new();
+        let revision = exam(id, 1, &[]);
+        let exam = exam(id, 2, &[ObjectId::new()]);
+        let deployed = ExamEnvironmentExam::from(self::exam(id, 1, &[ObjectId::new()]));
+
+        let drift = environment_drift(&exam, Some(&deployed), Some(&revision), false);
+
+        assert_eq!(drift.status, DriftStatus::Diverged);
+        assert_eq!(drift.differences, vec![ExamPart::Prerequisites]);
+    }
+
+    #[test]
+    fn later_version_is_ahead() {
+        let id = ObjectId::new();
+        let exam = exam(id, 2, &[]);
+        let mut deployed = ExamEnvironmentExam::from(self::exam(id, 3, &[]));
+        deployed.config.name = "Renamed".to_string();
+
+        let drift = environment_drift(&exam, Some(&deployed), None, false);
+
+        assert_eq!(drift.status, DriftStatus::Ahead);
+        assert_eq!(drift.version, Some(3));
+        assert_eq!(drift.differences, vec![ExamPart::Config]);
+    }
+
+    #[test]
+    fn same_version_with_other_content_is_diverged() {
+        let id = ObjectId::new();
+        let exam = exam(id, 2, &[]);
+        let deployed = ExamEnvironmentExam::from(self::exam(id, 2, &[ObjectId::new()]));
+
+        let drift = environment_drift(&exam, Some(&deployed), None, true);
+
+        assert_eq!(drift.status, DriftStatus::Diverged);
+        assert_eq!(
+            drift.differences,
+            vec![ExamPart::Prerequisites, ExamPart::Challenges]
+        );
+    }
+
+    #[test]
+    fn challenge_ids_are_only_the_exams() {
+        let exam_id = ObjectId::new();
+        let challenge = |exam_id| ExamEnvironmentChallenge {
+            id: ObjectId::new(),
+            exam_id,
+            challenge_id: ObjectId::new(),
+            version: 1,
+        };
+        let challenges = vec![challenge(exam_id), challenge(ObjectId::new())];
+
+        let ids = challenge_ids(&challenges, exam_id);
+
+        assert_eq!(ids, BTreeSet::from([challenges[0].challenge_id]));
+    }
+}
@@ -7,8 +7,9 @@ pub enum Error {
     Server(StatusCode, String),
     #[error("{1}")]
     Generation(StatusCode, String),
-    #[error("{1}")]
-    InvalidConfig(StatusCode, String),
+    /// A step of deploying an exam failed
+    #[error("{0} failed: {1}")]
+    Deployment(&'static str, mongodb::error::Error),
     // Froms
     #[error("{0}")]
     MongoDB(#[from] mongodb::error::Error),
@@ -28,6 +29,8 @@ pub enum Error {
     TimeConversion(#[from] time::error::ConversionRange),
     #[error("{0}")]
     Supabase(#[from] supabase_rs::errors::ErrorTypes),
+    #[error("{0}")]
+    Join(#[from] tokio::task::JoinError),
 }
 
 impl IntoResponse for Error {
@@ -44,7 +47,6 @@ impl From<Error> for StatusCode {
         match error {
             Error::Server(c, _) => c,
             Error::Generation(c, _) => c,
-            Error::InvalidConfig(c, _) => c,
             _ => StatusCode::INTERNAL_SERVER_ERROR,
         }
     }
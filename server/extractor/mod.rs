@@ -4,6 +4,7 @@ use axum::{
 };
 use axum_extra::extract::PrivateCookieJar;
 use mongodb::bson::doc;
+use mongodb::bson::oid::ObjectId;
 
 use axum::extract::ws::WebSocketUpgrade;
 use axum::response::IntoResponse;
@@ -15,7 +16,7 @@ use tracing::{error, info, warn};
 use crate::{
     database::prisma,
     errors::Error,
-    routes::websocket::handle_users_ws,
+    routes::websocket::{handle_exam_ws, handle_users_ws, load_synced_exam},
     state::{Activity, ServerState, User},
 };
 
@@ -100,19 +101,21 @@ pub struct QueryAuth {
 }
 
 pub async fn ws_handler_exam(
-    _ws: WebSocketUpgrade,
-    Path(exam_id): Path<String>,
-    State(_state): State<ServerState>,
+    ws: WebSocketUpgrade,
+    session: Session,
+    Path(exam_id): Path<ObjectId>,
+    State(state): State<ServerState>,
     // Token has to be extracted from URL query parameters for now
     // as browser APIs do not support sending custom headers with WebSocket connections
-    // TypedHeader(auth_header): TypedHeader<Authorization<Bearer>>,
-
-    // Query(QueryAuth { token }): Query<QueryAuth>,
-) -> impl IntoResponse {
+    Query(QueryAuth { token }): Query<QueryAuth>,
+) -> Result<impl IntoResponse, Error> {
     info!("WebSocket connection request for exam_id: {}", exam_id);
-    // let token = auth_header.token();
 
-    // todo!()
+    let user = user_from_web_socket_token(&session, &state, &token).await?;
+    load_synced_exam(&state, exam_id).await?;
+
+    let upgrade_res = ws.on_upgrade(move |socket| handle_exam_ws(socket, user, state, exam_id));
+    Ok(upgrade_res)
 }
 
 pub async fn ws_handler_users(
@@ -123,13 +126,22 @@ pub async fn ws_handler_users(
 ) -> Result<impl IntoResponse, Error> {
     info!("WebSocket connection request for users");
 
-    let cookie = session
-        .remove::<String>(&token)
-        .await?
-        .ok_or(Error::Server(
-            StatusCode::UNAUTHORIZED,
-            format!("session cookie not behind token"),
-        ))?;
+    let user = user_from_web_socket_token(&session, &state, &token).await?;
+
+    let upgrade_res = ws.on_upgrade(move |socket| handle_users_ws(socket, user, state));
+    Ok(upgrade_res)
+}
+
+/// Exchanges the one-time token given by `get_session_user` for the session user
+async fn user_from_web_socket_token(
+    session: &Session,
+    state: &ServerState,
+    token: &str,
+) -> Result<prisma::ExamCreatorUser, Error> {
+    let cookie = session.remove::<String>(token).await?.ok_or(Error::Server(
+        StatusCode::UNAUTHORIZED,
+        format!("session cookie not behind token"),
+    ))?;
 
     let session = state
         .production_database
@@ -151,6 +163,5 @@ pub async fn ws_handler_users(
             format!("user not found: {}", session.user_id),
         ))?;
 
-    let upgrade_res = ws.on_upgrade(move |socket| handle_users_ws(socket, user, state));
-    Ok(upgrade_res)
+    Ok(user)
 }
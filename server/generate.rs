@@ -1,7 +1,9 @@
 use http::StatusCode;
 use mongodb::bson::oid::ObjectId;
-use rand::seq::SliceRandom;
+use rand::{Rng, SeedableRng, seq::SliceRandom};
+use rand_chacha::ChaCha8Rng;
 use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
 use std::time::{Duration, Instant};
 use tracing::trace;
 
@@ -9,7 +11,7 @@ use crate::database::prisma::{
     ExamEnvironmentAnswer, ExamEnvironmentConfig, ExamEnvironmentGeneratedExam,
     ExamEnvironmentGeneratedMultipleChoiceQuestion, ExamEnvironmentGeneratedQuestionSet,
     ExamEnvironmentMultipleChoiceQuestion, ExamEnvironmentQuestionSet,
-    ExamEnvironmentQuestionSetConfig,
+    ExamEnvironmentQuestionSetConfig, ExamEnvironmentQuestionType, ExamEnvironmentTagConfig,
 };
 use crate::errors::Error;
 
@@ -21,41 +23,23 @@ pub struct ExamInput {
     pub config: ExamEnvironmentConfig,
 }
 
-#[derive(Debug, Clone)]
-struct QuestionSetConfigWithQuestions {
-    config: ExamEnvironmentQuestionSetConfig,
-    question_sets: Vec<ExamEnvironmentQuestionSet>,
-}
-
 const TIMEOUT_IN_MS: u64 = 5_000;
 
 /// Generates an exam for the user, based on the exam configuration.
-pub fn generate_exam(exam: ExamInput) -> Result<ExamEnvironmentGeneratedExam, Error> {
-    let start_time = Instant::now();
-    let timeout = Duration::from_millis(TIMEOUT_IN_MS);
-
-    let mut rng = rand::rng();
-
-    // Shuffle question sets and their questions/answers
-    let mut shuffled_question_sets: Vec<ExamEnvironmentQuestionSet> = exam
-        .question_sets
-        .into_iter()
-        .map(|mut qs| {
-            let mut shuffled_questions: Vec<ExamEnvironmentMultipleChoiceQuestion> = qs
-                .questions
-                .into_iter()
-                .filter(|q| !q.deprecated)
-                .map(|mut q| {
-                    q.answers.shuffle(&mut rng);
-                    q
-                })
-                .collect();
-            shuffled_questions.shuffle(&mut rng);
-            qs.questions = shuffled_questions;
-            qs
-        })
-        .collect();
-    shuffled_question_sets.shuffle(&mut rng);
+///
+/// Searches for question sets, questions, and answers which fulfill every question set config and tag config.
+/// Candidates are tried in an order derived from `seed`, so the same exam and seed always produce the same question sets, questions, and answers.
+/// Among equally valid candidates, the least exposed question sets, questions, and answers are preferred.
+/// If the search is exhausted, no valid exam exists for the configuration, and a `400` is returned.
+/// If the search runs for longer than `TIMEOUT_IN_MS`, whether a valid exam exists is unknown,
+/// and a `408` is returned.
+pub fn generate_exam(
+    exam: ExamInput,
+    seed: u32,
+    exposure: &Exposure,
+) -> Result<ExamEnvironmentGeneratedExam, Error> {
+    // ChaCha output is stable across `rand` versions, unlike `StdRng`
+    let mut rng = ChaCha8Rng::seed_from_u64(seed.into());
 
     if exam.config.question_sets.is_empty() {
         return Err(Error::Generation(
@@ -67,426 +51,815 @@ pub fn generate_exam(exam: ExamInput) -> Result<ExamEnvironmentGeneratedExam, Er
         ));
     }
 
-    // Convert question set config by type: [[all question sets of type], [another type], ...]
-    let mut type_converted_question_sets_config: Vec<Vec<ExamEnvironmentQuestionSetConfig>> =
-        Vec::new();
-    for config in exam.config.question_sets.iter() {
-        if let Some(type_group) = type_converted_question_sets_config
-            .iter_mut()
-            .find(|group| group.first().map(|c| &c._type) == Some(&config._type))
-        {
-            type_group.push(config.clone());
-        } else {
-            type_converted_question_sets_config.push(vec![config.clone()]);
+    // Shuffle question sets and their questions, then order them by exposure.
+    // The sorts are stable, so equally exposed candidates stay shuffled.
+    let mut question_sets: Vec<ExamEnvironmentQuestionSet> = exam
+        .question_sets
+        .into_iter()
+        .map(|mut qs| {
+            qs.questions.retain(|q| !q.deprecated);
+            qs.questions.shuffle(&mut rng);
+            qs.questions.sort_by_key(|q| exposure.question(q.id));
+            qs
+        })
+        .collect();
+    question_sets.shuffle(&mut rng);
+    question_sets.sort_by_key(|qs| exposure.question_set(qs.id));
+
+    // Group question set configs by type, in the order each type first appears
+    let mut question_set_configs: Vec<&ExamEnvironmentQuestionSetConfig> =
+        exam.config.question_sets.iter().collect();
+    question_set_configs.sort_by_key(|config| {
+        exam.config
+            .question_sets
+            .iter()
+            .position(|c| c._type == config._type)
+    });
+
+    let solver = Solver::new(&question_set_configs, &exam.config.tags, &question_sets);
+    let assignment = match solver.solve() {
+        Ok(Some(assignment)) => assignment,
+        Ok(None) => {
+            return Err(Error::Generation(
+                StatusCode::BAD_REQUEST,
+                format!(
+                    "Invalid Exam Configuration for {}. No combination of questions fulfills the question set and tag configs.",
+                    exam.id
+                ),
+            ));
         }
+        Err(SearchTimedOut) => {
+            return Err(Error::Generation(
+                StatusCode::REQUEST_TIMEOUT,
+                format!(
+                    "Unable to generate exam {} within {}ms. No valid exam was found, but one may exist: validate the config to check whether it can be generated.",
+                    exam.id, TIMEOUT_IN_MS
+                ),
+            ));
+        }
+    };
+
+    trace!("found question sets and questions fulfilling config");
+
+    // Build the final generated exam structure
+    let question_sets = assignment
+        .into_iter()
+        .map(|(config, set, questions)| {
+            let question_set = &question_sets[set];
+            let questions = questions
+                .into_iter()
+                .map(|q| {
+                    let question = get_question_with_random_answers(
+                        &question_set.questions[q],
+                        question_set_configs[config],
+                        exposure,
+                        &mut rng,
+                    )?;
+                    let answers: Vec<ObjectId> =
+                        question.answers.into_iter().map(|a| a.id).collect();
+                    Ok(ExamEnvironmentGeneratedMultipleChoiceQuestion {
+                        id: question.id,
+                        answers,
+                    })
+                })
+                .collect::<Result<Vec<_>, Error>>()?;
+            Ok(ExamEnvironmentGeneratedQuestionSet {
+                id: question_set.id,
+                questions,
+            })
+        })
+        .collect::<Result<Vec<_>, Error>>()?;
+
+    Ok(ExamEnvironmentGeneratedExam {
+        id: ObjectId::new(),
+        exam_id: exam.id,
+        question_sets,
+        deprecated: false,
+        version: 1,
+    })
+}
+
+/// Number of generations each question set, question, and answer appears in
+#[derive(Debug, Clone, Default)]
+pub struct Exposure {
+    generations: u32,
+    question_sets: HashMap<ObjectId, u32>,
+    questions: HashMap<ObjectId, u32>,
+    answers: HashMap<ObjectId, u32>,
+}
+
+impl Exposure {
+    pub fn new<'a>(
+        generated_exams: impl IntoIterator<Item = &'a ExamEnvironmentGeneratedExam>,
+    ) -> Self {
+        let mut exposure = Self::default();
+        for generated_exam in generated_exams {
+            exposure.add(generated_exam);
+        }
+        exposure
     }
 
-    // Sort each type group randomly (heuristic for retry)
-    for group in type_converted_question_sets_config.iter_mut() {
-        group.shuffle(&mut rng);
+    pub fn add(&mut self, generated_exam: &ExamEnvironmentGeneratedExam) {
+        self.generations += 1;
+        for question_set in &generated_exam.question_sets {
+            *self.question_sets.entry(question_set.id).or_default() += 1;
+            for question in &question_set.questions {
+                *self.questions.entry(question.id).or_default() += 1;
+                for answer in &question.answers {
+                    *self.answers.entry(*answer).or_default() += 1;
+                }
+            }
+        }
     }
 
-    let sorted_question_sets_config: Vec<ExamEnvironmentQuestionSetConfig> =
-        type_converted_question_sets_config
-            .into_iter()
-            .flatten()
-            .collect();
+    fn question_set(&self, id: ObjectId) -> u32 {
+        self.question_sets.get(&id).copied().unwrap_or(0)
+    }
 
-    // Move all questions from set that are used to fulfill tag config.
-    let mut question_sets_config_with_questions: Vec<QuestionSetConfigWithQuestions> =
-        sorted_question_sets_config
-            .into_iter()
-            .map(|config| QuestionSetConfigWithQuestions {
-                config,
-                question_sets: Vec::new(),
-            })
-            .collect();
+    pub fn question(&self, id: ObjectId) -> u32 {
+        self.questions.get(&id).copied().unwrap_or(0)
+    }
 
-    // Sort tag config by number of tags in descending order.
-    let mut sorted_tag_config = exam.config.tags.clone();
-    sorted_tag_config.sort_by(|a, b| b.group.len().cmp(&a.group.len()));
+    pub fn answer(&self, id: ObjectId) -> u32 {
+        self.answers.get(&id).copied().unwrap_or(0)
+    }
 
-    // Main allocation loop
-    'question_sets_config_loop: for qsc_with_qs in question_sets_config_with_questions.iter_mut() {
-        'sorted_tag_config_loop: for tag_config in sorted_tag_config.iter_mut() {
-            // Collect questions to remove (question_set_id, question_id)
-            let mut questions_to_remove: Vec<(ObjectId, ObjectId)> = Vec::new();
+    /// Exposure of the exam's non-deprecated questions and their answers
+    pub fn distribution(&self, exam: &ExamInput) -> ExposureDistribution {
+        let questions: Vec<&ExamEnvironmentMultipleChoiceQuestion> = exam
+            .question_sets
+            .iter()
+            .flat_map(|qs| &qs.questions)
+            .filter(|q| !q.deprecated)
+            .collect();
 
-            for question_set in shuffled_question_sets
-                .iter_mut()
-                .filter(|sqs| sqs._type == qsc_with_qs.config._type)
-            {
-                // If questionSet does not have enough questions for config, do not consider.
-                if qsc_with_qs.config.number_of_questions > question_set.questions.len() as i64 {
-                    trace!(
-                        number_of_questions = question_set.questions.len(),
-                        "skipping. not enough questions in question set"
-                    );
-                    continue;
-                }
-                // If tagConfig is finished, skip.
-                if tag_config.number_of_questions == 0 {
-                    trace!(?tag_config.group, "skipping. tag config fulfilled");
-                    continue 'sorted_tag_config_loop;
-                }
-                // If questionSetConfig has been fulfilled, skip.
-                if is_question_set_config_fulfilled(qsc_with_qs) {
-                    trace!(?qsc_with_qs, "skipping. question set config fulfilled");
-                    continue 'question_sets_config_loop;
+        ExposureDistribution {
+            generations: self.generations,
+            questions: ExposureSummary::new(questions.iter().map(|q| ItemExposure {
+                id: q.id,
+                count: self.question(q.id),
+            })),
+            answers: ExposureSummary::new(questions.iter().flat_map(|q| &q.answers).map(|a| {
+                ItemExposure {
+                    id: a.id,
+                    count: self.answer(a.id),
                 }
+            })),
+        }
+    }
+}
 
-                // Store question_set id and metadata before mutable borrow
-                let question_set_id = question_set.id;
-                let question_set_type = question_set._type.clone();
-                let question_set_context = question_set.context.clone();
-
-                // Find question with at least all tags in the set.
-                let questions: Vec<&mut ExamEnvironmentMultipleChoiceQuestion> = question_set
-                    .questions
-                    .iter_mut()
-                    .filter(|q| tag_config.group.iter().all(|t| q.tags.contains(t)))
-                    .collect();
-
-                // trace!(
-                //     number_of_questions = questions.len(),
-                //     ?tag_config,
-                //     "questions fulfilling tags"
-                // );
-
-                for question in questions {
-                    // Does question fulfill criteria for questionSetConfig:
-                    let number_of_correct_answers =
-                        question.answers.iter().filter(|a| a.is_correct).count() as i64;
-                    let number_of_incorrect_answers =
-                        question.answers.iter().filter(|a| !a.is_correct).count() as i64;
-
-                    if qsc_with_qs.config.number_of_correct_answers <= number_of_correct_answers
-                        && qsc_with_qs.config.number_of_incorrect_answers
-                            <= number_of_incorrect_answers
-                    {
-                        if is_question_set_config_fulfilled(qsc_with_qs) {
-                            continue 'question_sets_config_loop;
-                        }
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct ExposureDistribution {
+    /// Number of generations exposure was counted over
+    pub generations: u32,
+    pub questions: ExposureSummary,
+    pub answers: ExposureSummary,
+}
 
-                        // Push questionSet if it does not exist. Otherwise, just push question
-                        let qscqs = qsc_with_qs
-                            .question_sets
-                            .iter_mut()
-                            .find(|qs| qs.id == question_set_id);
-
-                        let question_with_correct_number_of_answers =
-                            get_question_with_random_answers(question, &qsc_with_qs.config)?;
-
-                        if let Some(existing_qs) = qscqs {
-                            if existing_qs.questions.len()
-                                == qsc_with_qs.config.number_of_questions as usize
-                            {
-                                break;
-                            }
-                            existing_qs
-                                .questions
-                                .push(question_with_correct_number_of_answers);
-                        } else {
-                            if qsc_with_qs.question_sets.len()
-                                == qsc_with_qs.config.number_of_set as usize
-                            {
-                                break;
-                            }
-                            // Create new question set from stored metadata
-                            let new_question_set = ExamEnvironmentQuestionSet {
-                                id: question_set_id,
-                                _type: question_set_type.clone(),
-                                context: question_set_context.clone(),
-                                questions: vec![question_with_correct_number_of_answers],
-                            };
-                            qsc_with_qs.question_sets.push(new_question_set);
-                        }
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct ExposureSummary {
+    pub min: u32,
+    pub max: u32,
+    pub mean: f64,
+    pub std_dev: f64,
+    /// Number of items which appear in no generation
+    pub unexposed: usize,
+    /// Exposure of each item, least exposed first
+    pub items: Vec<ItemExposure>,
+}
 
-                        // Mark question for removal
-                        questions_to_remove.push((question_set_id, question.id));
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct ItemExposure {
+    pub id: ObjectId,
+    pub count: u32,
+}
 
-                        tag_config.number_of_questions -= 1;
-                    }
-                }
-            }
+impl ExposureSummary {
+    fn new(items: impl Iterator<Item = ItemExposure>) -> Self {
+        let mut items: Vec<ItemExposure> = items.collect();
+        items.sort_by_key(|item| item.count);
 
-            // Remove marked questions after iteration
-            for (qs_id, q_id) in questions_to_remove {
-                if let Some(qs) = shuffled_question_sets.iter_mut().find(|qs| qs.id == qs_id) {
-                    qs.questions.retain(|q| q.id != q_id);
-                }
-            }
+        let n = items.len().max(1) as f64;
+        let mean = items.iter().map(|item| item.count as f64).sum::<f64>() / n;
+        let variance = items
+            .iter()
+            .map(|item| (item.count as f64 - mean).powi(2))
+            .sum::<f64>()
+            / n;
+
+        Self {
+            min: items.first().map(|item| item.count).unwrap_or(0),
+            max: items.last().map(|item| item.count).unwrap_or(0),
+            mean,
+            std_dev: variance.sqrt(),
+            unexposed: items.iter().filter(|item| item.count == 0).count(),
+            items,
         }
+    }
+}
 
-        // Add questions to questionSetsConfigWithQuestions until fulfilled.
-        while !is_question_set_config_fulfilled(qsc_with_qs) {
-            if start_time.elapsed() > timeout {
-                return Err(Error::Generation(
-                    StatusCode::REQUEST_TIMEOUT,
-                    format!("Unable to generate exam within {}ms", TIMEOUT_IN_MS),
-                ));
-            }
+/// The search ran out of time before finding a valid exam or proving none exists
+#[derive(Debug)]
+struct SearchTimedOut;
 
-            // Ensure all questionSets ARE FULL
-            if (qsc_with_qs.config.number_of_set as usize) > qsc_with_qs.question_sets.len() {
-                // Find a question set with enough questions and answers to fulfill config
-                let question_set = shuffled_question_sets
-                    .iter()
-                    .find(|qs| {
-                        if qs._type == qsc_with_qs.config._type && qs.questions.len() >= qsc_with_qs.config.number_of_questions as usize
-                            {
-                                let questions: Vec<&ExamEnvironmentMultipleChoiceQuestion> = qs
-                                    .questions
-                                    .iter()
-                                    .filter(|q| {
-                                        let number_of_correct_answers =
-                                            q.answers.iter().filter(|a| a.is_correct).count()
-                                                as i64;
-                                        let number_of_incorrect_answers =
-                                            q.answers.iter().filter(|a| !a.is_correct).count()
-                                                as i64;
-                                        number_of_correct_answers
-                                            >= qsc_with_qs.config.number_of_correct_answers
-                                            && number_of_incorrect_answers
-                                                >= qsc_with_qs.config.number_of_incorrect_answers
-                                    })
-                                    .collect();
-
-                                return questions.len()
-                                    >= qsc_with_qs.config.number_of_questions as usize;
-                        }
+/// Backtracking search over which question sets fill each question set config,
+/// and which questions are taken from each question set.
+///
+/// Question sets and questions are referred to by index into the question sets being searched.
+struct Solver<'a> {
+    question_set_configs: &'a [&'a ExamEnvironmentQuestionSetConfig],
+    tag_configs: &'a [ExamEnvironmentTagConfig],
+    /// Per config, per question set: questions with enough correct and incorrect answers for the config.
+    ///
+    /// Empty if the question set is not of the config's type.
+    eligible: Vec<Vec<Vec<usize>>>,
+    /// Per question set, per question: tag configs the question counts towards
+    question_tags: Vec<Vec<Vec<usize>>>,
+    /// Config of each question set to be chosen
+    slots: Vec<usize>,
+    /// Chosen question set and questions for each filled slot
+    chosen: Vec<(usize, Vec<usize>)>,
+    used: Vec<bool>,
+    /// Number of chosen questions counting towards each tag config
+    tag_counts: Vec<i64>,
+    nodes: u64,
+    start_time: Instant,
+}
 
-                        false
-                    })
-                    .cloned()
-                    .ok_or_else(|| {
-                        Error::Generation(StatusCode::BAD_REQUEST, format!(
-                            "Invalid Exam Configuration for {}. Not enough questions for question type {:?}.",
-                            exam.id, qsc_with_qs.config._type
-                        ))
-                    })?;
-
-                trace!("question set found to fulfill");
-
-                // Remove questionSet from shuffledQuestionSets
-                let question_set_id = question_set.id;
-                shuffled_question_sets.retain(|qs| qs.id != question_set_id);
-
-                // Find question with enough answers to fulfill config
-                let questions: Vec<ExamEnvironmentMultipleChoiceQuestion> = question_set
-                    .questions
+type Assignment = Vec<(usize, usize, Vec<usize>)>;
+
+impl<'a> Solver<'a> {
+    fn new(
+        question_set_configs: &'a [&'a ExamEnvironmentQuestionSetConfig],
+        tag_configs: &'a [ExamEnvironmentTagConfig],
+        question_sets: &[ExamEnvironmentQuestionSet],
+    ) -> Self {
+        let question_tags: Vec<Vec<Vec<usize>>> = question_sets
+            .iter()
+            .map(|qs| {
+                qs.questions
                     .iter()
-                    .filter(|q| {
-                        let number_of_correct_answers =
-                            q.answers.iter().filter(|a| a.is_correct).count() as i64;
-                        let number_of_incorrect_answers =
-                            q.answers.iter().filter(|a| !a.is_correct).count() as i64;
-                        number_of_correct_answers >= qsc_with_qs.config.number_of_correct_answers
-                            && number_of_incorrect_answers
-                                >= qsc_with_qs.config.number_of_incorrect_answers
+                    .map(|q| {
+                        tag_configs
+                            .iter()
+                            .enumerate()
+                            .filter(|(_, tc)| tc.group.iter().all(|t| q.tags.contains(t)))
+                            .map(|(i, _)| i)
+                            .collect()
                     })
-                    .cloned()
-                    .collect();
-
-                let num_to_add = qsc_with_qs.config.number_of_questions as usize;
-                let questions_with_correct_answers: Result<
-                    Vec<ExamEnvironmentMultipleChoiceQuestion>,
-                    Error,
-                > = questions
+                    .collect()
+            })
+            .collect();
+
+        let eligible: Vec<Vec<Vec<usize>>> = question_set_configs
+            .iter()
+            .map(|config| {
+                question_sets
                     .iter()
-                    .take(num_to_add)
-                    .map(|q| get_question_with_random_answers(q, &qsc_with_qs.config))
-                    .collect();
-
-                let mut question_set_with_correct_number_of_answers = question_set.clone();
-                question_set_with_correct_number_of_answers.questions =
-                    questions_with_correct_answers?;
-
-                trace!(
-                    number_of_questions =
-                        question_set_with_correct_number_of_answers.questions.len(),
-                    "pushing question set to question set with config"
-                );
-                qsc_with_qs
-                    .question_sets
-                    .push(question_set_with_correct_number_of_answers);
+                    .map(|qs| {
+                        if qs._type != config._type {
+                            return vec![];
+                        }
+                        qs.questions
+                            .iter()
+                            .enumerate()
+                            .filter(|(_, q)| has_enough_answers(q, config))
+                            .map(|(i, _)| i)
+                            .collect()
+                    })
+                    .collect()
+            })
+            .collect();
+
+        let slots = question_set_configs
+            .iter()
+            .enumerate()
+            .flat_map(|(i, config)| std::iter::repeat_n(i, config.number_of_set.max(0) as usize))
+            .collect();
+
+        Self {
+            question_set_configs,
+            tag_configs,
+            eligible,
+            question_tags,
+            slots,
+            chosen: vec![],
+            used: vec![false; question_sets.len()],
+            tag_counts: vec![0; tag_configs.len()],
+            nodes: 0,
+            start_time: Instant::now(),
+        }
+    }
+
+    /// Returns the chosen `(config, question set, questions)`, or `None` if no valid exam exists
+    fn solve(mut self) -> Result<Option<Assignment>, SearchTimedOut> {
+        if !self.fill_slot(0)? {
+            return Ok(None);
+        }
+
+        let assignment = self
+            .chosen
+            .into_iter()
+            .zip(self.slots)
+            .map(|((set, questions), config)| (config, set, questions))
+            .collect();
+        Ok(Some(assignment))
+    }
+
+    fn fill_slot(&mut self, slot: usize) -> Result<bool, SearchTimedOut> {
+        let Some(&config) = self.slots.get(slot) else {
+            return Ok(self.tag_configs_fulfilled());
+        };
+        if !self.slots_fillable(slot) {
+            return Ok(false);
+        }
+        let needed = self.number_of_questions(config);
+
+        // Slots of the same config are interchangeable, so question sets are only tried in increasing order
+        let first_set = match self.chosen.last() {
+            Some((set, _)) if self.slots[slot - 1] == config => set + 1,
+            _ => 0,
+        };
+
+        for set in first_set..self.used.len() {
+            if self.used[set] || self.eligible[config][set].len() < needed {
+                continue;
             }
 
-            // Ensure all existing questionSets have correct number of questions
-            for question_set in qsc_with_qs.question_sets.iter_mut() {
-                if (question_set.questions.len() as i64) < qsc_with_qs.config.number_of_questions {
-                    let questions: Vec<ExamEnvironmentMultipleChoiceQuestion> =
-                        shuffled_question_sets
-                            .iter()
-                            .find(|qs| qs.id == question_set.id)
-                            .ok_or_else(|| {
-                                Error::Generation(StatusCode::BAD_REQUEST, format!(
-                                    "Invalid Exam Configuration for {}. Not enough questions for question type {:?}.",
-                                    exam.id, qsc_with_qs.config._type
-                                ))
-                            })?
-                            .questions
-                            .iter()
-                            .filter(|q| !question_set.questions.iter().any(|qsq| qsq.id == q.id))
-                            .cloned()
-                            .collect();
-
-                    let questions_with_enough_answers: Vec<ExamEnvironmentMultipleChoiceQuestion> =
-                        questions
-                            .into_iter()
-                            .filter(|q| {
-                                let number_of_correct_answers =
-                                    q.answers.iter().filter(|a| a.is_correct).count() as i64;
-                                let number_of_incorrect_answers =
-                                    q.answers.iter().filter(|a| !a.is_correct).count() as i64;
-                                number_of_correct_answers
-                                    >= qsc_with_qs.config.number_of_correct_answers
-                                    && number_of_incorrect_answers
-                                        >= qsc_with_qs.config.number_of_incorrect_answers
-                            })
-                            .collect();
-
-                    // Push as many questions as needed to fulfill questionSetConfig
-                    let num_to_add = (qsc_with_qs.config.number_of_questions as usize)
-                        - question_set.questions.len();
-                    let questions_to_add: Vec<ExamEnvironmentMultipleChoiceQuestion> =
-                        questions_with_enough_answers
-                            .into_iter()
-                            .take(num_to_add)
-                            .collect();
-
-                    let questions_with_correct_answers: Result<
-                        Vec<ExamEnvironmentMultipleChoiceQuestion>,
-                        Error,
-                    > = questions_to_add
-                        .iter()
-                        .map(|q| get_question_with_random_answers(q, &qsc_with_qs.config))
-                        .collect();
-
-                    question_set
-                        .questions
-                        .extend(questions_with_correct_answers?);
-
-                    // Remove questions from shuffledQuestionSets
-                    for q in questions_to_add.iter() {
-                        if let Some(qs) = shuffled_question_sets
-                            .iter_mut()
-                            .find(|qs| qs.id == question_set.id)
-                        {
-                            qs.questions.retain(|qs_q| qs_q.id != q.id);
-                        }
-                    }
-                }
+            self.used[set] = true;
+            self.chosen.push((set, Vec::with_capacity(needed)));
+            if self.fill_questions(slot, 0)? {
+                return Ok(true);
             }
+            self.chosen.pop();
+            self.used[set] = false;
         }
+
+        Ok(false)
     }
 
-    trace!("finished question set config");
+    /// Decides whether to take each eligible question of the slot's question set, starting at `next`
+    fn fill_questions(&mut self, slot: usize, next: usize) -> Result<bool, SearchTimedOut> {
+        self.visit()?;
 
-    // Update tag config for cases where one question fulfills multiple tag configs
-    for qsc_with_qs in question_sets_config_with_questions.iter() {
-        for question_set in qsc_with_qs.question_sets.iter() {
-            for question in question_set.questions.iter() {
-                for tag_config in sorted_tag_config.iter_mut() {
-                    if tag_config.number_of_questions <= 0 {
-                        continue;
-                    }
-                    if tag_config.group.iter().all(|t| question.tags.contains(t)) {
-                        tag_config.number_of_questions -= 1;
-                    }
-                }
+        let config = self.slots[slot];
+        let (set, ref questions) = self.chosen[slot];
+        let open = self.number_of_questions(config) - questions.len();
+        if open == 0 {
+            return self.fill_slot(slot + 1);
+        }
+        if self.eligible[config][set].len() - next < open || !self.tag_configs_reachable(slot, next)
+        {
+            return Ok(false);
+        }
+
+        let question = self.eligible[config][set][next];
+
+        // Take the question
+        self.chosen[slot].1.push(question);
+        for &t in &self.question_tags[set][question] {
+            self.tag_counts[t] += 1;
+        }
+        if self.fill_questions(slot, next + 1)? {
+            return Ok(true);
+        }
+        for &t in &self.question_tags[set][question] {
+            self.tag_counts[t] -= 1;
+        }
+        self.chosen[slot].1.pop();
+
+        // Skip the question
+        self.fill_questions(slot, next + 1)
+    }
+
+    /// Whether enough unused question sets are eligible for the slots from `slot` onwards.
+    ///
+    /// Each config is checked on its own, then with the other configs of its type,
+    /// as they compete for the same question sets.
+    fn slots_fillable(&self, slot: usize) -> bool {
+        let remaining = &self.slots[slot..];
+        let fillable = |configs: &[usize]| {
+            let required = remaining.iter().filter(|c| configs.contains(c)).count();
+            let available = (0..self.used.len())
+                .filter(|&s| !self.used[s])
+                .filter(|&s| {
+                    configs
+                        .iter()
+                        .any(|&c| self.eligible[c][s].len() >= self.number_of_questions(c))
+                })
+                .count();
+            available >= required
+        };
+
+        // Slots of the same config are next to each other
+        let mut configs = remaining.to_vec();
+        configs.dedup();
+        configs.iter().all(|&config| {
+            let same_type: Vec<usize> = configs
+                .iter()
+                .copied()
+                .filter(|&c| {
+                    self.question_set_configs[c]._type == self.question_set_configs[config]._type
+                })
+                .collect();
+            fillable(&[config]) && fillable(&same_type)
+        })
+    }
+
+    /// Whether every tag config could still be fulfilled, assuming the best case for all open questions.
+    ///
+    /// Each tag config is checked on its own, then all together, as one question may not count towards
+    /// enough tag configs to fulfill them all.
+    /// Later slots may be over-estimated, as question sets are not shared between their configs.
+    fn tag_configs_reachable(&self, slot: usize, next: usize) -> bool {
+        let missing: Vec<i64> = self
+            .tag_configs
+            .iter()
+            .zip(&self.tag_counts)
+            .map(|(tag_config, count)| (tag_config.number_of_questions - count).max(0))
+            .collect();
+        if missing.iter().all(|m| *m == 0) {
+            return true;
+        }
+
+        let each_reachable = missing
+            .iter()
+            .enumerate()
+            .filter(|(_, m)| **m > 0)
+            .all(|(t, m)| self.best_case(slot, next, |tags| tags.contains(&t) as i64) >= *m);
+
+        each_reachable
+            && self.best_case(slot, next, |tags| {
+                tags.iter().filter(|&&t| missing[t] > 0).count() as i64
+            }) >= missing.iter().sum::<i64>()
+    }
+
+    /// Upper bound of the total `value` of all open questions.
+    ///
+    /// `value` is given the tag configs a question counts towards.
+    fn best_case(&self, slot: usize, next: usize, value: impl Fn(&[usize]) -> i64) -> i64 {
+        let config = self.slots[slot];
+        let (set, ref questions) = self.chosen[slot];
+        let open = self.number_of_questions(config) - questions.len();
+
+        self.top_values(config, set, next, open, &value) + self.later_best_case(slot + 1, &value)
+    }
+
+    /// Upper bound of the total `value` of the questions of all slots from `first_slot` onwards
+    fn later_best_case(&self, first_slot: usize, value: &impl Fn(&[usize]) -> i64) -> i64 {
+        let mut total = 0;
+
+        let mut later_slots = self.slots[first_slot..].iter().peekable();
+        while let Some(&later_config) = later_slots.next() {
+            let mut count = 1;
+            while later_slots.next_if_eq(&&later_config).is_some() {
+                count += 1;
             }
+
+            let needed = self.number_of_questions(later_config);
+            let mut set_values: Vec<i64> = (0..self.used.len())
+                .filter(|&s| !self.used[s] && self.eligible[later_config][s].len() >= needed)
+                .map(|s| self.top_values(later_config, s, 0, needed, value))
+                .collect();
+            set_values.sort_unstable_by(|a, b| b.cmp(a));
+            total += set_values.into_iter().take(count).sum::<i64>();
         }
+
+        total
     }
 
-    trace!("finished updating tag config post question set config");
+    /// Sum of the `n` highest values of the eligible questions of a question set, starting at `from`
+    fn top_values(
+        &self,
+        config: usize,
+        set: usize,
+        from: usize,
+        n: usize,
+        value: &impl Fn(&[usize]) -> i64,
+    ) -> i64 {
+        let mut values: Vec<i64> = self.eligible[config][set][from..]
+            .iter()
+            .map(|&q| value(&self.question_tags[set][q]))
+            .filter(|v| *v > 0)
+            .collect();
+        values.sort_unstable_by(|a, b| b.cmp(a));
+        values.into_iter().take(n).sum()
+    }
 
-    // Verify all tag configs are fulfilled
-    for tag_config in sorted_tag_config.iter() {
-        if tag_config.number_of_questions > 0 {
-            return Err(Error::Generation(
-                StatusCode::BAD_REQUEST,
-                format!(
-                    "Invalid Exam Configuration for exam \"{}\". Not enough questions for tag group \"{}\".",
-                    exam.id,
-                    tag_config.group.join(",")
-                ),
-            ));
+    fn tag_configs_fulfilled(&self) -> bool {
+        self.tag_configs
+            .iter()
+            .zip(&self.tag_counts)
+            .all(|(tag_config, count)| *count >= tag_config.number_of_questions)
+    }
+
+    fn number_of_questions(&self, config: usize) -> usize {
+        self.question_set_configs[config].number_of_questions.max(0) as usize
+    }
+
+    fn visit(&mut self) -> Result<(), SearchTimedOut> {
+        self.nodes += 1;
+        // Checking the time is relatively expensive, so only do so periodically
+        if self.nodes.is_multiple_of(1024)
+            && self.start_time.elapsed() > Duration::from_millis(TIMEOUT_IN_MS)
+        {
+            trace!(nodes = self.nodes, "search timed out");
+            return Err(SearchTimedOut);
         }
+        Ok(())
     }
+}
 
-    trace!("all tag configs are fulfilled");
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub enum Feasibility {
+    Feasible,
+    Infeasible,
+    /// The search ran out of time before finding a valid exam or proving none exists
+    Unknown,
+}
 
-    // Build the final generated exam structure
-    let question_sets: Vec<ExamEnvironmentGeneratedQuestionSet> =
-        question_sets_config_with_questions
-            .into_iter()
-            .flat_map(|qsc| {
-                qsc.question_sets.into_iter().map(|qs| {
-                    let questions: Vec<ExamEnvironmentGeneratedMultipleChoiceQuestion> = qs
-                        .questions
-                        .into_iter()
-                        .map(|q| {
-                            let answers: Vec<ObjectId> =
-                                q.answers.into_iter().map(|a| a.id).collect();
-                            ExamEnvironmentGeneratedMultipleChoiceQuestion { id: q.id, answers }
-                        })
-                        .collect();
-                    ExamEnvironmentGeneratedQuestionSet {
-                        id: qs.id,
-                        questions,
-                    }
+/// Why an exam config can or cannot be generated
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct FeasibilityReport {
+    pub status: Feasibility,
+    /// Problems with the config itself, such as a missing name
+    pub errors: Vec<String>,
+    pub question_set_configs: Vec<QuestionSetConfigShortfall>,
+    pub tag_configs: Vec<TagConfigShortfall>,
+    pub competing_tag_configs: Vec<TagConfigCompetition>,
+}
+
+/// Question set configs of the same type which need more question sets than are eligible for any of them.
+///
+/// Only the smallest such groups are reported. A group of one config is short on its own,
+/// otherwise the configs starve each other.
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct QuestionSetConfigShortfall {
+    /// Indices into `config.questionSets`
+    pub question_set_configs: Vec<usize>,
+    #[serde(rename = "type")]
+    pub _type: ExamEnvironmentQuestionType,
+    /// Total `numberOfSet` of the configs
+    pub required: i64,
+    /// Question sets with enough questions, with enough answers, for at least one of the configs
+    pub available: i64,
+    /// Number of question sets short
+    pub short: i64,
+    /// Number of questions short
+    pub short_questions: i64,
+}
+
+/// A tag config which cannot be fulfilled, even if every question chosen counted towards it
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TagConfigShortfall {
+    /// Index into `config.tags`
+    pub tag_config: usize,
+    pub group: Vec<String>,
+    pub required: i64,
+    /// Most questions counting towards the tag config an exam could have
+    pub available: i64,
+    pub short: i64,
+}
+
+/// Two tag configs which can each be fulfilled, but not both at once,
+/// as there are not enough questions counting towards either to go around.
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TagConfigCompetition {
+    /// Indices into `config.tags`
+    pub tag_configs: [usize; 2],
+    pub groups: [Vec<String>; 2],
+    /// Number of questions counting towards both tag configs
+    pub shared_questions: usize,
+    /// Total `numberOfQuestions` of both tag configs
+    pub required: i64,
+    /// Most questions counting towards either tag config an exam could have, with shared questions counted twice
+    pub available: i64,
+    pub short: i64,
+}
+
+/// Checks whether an exam can be generated, naming the constraints which prevent it if not.
+///
+/// Shortfalls are found from upper bounds on what any exam could contain.
+/// If there are none, a search for a valid exam decides the status.
+pub fn check_feasibility(exam: ExamInput) -> FeasibilityReport {
+    let mut report = FeasibilityReport {
+        status: Feasibility::Infeasible,
+        errors: vec![],
+        question_set_configs: vec![],
+        tag_configs: vec![],
+        competing_tag_configs: vec![],
+    };
+
+    if exam.config.question_sets.is_empty() {
+        report
+            .errors
+            .push("Invalid exam config - no question sets config.".to_string());
+        return report;
+    }
+
+    let question_sets: Vec<ExamEnvironmentQuestionSet> = exam
+        .question_sets
+        .into_iter()
+        .map(|mut qs| {
+            qs.questions.retain(|q| !q.deprecated);
+            qs
+        })
+        .collect();
+    let question_set_configs: Vec<&ExamEnvironmentQuestionSetConfig> =
+        exam.config.question_sets.iter().collect();
+    let tag_configs = &exam.config.tags;
+    let solver = Solver::new(&question_set_configs, tag_configs, &question_sets);
+
+    report.question_set_configs = question_set_config_shortfalls(&solver);
+
+    for (t, tag_config) in tag_configs.iter().enumerate() {
+        let available = solver.later_best_case(0, &|tags: &[usize]| tags.contains(&t) as i64);
+        if available < tag_config.number_of_questions {
+            report.tag_configs.push(TagConfigShortfall {
+                tag_config: t,
+                group: tag_config.group.clone(),
+                required: tag_config.number_of_questions,
+                available,
+                short: tag_config.number_of_questions - available,
+            });
+        }
+    }
+
+    for (t1, first) in tag_configs.iter().enumerate() {
+        for (t2, second) in tag_configs.iter().enumerate().skip(t1 + 1) {
+            let short_alone = report
+                .tag_configs
+                .iter()
+                .any(|s| s.tag_config == t1 || s.tag_config == t2);
+            if short_alone || first.number_of_questions <= 0 || second.number_of_questions <= 0 {
+                continue;
+            }
+
+            let required = first.number_of_questions + second.number_of_questions;
+            let available = solver.later_best_case(0, &|tags: &[usize]| {
+                tags.iter().filter(|&&t| t == t1 || t == t2).count() as i64
+            });
+            if available >= required {
+                continue;
+            }
+
+            let shared_questions = question_sets
+                .iter()
+                .flat_map(|qs| qs.questions.iter())
+                .filter(|q| {
+                    first.group.iter().all(|t| q.tags.contains(t))
+                        && second.group.iter().all(|t| q.tags.contains(t))
                 })
-            })
-            .collect();
+                .count();
+            report.competing_tag_configs.push(TagConfigCompetition {
+                tag_configs: [t1, t2],
+                groups: [first.group.clone(), second.group.clone()],
+                shared_questions,
+                required,
+                available,
+                short: required - available,
+            });
+        }
+    }
 
-    Ok(ExamEnvironmentGeneratedExam {
-        id: ObjectId::new(),
-        exam_id: exam.id,
-        question_sets,
-        deprecated: false,
-        version: 1,
-    })
+    if !report.question_set_configs.is_empty()
+        || !report.tag_configs.is_empty()
+        || !report.competing_tag_configs.is_empty()
+    {
+        return report;
+    }
+
+    report.status = match solver.solve() {
+        Ok(Some(_)) => Feasibility::Feasible,
+        Ok(None) => Feasibility::Infeasible,
+        Err(SearchTimedOut) => Feasibility::Unknown,
+    };
+    report
 }
 
-/// Returns the configs that are not fulfilled
-fn is_question_set_config_fulfilled(qsc_with_qs: &QuestionSetConfigWithQuestions) -> bool {
-    let enough_of_set =
-        qsc_with_qs.config.number_of_set as usize == qsc_with_qs.question_sets.len();
+/// Finds the smallest groups of same-type question set configs which need more question sets than are eligible
+fn question_set_config_shortfalls(solver: &Solver) -> Vec<QuestionSetConfigShortfall> {
+    let configs = solver.question_set_configs;
+    // Question sets with enough eligible questions for each config
+    let eligible_sets: Vec<Vec<bool>> = (0..configs.len())
+        .map(|c| {
+            solver.eligible[c]
+                .iter()
+                .map(|questions| {
+                    !questions.is_empty() && questions.len() >= solver.number_of_questions(c)
+                })
+                .collect()
+        })
+        .collect();
 
-    if !enough_of_set {
-        trace!(
-            expected = qsc_with_qs.config.number_of_set,
-            actual = qsc_with_qs.question_sets.len(),
-            "not enough of set"
-        );
+    let mut types: Vec<&ExamEnvironmentQuestionType> = vec![];
+    for config in configs {
+        if !types.contains(&&config._type) {
+            types.push(&config._type);
+        }
     }
 
-    let enough_questions = qsc_with_qs.question_sets.iter().all(|qs| {
-        let num_questions = qs.questions.len();
-        let expected_num_questions = qsc_with_qs.config.number_of_questions as usize;
-        if num_questions != expected_num_questions {
-            trace!(
-                expected = expected_num_questions,
-                actual = num_questions,
-                "number of questions required in config does not match number of questions"
-            );
+    let mut shortfalls = vec![];
+    for _type in types {
+        let group: Vec<usize> = (0..configs.len())
+            .filter(|&c| configs[c]._type == *_type)
+            .collect();
+        // Every combination of configs is checked, so limit how many there can be
+        let subsets: Vec<Vec<usize>> = if group.len() <= 12 {
+            let mut subsets: Vec<Vec<usize>> = (1..1u32 << group.len())
+                .map(|mask| {
+                    group
+                        .iter()
+                        .enumerate()
+                        .filter(|(i, _)| mask & (1 << i) != 0)
+                        .map(|(_, &c)| c)
+                        .collect()
+                })
+                .collect();
+            subsets.sort_by_key(|subset| subset.len());
+            subsets
+        } else {
+            group
+                .iter()
+                .map(|&c| vec![c])
+                .chain([group.clone()])
+                .collect()
+        };
+
+        let mut short_subsets: Vec<Vec<usize>> = vec![];
+        for subset in subsets {
+            if short_subsets
+                .iter()
+                .any(|short| short.iter().all(|c| subset.contains(c)))
+            {
+                continue;
+            }
+
+            let required: i64 = subset.iter().map(|&c| configs[c].number_of_set).sum();
+            let available = (0..solver.used.len())
+                .filter(|&s| subset.iter().any(|&c| eligible_sets[c][s]))
+                .count() as i64;
+            if available >= required {
+                continue;
+            }
+
+            // Sets are short for the configs needing the fewest questions first
+            let mut per_set: Vec<i64> = subset
+                .iter()
+                .flat_map(|&c| {
+                    std::iter::repeat_n(
+                        configs[c].number_of_questions,
+                        configs[c].number_of_set.max(0) as usize,
+                    )
+                })
+                .collect();
+            per_set.sort_unstable();
+            let short = required - available;
+            shortfalls.push(QuestionSetConfigShortfall {
+                question_set_configs: subset.clone(),
+                _type: _type.clone(),
+                required,
+                available,
+                short,
+                short_questions: per_set.into_iter().take(short as usize).sum(),
+            });
+            short_subsets.push(subset);
         }
+    }
 
-        num_questions == expected_num_questions
-    });
+    shortfalls
+}
+
+fn has_enough_answers(
+    question: &ExamEnvironmentMultipleChoiceQuestion,
+    question_set_config: &ExamEnvironmentQuestionSetConfig,
+) -> bool {
+    let number_of_correct_answers = question.answers.iter().filter(|a| a.is_correct).count() as i64;
+    let number_of_incorrect_answers =
+        question.answers.iter().filter(|a| !a.is_correct).count() as i64;
 
-    enough_of_set && enough_questions
+    number_of_correct_answers >= question_set_config.number_of_correct_answers
+        && number_of_incorrect_answers >= question_set_config.number_of_incorrect_answers
 }
 
 /// Gets random answers for a question.
 fn get_question_with_random_answers(
     question: &ExamEnvironmentMultipleChoiceQuestion,
     question_set_config: &ExamEnvironmentQuestionSetConfig,
+    exposure: &Exposure,
+    rng: &mut impl Rng,
 ) -> Result<ExamEnvironmentMultipleChoiceQuestion, Error> {
-    let mut rng = rand::rng();
     let mut random_answers = question.answers.clone();
-    random_answers.shuffle(&mut rng);
+    random_answers.shuffle(rng);
+    random_answers.sort_by_key(|a| exposure.answer(a.id));
 
     let incorrect_answers: Vec<ExamEnvironmentAnswer> = random_answers
         .iter()
@@ -519,3 +892,243 @@ fn get_question_with_random_answers(
     result.answers = answers;
     Ok(result)
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn answer(is_correct: bool) -> ExamEnvironmentAnswer {
+        ExamEnvironmentAnswer {
+            id: ObjectId::new(),
+            is_correct,
+            text: "Answer".to_string(),
+        }
+    }
+
+    fn question(tags: &[&str]) -> ExamEnvironmentMultipleChoiceQuestion {
+        ExamEnvironmentMultipleChoiceQuestion {
+            id: ObjectId::new(),
+            text: "Question".to_string(),
+            tags: tags.iter().map(|t| t.to_string()).collect(),
+            audio: None,
+            answers: vec![answer(true), answer(false), answer(false)],
+            deprecated: false,
+        }
+    }
+
+    /// Question sets of untagged questions, with the first `tagged` questions tagged "a"
+    fn question_sets(
+        sets: usize,
+        questions: usize,
+        tagged: usize,
+    ) -> Vec<ExamEnvironmentQuestionSet> {
+        let mut remaining_tagged = tagged;
+        (0..sets)
+            .map(|_| ExamEnvironmentQuestionSet {
+                id: ObjectId::new(),
+                _type: ExamEnvironmentQuestionType::MultipleChoice,
+                context: None,
+                questions: (0..questions)
+                    .map(|_| {
+                        if remaining_tagged > 0 {
+                            remaining_tagged -= 1;
+                            question(&["a"])
+                        } else {
+                            question(&[])
+                        }
+                    })
+                    .collect(),
+            })
+            .collect()
+    }
+
+    fn question_set_config(
+        number_of_set: i64,
+        number_of_questions: i64,
+    ) -> ExamEnvironmentQuestionSetConfig {
+        ExamEnvironmentQuestionSetConfig {
+            _type: ExamEnvironmentQuestionType::MultipleChoice,
+            number_of_set,
+            number_of_questions,
+            number_of_correct_answers: 1,
+            number_of_incorrect_answers: 1,
+        }
+    }
+
+    fn tag_config(number_of_questions: i64) -> ExamEnvironmentTagConfig {
+        ExamEnvironmentTagConfig {
+            group: vec!["a".to_string()],
+            number_of_questions,
+        }
+    }
+
+    fn exam_input(
+        question_sets: Vec<ExamEnvironmentQuestionSet>,
+        question_set_configs: Vec<ExamEnvironmentQuestionSetConfig>,
+        tag_configs: Vec<ExamEnvironmentTagConfig>,
+    ) -> ExamInput {
+        ExamInput {
+            id: ObjectId::new(),
+            question_sets,
+            config: ExamEnvironmentConfig {
+                question_sets: question_set_configs,
+                tags: tag_configs,
+                ..Default::default()
+            },
+        }
+    }
+
+    fn solve(exam: &ExamInput) -> Result<Option<Assignment>, SearchTimedOut> {
+        let question_set_configs: Vec<&ExamEnvironmentQuestionSetConfig> =
+            exam.config.question_sets.iter().collect();
+        Solver::new(
+            &question_set_configs,
+            &exam.config.tags,
+            &exam.question_sets,
+        )
+        .solve()
+    }
+
+    #[test]
+    fn generates_exam_fulfilling_configs() {
+        let exam = exam_input(
+            question_sets(3, 3, 2),
+            vec![question_set_config(2, 2)],
+            vec![tag_config(2)],
+        );
+        let tagged: Vec<ObjectId> = exam
+            .question_sets
+            .iter()
+            .flat_map(|qs| &qs.questions)
+            .filter(|q| !q.tags.is_empty())
+            .map(|q| q.id)
+            .collect();
+
+        let generated_exam = generate_exam(exam, 1, &Exposure::default()).unwrap();
+
+        assert_eq!(generated_exam.question_sets.len(), 2);
+        for question_set in &generated_exam.question_sets {
+            assert_eq!(question_set.questions.len(), 2);
+            for question in &question_set.questions {
+                assert_eq!(question.answers.len(), 2);
+            }
+        }
+        let chosen_tagged = generated_exam
+            .question_sets
+            .iter()
+            .flat_map(|qs| &qs.questions)
+            .filter(|q| tagged.contains(&q.id))
+            .count();
+        assert_eq!(chosen_tagged, 2);
+    }
+
+    #[test]
+    fn same_seed_generates_same_exam() {
+        let exam = exam_input(
+            question_sets(5, 4, 3),
+            vec![question_set_config(3, 2)],
+            vec![tag_config(1)],
+        );
+
+        let first = generate_exam(exam.clone(), 7, &Exposure::default()).unwrap();
+        let second = generate_exam(exam, 7, &Exposure::default()).unwrap();
+
+        assert_eq!(first.question_sets, second.question_sets);
+    }
+
+    #[test]
+    fn prefers_least_exposed_question_sets() {
+        let exam = exam_input(
+            question_sets(2, 2, 0),
+            vec![question_set_config(1, 2)],
+            vec![],
+        );
+        let exposed = generate_exam(exam.clone(), 1, &Exposure::default()).unwrap();
+        let exposure = Exposure::new(std::slice::from_ref(&exposed));
+
+        let generated_exam = generate_exam(exam, 1, &exposure).unwrap();
+
+        assert_ne!(
+            generated_exam.question_sets[0].id,
+            exposed.question_sets[0].id
+        );
+    }
+
+    #[test]
+    fn unfulfillable_tag_config_is_infeasible() {
+        let exam = exam_input(
+            question_sets(3, 3, 1),
+            vec![question_set_config(2, 2)],
+            vec![tag_config(2)],
+        );
+
+        assert!(matches!(solve(&exam), Ok(None)));
+        assert!(matches!(
+            generate_exam(exam, 1, &Exposure::default()),
+            Err(Error::Generation(StatusCode::BAD_REQUEST, _))
+        ));
+    }
+
+    #[test]
+    fn too_few_question_sets_is_proven_infeasible() {
+        // Large enough that an exhaustive search would time out
+        let exam = exam_input(
+            question_sets(40, 10, 0),
+            vec![question_set_config(41, 1)],
+            vec![],
+        );
+
+        assert!(matches!(solve(&exam), Ok(None)));
+    }
+
+    #[test]
+    fn competing_question_set_configs_are_proven_infeasible() {
+        // Only 20 question sets have enough questions for the second config,
+        // and every question set is eligible for the first
+        let mut sets = question_sets(20, 5, 0);
+        sets.extend(question_sets(20, 1, 0));
+        let exam = exam_input(
+            sets,
+            vec![question_set_config(25, 1), question_set_config(16, 5)],
+            vec![],
+        );
+
+        assert!(matches!(solve(&exam), Ok(None)));
+    }
+
+    #[test]
+    fn questions_without_enough_answers_are_not_chosen() {
+        let mut sets = question_sets(1, 3, 0);
+        sets[0].questions[0].answers.retain(|a| a.is_correct);
+        let exam = exam_input(sets, vec![question_set_config(1, 3)], vec![]);
+
+        assert!(matches!(solve(&exam), Ok(None)));
+    }
+
+    #[test]
+    fn feasibility_report_names_short_tag_config() {
+        let exam = exam_input(
+            question_sets(3, 3, 1),
+            vec![question_set_config(2, 2)],
+            vec![tag_config(2)],
+        );
+
+        let report = check_feasibility(exam);
+
+        assert_eq!(report.status, Feasibility::Infeasible);
+        assert_eq!(report.tag_configs.len(), 1);
+        assert_eq!(report.tag_configs[0].available, 1);
+        assert_eq!(report.tag_configs[0].short, 1);
+    }
+
+    #[test]
+    fn feasibility_report_is_feasible_for_valid_config() {
+        let exam = exam_input(
+            question_sets(3, 3, 2),
+            vec![question_set_config(2, 2)],
+            vec![tag_config(2)],
+        );
+
+        assert_eq!(check_feasibility(exam).status, Feasibility::Feasible);
+    }
+}
@@ -0,0 +1,361 @@
+use std::sync::Arc;
+
+use futures_util::{TryStreamExt, future::try_join_all};
+use http::StatusCode;
+use mongodb::{
+    bson::{DateTime, Document, doc, oid::ObjectId},
+    options::ReturnDocument,
+};
+use tokio::sync::broadcast;
+use tracing::{error, info, warn};
+
+use crate::{
+    database::{
+        Database,
+        generation::{ExamCreatorGeneratedExamSeed, ExamCreatorGenerationJob},
+        prisma,
+    },
+    errors::Error,
+    generate,
+};
+
+/// Number of exams generated, in parallel unless balanced, then inserted together
+const GENERATION_BATCH_SIZE: u32 = 32;
+/// Number of seeds tried for each exam of an unseeded job, before the job fails
+const MAX_SEED_ATTEMPTS: u32 = 3;
+
+/// Works through queued generation jobs, oldest first, one at a time.
+///
+/// Jobs which were running when the server stopped are resumed.
+pub async fn run_generation_jobs(
+    production_database: Database,
+    staging_database: Database,
+    job_updates: broadcast::Sender<ExamCreatorGenerationJob>,
+    // How often to check for queued jobs
+    interval: std::time::Duration,
+) {
+    loop {
+        tokio::time::sleep(interval).await;
+
+        let job = match production_database
+            .generation_job
+            .find_one(doc! { "status": { "$in": ["Queued", "Running"] } })
+            .sort(doc! { "createdAt": 1 })
+            .await
+        {
+            Ok(Some(job)) => job,
+            Ok(None) => continue,
+            Err(e) => {
+                error!("Failed to find generation jobs: {e}");
+                continue;
+            }
+        };
+
+        let database = match job.database_environment {
+            prisma::ExamCreatorDatabaseEnvironment::Staging => &staging_database,
+            prisma::ExamCreatorDatabaseEnvironment::Production => &production_database,
+        };
+
+        let job_id = job.id;
+        if let Err(e) = run_generation_job(&production_database, database, &job_updates, job).await
+        {
+            error!("Generation job {job_id} failed: {e}");
+            match update_running_job(
+                &production_database,
+                job_id,
+                doc! { "status": "Failed", "error": e.to_string() },
+            )
+            .await
+            {
+                Ok(Some(job)) => {
+                    let _ = job_updates.send(job);
+                }
+                Ok(None) => {}
+                // The job is still running, so is retried on the next interval
+                Err(e) => error!("Failed to record failure of generation job {job_id}: {e}"),
+            }
+        }
+    }
+}
+
+async fn run_generation_job(
+    production_database: &Database,
+    database: &Database,
+    job_updates: &broadcast::Sender<ExamCreatorGenerationJob>,
+    job: ExamCreatorGenerationJob,
+) -> Result<(), Error> {
+    let Some(mut job) = production_database
+        .generation_job
+        .find_one_and_update(
+            doc! { "_id": job.id, "status": { "$in": ["Queued", "Running"] } },
+            doc! { "$set": { "status": "Running", "updatedAt": DateTime::now() } },
+        )
+        .return_document(ReturnDocument::After)
+        .await?
+    else {
+        // Cancelled since it was found
+        return Ok(());
+    };
+    let _ = job_updates.send(job.clone());
+
+    let exam = match job.revision {
+        Some(revision) => production_database
+            .exam_creator_exam_revision
+            .find_one(doc! { "examId": job.exam_id, "revision": revision })
+            .await?
+            .map(|revision| revision.exam),
+        None => {
+            production_database
+                .exam_creator_exam
+                .find_one(doc! { "_id": job.exam_id })
+                .await?
+        }
+    }
+    .ok_or(Error::Server(
+        StatusCode::BAD_REQUEST,
+        format!("exam non-existent: {}", job.exam_id),
+    ))?;
+
+    let exam_input = generate::ExamInput {
+        id: exam.id,
+        question_sets: exam.question_sets,
+        config: exam.config,
+    };
+
+    // Counted from the seeds, in case the server stopped before the job's progress was recorded
+    let mut generated = database
+        .generated_exam_seed
+        .count_documents(doc! { "jobId": job.id })
+        .await? as u32;
+
+    let mut exposure = if job.balance_exposure {
+        job_exposure(database, &job, generated).await?
+    } else {
+        generate::Exposure::default()
+    };
+
+    info!(
+        "Running generation job {}: {generated} of {} exams generated",
+        job.id, job.count
+    );
+
+    let exam_input = Arc::new(exam_input);
+    let seeded = job.seed.is_some();
+    while generated < job.count {
+        let batch_size = GENERATION_BATCH_SIZE.min(job.count - generated);
+        let seeds: Vec<u32> = (generated..generated + batch_size)
+            .map(|i| match job.seed {
+                Some(seed) => seed.wrapping_add(i),
+                None => rand::random(),
+            })
+            .collect();
+
+        let results = if job.balance_exposure {
+            // Each exam is balanced against every exam generated before it, so they are generated in turn
+            let exam_input = Arc::clone(&exam_input);
+            let (results, batch_exposure) = tokio::task::spawn_blocking(move || {
+                let mut results = vec![];
+                for seed in seeds {
+                    let result = generate_exam(&exam_input, seed, seeded, &exposure);
+                    let failed = match &result.1 {
+                        Ok(generated_exam) => {
+                            exposure.add(generated_exam);
+                            false
+                        }
+                        Err(_) => true,
+                    };
+                    results.push(result);
+                    if failed {
+                        break;
+                    }
+                }
+                (results, exposure)
+            })
+            .await?;
+            exposure = batch_exposure;
+            results
+        } else {
+            let handles = seeds.iter().map(|&seed| {
+                let exam_input = Arc::clone(&exam_input);
+                tokio::task::spawn_blocking(move || {
+                    generate_exam(&exam_input, seed, seeded, &generate::Exposure::default())
+                })
+            });
+            try_join_all(handles).await?
+        };
+
+        // Only the exams before the first failure are kept, so the job's seeds stay contiguous if it is resumed
+        let mut generated_exams = vec![];
+        let mut generated_exam_seeds = vec![];
+        let mut failure = None;
+        for ((seed, result), job_index) in results.into_iter().zip(generated..) {
+            match result {
+                Ok(generated_exam) => {
+                    generated_exam_seeds.push(ExamCreatorGeneratedExamSeed {
+                        id: generated_exam.id,
+                        exam_id: job.exam_id,
+                        revision: job.revision,
+                        seed,
+                        job_id: Some(job.id),
+                        job_index: Some(job_index),
+                        balanced_before: job.balance_exposure.then_some(job_index),
+                        created_at: DateTime::now(),
+                    });
+                    generated_exams.push(generated_exam);
+                }
+                Err(e) => {
+                    failure = Some(e);
+                    break;
+                }
+            }
+        }
+
+        if !generated_exams.is_empty() {
+            insert_generated_exams(database, &generated_exams, &generated_exam_seeds).await?;
+        }
+        generated += generated_exams.len() as u32;
+
+        // Cancelled jobs are no longer running, so are not updated
+        let Some(updated) =
+            update_running_job(production_database, job.id, doc! { "generated": generated })
+                .await?
+        else {
+            info!("Generation job {} cancelled", job.id);
+            return Ok(());
+        };
+        job = updated;
+        let _ = job_updates.send(job.clone());
+
+        if let Some(e) = failure {
+            return Err(e);
+        }
+    }
+
+    let exposure = job
+        .balance_exposure
+        .then(|| exposure.distribution(&exam_input));
+    if let Some(job) = update_running_job(
+        production_database,
+        job.id,
+        doc! { "status": "Completed", "exposure": bson::serialize_to_bson(&exposure)? },
+    )
+    .await?
+    {
+        info!("Generation job {} completed", job.id);
+        let _ = job_updates.send(job);
+    }
+
+    Ok(())
+}
+
+/// Generates an exam from `seed`, returning the seed the exam was generated from.
+///
+/// Exams of unseeded jobs which time out are retried with a new seed, as the search may find a valid exam
+/// sooner in another order. Exams of seeded jobs are not, so the job stays reproducible from its seed.
+fn generate_exam(
+    exam_input: &generate::ExamInput,
+    seed: u32,
+    seeded: bool,
+    exposure: &generate::Exposure,
+) -> (u32, Result<prisma::ExamEnvironmentGeneratedExam, Error>) {
+    let mut seed = seed;
+    let mut attempts = 1;
+    loop {
+        let result = generate::generate_exam(exam_input.clone(), seed, exposure);
+        match result {
+            Err(Error::Generation(StatusCode::REQUEST_TIMEOUT, _))
+                if !seeded && attempts < MAX_SEED_ATTEMPTS =>
+            {
+                warn!(
+                    "Generating exam {} from seed {seed} timed out, retrying",
+                    exam_input.id
+                );
+                seed = rand::random();
+                attempts += 1;
+            }
+            result => return (seed, result),
+        }
+    }
+}
+
+/// Inserts the generations with their seeds in one transaction,
+/// so the job's progress, counted from the seeds, matches the generations.
+async fn insert_generated_exams(
+    database: &Database,
+    generated_exams: &[prisma::ExamEnvironmentGeneratedExam],
+    generated_exam_seeds: &[ExamCreatorGeneratedExamSeed],
+) -> Result<(), Error> {
+    let mut session = database.client.start_session().await?;
+    session.start_transaction().await?;
+
+    let res = async {
+        database
+            .generated_exam
+            .insert_many(generated_exams)
+            .session(&mut session)
+            .await?;
+        database
+            .generated_exam_seed
+            .insert_many(generated_exam_seeds)
+            .session(&mut session)
+            .await?;
+        Ok::<_, mongodb::error::Error>(())
+    }
+    .await;
+
+    if let Err(e) = res {
+        if let Err(abort_error) = session.abort_transaction().await {
+            warn!("Failed to abort inserting generations: {abort_error}");
+        }
+        return Err(e.into());
+    }
+    session.commit_transaction().await?;
+
+    Ok(())
+}
+
+/// Exposure which a balanced job's generations are balanced against:
+/// the job's `balanced_against`, and the job's own generations before `job_index`.
+pub async fn job_exposure(
+    database: &Database,
+    job: &ExamCreatorGenerationJob,
+    job_index: u32,
+) -> Result<generate::Exposure, Error> {
+    let mut generated_exam_ids: Vec<ObjectId> = database
+        .generated_exam_seed
+        .find(doc! { "jobId": job.id, "jobIndex": { "$lt": job_index } })
+        .await?
+        .map_ok(|generated_exam_seed| generated_exam_seed.id)
+        .try_collect()
+        .await?;
+    generated_exam_ids.extend(&job.balanced_against);
+
+    let generated_exams: Vec<prisma::ExamEnvironmentGeneratedExam> = database
+        .generated_exam
+        .find(doc! { "_id": { "$in": generated_exam_ids } })
+        .await?
+        .try_collect()
+        .await?;
+
+    Ok(generate::Exposure::new(&generated_exams))
+}
+
+/// Sets the given fields of a running job, and returns the updated job.
+///
+/// Returns `None` if the job is no longer running.
+async fn update_running_job(
+    production_database: &Database,
+    job_id: ObjectId,
+    mut update: Document,
+) -> Result<Option<ExamCreatorGenerationJob>, Error> {
+    update.insert("updatedAt", DateTime::now());
+    let job = production_database
+        .generation_job
+        .find_one_and_update(
+            doc! { "_id": job_id, "status": "Running" },
+            doc! { "$set": update },
+        )
+        .return_document(ReturnDocument::After)
+        .await?;
+    Ok(job)
+}
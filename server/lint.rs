@@ -0,0 +1,442 @@
+use std::collections::HashMap;
+
+use crate::{
+    database::prisma::{ExamCreatorExam, ExamEnvironmentMultipleChoiceQuestion},
+    validation::{IssueCode, ValidationIssue, answer_path, question_path, question_set_path},
+};
+
+/// A check over an exam's content for likely mistakes, which do not prevent generation.
+///
+/// Deprecated questions are not linted.
+pub trait LintRule: Sync {
+    fn lint(&self, exam: &ExamCreatorExam) -> Vec<ValidationIssue>;
+}
+
+/// Every rule run by `lint_exam`
+pub static RULES: &[&dyn LintRule] = &[
+    &DuplicateAnswerText,
+    &DuplicateQuestionText,
+    &AnswerContainsQuestion,
+    &CorrectAnswerReused,
+    &CorrectAnswerLongest,
+    &UnclosedCode,
+];
+
+pub fn lint_exam(exam: &ExamCreatorExam) -> Vec<ValidationIssue> {
+    RULES.iter().flat_map(|rule| rule.lint(exam)).collect()
+}
+
+/// Non-deprecated questions, with the indices of their question set and of themselves in it
+fn live_questions(
+    exam: &ExamCreatorExam,
+) -> impl Iterator<Item = (usize, usize, &ExamEnvironmentMultipleChoiceQuestion)> {
+    exam.question_sets
+        .iter()
+        .enumerate()
+        .flat_map(|(i, qs)| qs.questions.iter().enumerate().map(move |(j, q)| (i, j, q)))
+        .filter(|(_, _, q)| !q.deprecated)
+}
+
+/// Text as compared by the rules: trimmed, lowercase, with whitespace collapsed
+fn normalize(text: &str) -> String {
+    text.split_whitespace()
+        .collect::<Vec<_>>()
+        .join(" ")
+        .to_lowercase()
+}
+
+/// Two answers of the same question with the same text
+pub struct DuplicateAnswerText;
+
+impl LintRule for DuplicateAnswerText {
+    fn lint(&self, exam: &ExamCreatorExam) -> Vec<ValidationIssue> {
+        let mut issues = vec![];
+        for (i, j, question) in live_questions(exam) {
+            let mut seen: HashMap<String, usize> = HashMap::new();
+            for (k, answer) in question.answers.iter().enumerate() {
+                let text = normalize(&answer.text);
+                if text.is_empty() {
+                    continue;
+                }
+                if let Some(first) = seen.get(&text) {
+                    issues.push(ValidationIssue::new(
+                        IssueCode::DuplicateAnswerText,
+                        answer_path(i, j, k),
+                        format!("Answer has the same text as {}", answer_path(i, j, *first)),
+                    ));
+                } else {
+                    seen.insert(text, k);
+                }
+            }
+        }
+        issues
+    }
+}
+
+/// Two questions anywhere in the exam with the same text
+pub struct DuplicateQuestionText;
+
+impl LintRule for DuplicateQuestionText {
+    fn lint(&self, exam: &ExamCreatorExam) -> Vec<ValidationIssue> {
+        let mut issues = vec![];
+        let mut seen: HashMap<String, (usize, usize)> = HashMap::new();
+        for (i, j, question) in live_questions(exam) {
+            let text = normalize(&question.text);
+            if text.is_empty() {
+                continue;
+            }
+            if let Some((first_i, first_j)) = seen.get(&text) {
+                issues.push(ValidationIssue::new(
+                    IssueCode::DuplicateQuestionText,
+                    question_path(i, j),
+                    format!(
+                        "Question has the same text as {}",
+                        question_path(*first_i, *first_j)
+                    ),
+                ));
+            } else {
+                seen.insert(text, (i, j));
+            }
+        }
+        issues
+    }
+}
+
+/// An answer which repeats its question's text
+pub struct AnswerContainsQuestion;
+
+impl LintRule for AnswerContainsQuestion {
+    fn lint(&self, exam: &ExamCreatorExam) -> Vec<ValidationIssue> {
+        let mut issues = vec![];
+        for (i, j, question) in live_questions(exam) {
+            let question_text = normalize(&question.text);
+            if question_text.is_empty() {
+                continue;
+            }
+            for (k, answer) in question.answers.iter().enumerate() {
+                if normalize(&answer.text).contains(&question_text) {
+                    issues.push(ValidationIssue::new(
+                        IssueCode::AnswerContainsQuestion,
+                        answer_path(i, j, k),
+                        "Answer contains the question text".to_string(),
+                    ));
+                }
+            }
+        }
+        issues
+    }
+}
+
+/// An answer with the same text as a correct answer of another question.
+///
+/// Seeing the answer in one question gives away the other.
+pub struct CorrectAnswerReused;
+
+impl LintRule for CorrectAnswerReused {
+    fn lint(&self, exam: &ExamCreatorExam) -> Vec<ValidationIssue> {
+        // Questions each correct answer text is correct for
+        let mut correct: HashMap<String, Vec<(usize, usize)>> = HashMap::new();
+        for (i, j, question) in live_questions(exam) {
+            for answer in question.answers.iter().filter(|a| a.is_correct) {
+                let text = normalize(&answer.text);
+                if !text.is_empty() {
+                    correct.entry(text).or_default().push((i, j));
+                }
+            }
+        }
+
+        let mut issues = vec![];
+        for (i, j, question) in live_questions(exam) {
+            for (k, answer) in question.answers.iter().enumerate() {
+                let Some(questions) = correct.get(&normalize(&answer.text)) else {
+                    continue;
+                };
+                if let Some((other_i, other_j)) = questions.iter().find(|&&q| q != (i, j)) {
+                    issues.push(ValidationIssue::new(
+                        IssueCode::CorrectAnswerReused,
+                        answer_path(i, j, k),
+                        format!(
+                            "Answer has the same text as a correct answer of {}",
+                            question_path(*other_i, *other_j)
+                        ),
+                    ));
+                }
+            }
+        }
+        issues
+    }
+}
+
+/// Correct answers which are usually the longest, so can be guessed without reading.
+///
+/// Only reported when the correct answers are the longest in most questions,
+/// as any one question may reasonably have the longest answer correct.
+pub struct CorrectAnswerLongest;
+
+impl LintRule for CorrectAnswerLongest {
+    fn lint(&self, exam: &ExamCreatorExam) -> Vec<ValidationIssue> {
+        let mut compared = 0;
+        let mut longest = vec![];
+        for (i, j, question) in live_questions(exam) {
+            let length = |text: &str| text.trim().chars().count();
+            let shortest_correct = question
+                .answers
+                .iter()
+                .filter(|a| a.is_correct)
+                .map(|a| length(&a.text))
+                .min();
+            let longest_incorrect = question
+                .answers
+                .iter()
+                .filter(|a| !a.is_correct)
+                .map(|a| length(&a.text))
+                .max();
+            if let (Some(shortest_correct), Some(longest_incorrect)) =
+                (shortest_correct, longest_incorrect)
+            {
+                compared += 1;
+                if shortest_correct > longest_incorrect {
+                    longest.push((i, j));
+                }
+            }
+        }
+
+        if longest.len() * 2 <= compared {
+            return vec![];
+        }
+
+        let total = longest.len();
+        longest
+            .into_iter()
+            .map(|(i, j)| {
+                ValidationIssue::new(
+                    IssueCode::CorrectAnswerLongest,
+                    question_path(i, j),
+                    format!(
+                        "Correct answers are longer than every incorrect answer, as in {} of {compared} questions",
+                        total
+                    ),
+                )
+            })
+            .collect()
+    }
+}
+
+/// Code blocks and inline code left open, so the rest of the markdown renders as code.
+///
+/// Only fences and backticks are checked. Other markdown is not parsed.
+pub struct UnclosedCode;
+
+impl LintRule for UnclosedCode {
+    fn lint(&self, exam: &ExamCreatorExam) -> Vec<ValidationIssue> {
+        let mut issues = vec![];
+        let mut check = |path: String, markdown: &str| {
+            if let Some(problem) = unclosed_code(markdown) {
+                issues.push(ValidationIssue::new(
+                    IssueCode::UnclosedCode,
+                    path,
+                    problem.to_string(),
+                ));
+            }
+        };
+
+        for (i, question_set) in exam.question_sets.iter().enumerate() {
+            if let Some(context) = &question_set.context {
+                check(format!("{}.context", question_set_path(i)), context);
+            }
+        }
+        for (i, j, question) in live_questions(exam) {
+            check(format!("{}.text", question_path(i, j)), &question.text);
+            for (k, answer) in question.answers.iter().enumerate() {
+                check(format!("{}.text", answer_path(i, j, k)), &answer.text);
+            }
+        }
+        issues
+    }
+}
+
+fn unclosed_code(markdown: &str) -> Option<&'static str> {
+    // Character and length of the open code block's fence
+    let mut fence: Option<(char, usize)> = None;
+    // Code spans can continue over lines, but not past the end of a paragraph
+    let mut paragraph = String::new();
+    for line in markdown.lines() {
+        if let Some((open_char, open_len)) = fence {
+            // Closed by a fence of the same character, at least as long, with nothing after it
+            if let Some((c, len, rest)) = code_fence(line)
+                && c == open_char
+                && len >= open_len
+                && rest.trim().is_empty()
+            {
+                fence = None;
+            }
+            continue;
+        }
+
+        // Indented code blocks cannot interrupt a paragraph
+        let indented_code =
+            paragraph.is_empty() && (line.starts_with("    ") || line.starts_with('\t'));
+        if indented_code {
+            continue;
+        }
+
+        let opens_fence = code_fence(line);
+        if opens_fence.is_some() || line.trim().is_empty() {
+            if !code_spans_closed(&paragraph) {
+                return Some("Inline code is not closed");
+            }
+            paragraph.clear();
+            fence = opens_fence.map(|(c, len, _)| (c, len));
+        } else {
+            paragraph.push_str(line);
+            paragraph.push('\n');
+        }
+    }
+
+    if fence.is_some() {
+        return Some("Code block is not closed");
+    }
+    if !code_spans_closed(&paragraph) {
+        return Some("Inline code is not closed");
+    }
+    None
+}
+
+/// The fence character and length of a line opening or closing a code block, and the text after the fence
+fn code_fence(line: &str) -> Option<(char, usize, &str)> {
+    let trimmed = line.trim_start_matches(' ');
+    if line.len() - trimmed.len() > 3 {
+        return None;
+    }
+    let c = trimmed.chars().next().filter(|&c| c == '`' || c == '~')?;
+    let len = trimmed.chars().take_while(|&t| t == c).count();
+    if len < 3 {
+        return None;
+    }
+    let rest = &trimmed[len..];
+    // Backticks after the fence make it inline code instead
+    if c == '`' && rest.contains('`') {
+        return None;
+    }
+    Some((c, len, rest))
+}
+
+/// Whether every run of backticks opening a code span is closed by a run of the same length.
+///
+/// Backslashes escape backticks outside of code spans, but not within them.
+fn code_spans_closed(text: &str) -> bool {
+    let mut open: Option<usize> = None;
+    let mut chars = text.chars().peekable();
+    while let Some(c) = chars.next() {
+        if c == '\\' && open.is_none() {
+            chars.next();
+        } else if c == '`' {
+            let mut run = 1;
+            while chars.next_if_eq(&'`').is_some() {
+                run += 1;
+            }
+            match open {
+                Some(o) if o == run => open = None,
+                Some(_) => {}
+                None => open = Some(run),
+            }
+        }
+    }
+    open.is_none()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn closed_code_is_not_reported() {
+        assert_eq!(unclosed_code("Use `let` here"), None);
+        assert_eq!(unclosed_code("```js\nlet a = `b`;\n```"), None);
+        assert_eq!(unclosed_code("No code"), None);
+    }
+
+    #[test]
+    fn unclosed_code_block() {
+        assert_eq!(
+            unclosed_code("```js\nlet a;"),
+            Some("Code block is not closed")
+        );
+    }
+
+    #[test]
+    fn unclosed_code_span() {
+        assert_eq!(
+            unclosed_code("Use `let here"),
+            Some("Inline code is not closed")
+        );
+    }
+
+    #[test]
+    fn code_block_is_closed_by_a_fence_at_least_as_long() {
+        // A shorter fence is content of the code block
+        let nested = "````md\n```js\nlet a;\n```\n````";
+        assert_eq!(unclosed_code(nested), None);
+        assert_eq!(
+            unclosed_code("````md\n```js\nlet a;\n```"),
+            Some("Code block is not closed")
+        );
+        assert_eq!(unclosed_code("```\nlet a;\n`````"), None);
+    }
+
+    #[test]
+    fn code_block_is_closed_by_the_same_fence_character() {
+        assert_eq!(unclosed_code("~~~\n```\n~~~"), None);
+        assert_eq!(
+            unclosed_code("~~~\nlet a;\n```"),
+            Some("Code block is not closed")
+        );
+    }
+
+    #[test]
+    fn fence_with_backticks_after_it_is_inline_code() {
+        assert_eq!(unclosed_code("```let a;``` is code"), None);
+    }
+
+    #[test]
+    fn escaped_backticks_outside_code_spans_are_not_code() {
+        assert_eq!(unclosed_code(r"Type \` to start code"), None);
+        assert_eq!(
+            unclosed_code(r"Type \`` to start code"),
+            Some("Inline code is not closed")
+        );
+    }
+
+    #[test]
+    fn backslashes_in_code_spans_do_not_escape() {
+        assert_eq!(unclosed_code(r"The path is `C:\`"), None);
+    }
+
+    #[test]
+    fn code_span_is_closed_by_a_run_of_the_same_length() {
+        assert_eq!(unclosed_code("``let a = `b`;``"), None);
+        assert_eq!(
+            unclosed_code("``let a;`"),
+            Some("Inline code is not closed")
+        );
+    }
+
+    #[test]
+    fn code_span_ends_with_its_paragraph() {
+        assert_eq!(unclosed_code("`let\na;`"), None);
+        assert_eq!(
+            unclosed_code("`let\n\na;`"),
+            Some("Inline code is not closed")
+        );
+    }
+
+    #[test]
+    fn indented_code_blocks_are_not_checked() {
+        assert_eq!(unclosed_code("Code:\n\n    let a = `b;\n\nDone"), None);
+        // Indented lines continue a paragraph
+        assert_eq!(
+            unclosed_code("Code: `let\n    a;"),
+            Some("Inline code is not closed")
+        );
+    }
+}
@@ -1,11 +1,23 @@
 mod app;
+mod compatibility;
 mod config;
 mod database;
+mod deployment;
+mod diff;
+mod drift;
 mod errors;
 mod extractor;
 mod generate;
+mod jobs;
+mod lint;
+mod merge;
+mod patch;
 mod routes;
+mod scheduler;
 mod state;
+mod tags;
+mod validation;
+mod variability;
 
 #[tokio::main]
 async fn main() {
@@ -40,24 +52,6 @@ async fn main() {
             sentry::ClientOptions {
                 release: sentry::release_name!(),
                 traces_sample_rate: 1.0,
-                before_send: Some(std::sync::Arc::new(|event| {
-                    // Filter out InvalidConfig errors - check message and exceptions
-                    let msg = event.message.as_ref().map(|m| m.as_str()).unwrap_or("");
-                    if msg.contains("InvalidConfig") {
-                        return None;
-                    }
-
-                    // Also check exception values
-                    for exception in &event.exception {
-                        if let Some(value) = &exception.value {
-                            if value.contains("InvalidConfig") {
-                                return None;
-                            }
-                        }
-                    }
-
-                    Some(event)
-                })),
                 ..Default::default()
             },
         )))
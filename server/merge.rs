@@ -0,0 +1,641 @@
+use std::collections::HashMap;
+
+use mongodb::bson::oid::ObjectId;
+use serde::{Deserialize, Serialize};
+use tracing::debug;
+
+use crate::{
+    database::{draft::DraftState, prisma::ExamCreatorExam},
+    patch::{AnswerField, ConfigField, ExamField, ExamOperation, QuestionField, QuestionSetField},
+};
+
+/// An exam being edited, along with the history needed to merge concurrent patches.
+///
+/// Every merged patch is given the next sequence number. Clients send the sequence number of the
+/// last patch they had applied (`base`), so the server can tell which earlier writes the author had seen.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SyncedExam {
+    pub exam: ExamCreatorExam,
+    #[serde(skip)]
+    pub draft: DraftState,
+    #[serde(skip)]
+    history: MergeHistory,
+}
+
+#[derive(Debug, Clone, Default)]
+struct MergeHistory {
+    /// Sequence number of the last merged patch or reset
+    seq: u64,
+    /// Last write to each field
+    writes: HashMap<FieldKey, Write>,
+    /// Removed question sets, questions, and answers
+    removed: HashMap<ObjectId, Write>,
+}
+
+#[derive(Debug, Clone)]
+struct Write {
+    seq: u64,
+    base: u64,
+    author: String,
+}
+
+impl Write {
+    /// Whether `other` was made without knowledge of this write
+    fn is_concurrent_with(&self, other: &Write) -> bool {
+        self.seq > other.base && self.author != other.author
+    }
+
+    /// Deterministic order between concurrent writes.
+    ///
+    /// The write based on the newer state wins, with ties broken by author.
+    /// This does not depend on the order in which the writes reached the server.
+    fn wins_over(&self, other: &Write) -> bool {
+        (self.base, &self.author) > (other.base, &other.author)
+    }
+}
+
+/// A single field of an exam, addressed by the ids of the items containing it
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct FieldKey {
+    pub question_set_id: Option<ObjectId>,
+    pub question_id: Option<ObjectId>,
+    pub answer_id: Option<ObjectId>,
+    /// `None` if the item itself was added or removed
+    pub field: Option<&'static str>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub enum ConflictKind {
+    /// The same field was set concurrently
+    SameField,
+    /// The item was edited while another user removed it, or one of its parents
+    Removed,
+}
+
+/// Concurrent edits which could not both be kept
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct MergeConflict {
+    pub kind: ConflictKind,
+    pub field: FieldKey,
+    /// Emails of the users whose edits conflicted
+    pub users: Vec<String>,
+    /// Email of the user whose edit was kept
+    pub kept: String,
+}
+
+#[derive(Debug, Default)]
+pub struct MergeOutcome {
+    /// Sequence number of the merged patch
+    pub seq: u64,
+    /// Operations applied to the exam, in order
+    pub applied: Vec<ExamOperation>,
+    /// Whether any of the author's operations were not applied
+    ///
+    /// The author has applied them locally, so needs to be sent the merged exam
+    pub discarded: bool,
+    pub conflicts: Vec<MergeConflict>,
+}
+
+impl SyncedExam {
+    pub fn new(exam: ExamCreatorExam) -> Self {
+        Self {
+            exam,
+            draft: DraftState::default(),
+            history: MergeHistory::default(),
+        }
+    }
+
+    /// Sequence number of the last merged patch or reset
+    pub fn seq(&self) -> u64 {
+        self.history.seq
+    }
+
+    /// Replaces the exam, such as after it is saved or discarded.
+    ///
+    /// Earlier writes no longer apply to the new exam, so are forgotten.
+    pub fn reset(&mut self, exam: ExamCreatorExam) -> u64 {
+        self.exam = exam;
+        self.draft.cleared();
+        self.history.writes.clear();
+        self.history.removed.clear();
+        self.history.seq += 1;
+        self.history.seq
+    }
+
+    /// Keeps the merged patches on top of an exam saved without them.
+    ///
+    /// The next save of the exam is then based on the saved exam's `version`,
+    /// rather than being rejected as outdated.
+    pub fn rebase(&mut self, version: i64) {
+        self.exam.version = version;
+        self.draft.changed();
+    }
+
+    /// Merges a patch made by `author`, who had seen all patches up to `base`.
+    ///
+    /// Operations are merged one at a time:
+    /// - Adding an item which already exists, or removing one already removed, is a no-op
+    /// - Edits to an item removed concurrently are discarded, as removal wins
+    /// - Concurrent sets of the same field are resolved with `Write::wins_over`
+    /// - Operations whose target does not exist are discarded
+    pub fn merge(
+        &mut self,
+        author: &str,
+        base: u64,
+        operations: Vec<ExamOperation>,
+    ) -> MergeOutcome {
+        let write = Write {
+            seq: self.history.seq + 1,
+            base,
+            author: author.to_string(),
+        };
+        let mut outcome = MergeOutcome {
+            seq: write.seq,
+            ..Default::default()
+        };
+
+        for operation in operations {
+            match self.merge_operation(&write, &operation, &mut outcome.conflicts) {
+                Ok(true) => outcome.applied.push(operation),
+                Ok(false) => {}
+                Err(e) => {
+                    debug!("Discarding exam operation from {author}: {e}");
+                    outcome.discarded = true;
+                }
+            }
+        }
+
+        if outcome.applied.is_empty() {
+            outcome.seq = self.history.seq;
+        } else {
+            self.history.seq = write.seq;
+            self.draft.edited(author);
+        }
+        outcome
+    }
+
+    /// Returns whether the operation was applied.
+    ///
+    /// Errors if the operation was discarded.
+    fn merge_operation(
+        &mut self,
+        write: &Write,
+        operation: &ExamOperation,
+        conflicts: &mut Vec<MergeConflict>,
+    ) -> Result<bool, String> {
+        let key = field_key(operation);
+
+        if let Some(removed_id) = removed_item(operation)
+            && self.history.removed.contains_key(&removed_id)
+        {
+            return Ok(false);
+        }
+        if let Some(added_id) = added_item(operation)
+            && contains_item(&self.exam, &key, added_id)
+        {
+            return Ok(false);
+        }
+
+        for id in required_items(operation) {
+            let Some(removal) = self.history.removed.get(&id) else {
+                continue;
+            };
+            if !removal.is_concurrent_with(write) {
+                // The author has seen the removal, so is out of date
+                if Some(id) != added_item(operation) {
+                    return Err(format!("item removed: {id}"));
+                }
+                continue;
+            }
+            conflicts.push(MergeConflict {
+                kind: ConflictKind::Removed,
+                field: key.clone(),
+                users: vec![removal.author.clone(), write.author.clone()],
+                kept: removal.author.clone(),
+            });
+            return Err(format!("item removed concurrently: {id}"));
+        }
+
+        if key.field.is_some()
+            && let Some(previous) = self.history.writes.get(&key)
+            && previous.is_concurrent_with(write)
+        {
+            let kept = if write.wins_over(previous) {
+                write
+            } else {
+                previous
+            };
+            conflicts.push(MergeConflict {
+                kind: ConflictKind::SameField,
+                field: key.clone(),
+                users: vec![previous.author.clone(), write.author.clone()],
+                kept: kept.author.clone(),
+            });
+            if !write.wins_over(previous) {
+                return Err(format!("lost concurrent write to {key:?}"));
+            }
+        }
+
+        operation.apply(&mut self.exam)?;
+
+        if key.field.is_some() {
+            self.history.writes.insert(key, write.clone());
+        }
+        if let Some(added_id) = added_item(operation) {
+            // Re-adding an item the author has seen removed, such as an undo
+            self.history.removed.remove(&added_id);
+        }
+        if let Some(removed_id) = removed_item(operation) {
+            // Concurrent edits inside the removed item are lost
+            for (edited, previous) in &self.history.writes {
+                let inside = [edited.question_set_id, edited.question_id, edited.answer_id]
+                    .contains(&Some(removed_id));
+                if inside && previous.is_concurrent_with(write) {
+                    conflicts.push(MergeConflict {
+                        kind: ConflictKind::Removed,
+                        field: edited.clone(),
+                        users: vec![previous.author.clone(), write.author.clone()],
+                        kept: write.author.clone(),
+                    });
+                }
+            }
+            self.history.removed.insert(removed_id, write.clone());
+        }
+
+        Ok(true)
+    }
+}
+
+fn field_key(operation: &ExamOperation) -> FieldKey {
+    let (question_set_id, question_id, answer_id, field) = match operation {
+        ExamOperation::SetExam { field } => (
+            None,
+            None,
+            None,
+            match field {
+                ExamField::Prerequisites(_) => "prerequisites",
+                ExamField::Deprecated(_) => "deprecated",
+            },
+        ),
+        ExamOperation::SetConfig { field } => (
+            None,
+            None,
+            None,
+            match field {
+                ConfigField::Name(_) => "config.name",
+                ConfigField::Note(_) => "config.note",
+                ConfigField::Tags(_) => "config.tags",
+                ConfigField::TotalTimeInS(_) => "config.totalTimeInS",
+                ConfigField::QuestionSets(_) => "config.questionSets",
+                ConfigField::RetakeTimeInS(_) => "config.retakeTimeInS",
+                ConfigField::PassingPercent(_) => "config.passingPercent",
+            },
+        ),
+        ExamOperation::SetQuestionSet {
+            question_set_id,
+            field,
+        } => (
+            Some(*question_set_id),
+            None,
+            None,
+            match field {
+                QuestionSetField::Type(_) => "type",
+                QuestionSetField::Context(_) => "context",
+            },
+        ),
+        ExamOperation::SetQuestion {
+            question_set_id,
+            question_id,
+            field,
+        } => (
+            Some(*question_set_id),
+            Some(*question_id),
+            None,
+            match field {
+                QuestionField::Text(_) => "text",
+                QuestionField::Tags(_) => "tags",
+                QuestionField::Audio(_) => "audio",
+                QuestionField::Deprecated(_) => "deprecated",
+            },
+        ),
+        ExamOperation::SetAnswer {
+            question_set_id,
+            question_id,
+            answer_id,
+            field,
+        } => (
+            Some(*question_set_id),
+            Some(*question_id),
+            Some(*answer_id),
+            match field {
+                AnswerField::Text(_) => "text",
+                AnswerField::IsCorrect(_) => "isCorrect",
+            },
+        ),
+        ExamOperation::AddQuestionSet { question_set } => {
+            return item_key(Some(question_set.id), None, None);
+        }
+        ExamOperation::RemoveQuestionSet { question_set_id } => {
+            return item_key(Some(*question_set_id), None, None);
+        }
+        ExamOperation::AddQuestion {
+            question_set_id,
+            question,
+        } => return item_key(Some(*question_set_id), Some(question.id), None),
+        ExamOperation::RemoveQuestion {
+            question_set_id,
+            question_id,
+        } => return item_key(Some(*question_set_id), Some(*question_id), None),
+        ExamOperation::AddAnswer {
+            question_set_id,
+            question_id,
+            answer,
+        } => {
+            return item_key(Some(*question_set_id), Some(*question_id), Some(answer.id));
+        }
+        ExamOperation::RemoveAnswer {
+            question_set_id,
+            question_id,
+            answer_id,
+        } => {
+            return item_key(Some(*question_set_id), Some(*question_id), Some(*answer_id));
+        }
+    };
+
+    FieldKey {
+        question_set_id,
+        question_id,
+        answer_id,
+        field: Some(field),
+    }
+}
+
+fn item_key(
+    question_set_id: Option<ObjectId>,
+    question_id: Option<ObjectId>,
+    answer_id: Option<ObjectId>,
+) -> FieldKey {
+    FieldKey {
+        question_set_id,
+        question_id,
+        answer_id,
+        field: None,
+    }
+}
+
+/// Ids of the items which must not have been removed for the operation to apply
+fn required_items(operation: &ExamOperation) -> Vec<ObjectId> {
+    let key = field_key(operation);
+    [key.question_set_id, key.question_id, key.answer_id]
+        .into_iter()
+        .flatten()
+        .collect()
+}
+
+fn added_item(operation: &ExamOperation) -> Option<ObjectId> {
+    match operation {
+        ExamOperation::AddQuestionSet { question_set } => Some(question_set.id),
+        ExamOperation::AddQuestion { question, .. } => Some(question.id),
+        ExamOperation::AddAnswer { answer, .. } => Some(answer.id),
+        _ => None,
+    }
+}
+
+fn removed_item(operation: &ExamOperation) -> Option<ObjectId> {
+    match operation {
+        ExamOperation::RemoveQuestionSet { question_set_id } => Some(*question_set_id),
+        ExamOperation::RemoveQuestion { question_id, .. } => Some(*question_id),
+        ExamOperation::RemoveAnswer { answer_id, .. } => Some(*answer_id),
+        _ => None,
+    }
+}
+
+/// Whether the item at `key` with the given id exists in the exam
+fn contains_item(exam: &ExamCreatorExam, key: &FieldKey, id: ObjectId) -> bool {
+    let question_set = exam
+        .question_sets
+        .iter()
+        .find(|qs| Some(qs.id) == key.question_set_id);
+    let question =
+        question_set.and_then(|qs| qs.questions.iter().find(|q| Some(q.id) == key.question_id));
+
+    match (key.question_id, key.answer_id) {
+        (None, _) => question_set.is_some_and(|qs| qs.id == id),
+        (Some(_), None) => question.is_some_and(|q| q.id == id),
+        (Some(_), Some(_)) => question.is_some_and(|q| q.answers.iter().any(|a| a.id == id)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::database::prisma::{
+        ExamEnvironmentMultipleChoiceQuestion, ExamEnvironmentQuestionSet,
+        ExamEnvironmentQuestionType,
+    };
+
+    fn question_set() -> ExamEnvironmentQuestionSet {
+        ExamEnvironmentQuestionSet {
+            id: ObjectId::new(),
+            _type: ExamEnvironmentQuestionType::MultipleChoice,
+            context: None,
+            questions: vec![ExamEnvironmentMultipleChoiceQuestion {
+                id: ObjectId::new(),
+                text: "Question".to_string(),
+                tags: vec![],
+                audio: None,
+                answers: vec![],
+                deprecated: false,
+            }],
+        }
+    }
+
+    fn synced_exam() -> SyncedExam {
+        let mut exam = ExamCreatorExam::default();
+        exam.question_sets.push(question_set());
+        SyncedExam::new(exam)
+    }
+
+    fn set_name(name: &str) -> ExamOperation {
+        ExamOperation::SetConfig {
+            field: ConfigField::Name(name.to_string()),
+        }
+    }
+
+    fn set_question_text(synced_exam: &SyncedExam, text: &str) -> ExamOperation {
+        let question_set = &synced_exam.exam.question_sets[0];
+        ExamOperation::SetQuestion {
+            question_set_id: question_set.id,
+            question_id: question_set.questions[0].id,
+            field: QuestionField::Text(text.to_string()),
+        }
+    }
+
+    fn remove_question_set(synced_exam: &SyncedExam) -> ExamOperation {
+        ExamOperation::RemoveQuestionSet {
+            question_set_id: synced_exam.exam.question_sets[0].id,
+        }
+    }
+
+    #[test]
+    fn sequential_writes_do_not_conflict() {
+        let mut synced_exam = synced_exam();
+
+        let first = synced_exam.merge("alice", 0, vec![set_name("First")]);
+        let second = synced_exam.merge("bob", first.seq, vec![set_name("Second")]);
+
+        assert!(second.conflicts.is_empty());
+        assert_eq!(second.seq, 2);
+        assert_eq!(synced_exam.exam.config.name, "Second");
+    }
+
+    #[test]
+    fn writes_by_the_same_author_do_not_conflict() {
+        let mut synced_exam = synced_exam();
+
+        synced_exam.merge("alice", 0, vec![set_name("First")]);
+        let second = synced_exam.merge("alice", 0, vec![set_name("Second")]);
+
+        assert!(second.conflicts.is_empty());
+        assert_eq!(synced_exam.exam.config.name, "Second");
+    }
+
+    #[test]
+    fn concurrent_writes_to_a_field_are_resolved_by_author() {
+        // Both orders of arrival keep the same write
+        for (first, second) in [("alice", "bob"), ("bob", "alice")] {
+            let mut synced_exam = synced_exam();
+
+            synced_exam.merge(first, 0, vec![set_name(first)]);
+            let outcome = synced_exam.merge(second, 0, vec![set_name(second)]);
+
+            assert_eq!(synced_exam.exam.config.name, "bob");
+            assert_eq!(outcome.conflicts.len(), 1);
+            assert_eq!(outcome.conflicts[0].kind, ConflictKind::SameField);
+            assert_eq!(outcome.conflicts[0].kept, "bob");
+            assert_eq!(outcome.discarded, second == "alice");
+        }
+    }
+
+    #[test]
+    fn concurrent_write_based_on_newer_state_wins() {
+        let mut synced_exam = synced_exam();
+        synced_exam.merge(
+            "carol",
+            0,
+            vec![ExamOperation::SetExam {
+                field: ExamField::Deprecated(true),
+            }],
+        );
+
+        synced_exam.merge("bob", 0, vec![set_name("bob")]);
+        let outcome = synced_exam.merge("alice", 1, vec![set_name("alice")]);
+
+        assert_eq!(synced_exam.exam.config.name, "alice");
+        assert_eq!(outcome.conflicts[0].kept, "alice");
+    }
+
+    #[test]
+    fn edits_to_an_item_removed_concurrently_are_discarded() {
+        let mut synced_exam = synced_exam();
+        let edit = set_question_text(&synced_exam, "Edited");
+
+        synced_exam.merge("alice", 0, vec![remove_question_set(&synced_exam)]);
+        let outcome = synced_exam.merge("bob", 0, vec![edit]);
+
+        assert!(outcome.applied.is_empty());
+        assert!(outcome.discarded);
+        assert_eq!(outcome.conflicts.len(), 1);
+        assert_eq!(outcome.conflicts[0].kind, ConflictKind::Removed);
+        assert_eq!(outcome.conflicts[0].kept, "alice");
+        assert!(synced_exam.exam.question_sets.is_empty());
+    }
+
+    #[test]
+    fn removing_an_item_edited_concurrently_reports_the_lost_edit() {
+        let mut synced_exam = synced_exam();
+        let edit = set_question_text(&synced_exam, "Edited");
+        let remove = remove_question_set(&synced_exam);
+
+        synced_exam.merge("bob", 0, vec![edit]);
+        let outcome = synced_exam.merge("alice", 0, vec![remove]);
+
+        assert_eq!(outcome.applied.len(), 1);
+        assert_eq!(outcome.conflicts.len(), 1);
+        assert_eq!(outcome.conflicts[0].kind, ConflictKind::Removed);
+        assert_eq!(outcome.conflicts[0].kept, "alice");
+    }
+
+    #[test]
+    fn repeated_add_and_remove_are_no_ops() {
+        let mut synced_exam = synced_exam();
+        let added = question_set();
+        let add = ExamOperation::AddQuestionSet {
+            question_set: added.clone(),
+        };
+        let remove = ExamOperation::RemoveQuestionSet {
+            question_set_id: added.id,
+        };
+
+        synced_exam.merge("alice", 0, vec![add.clone()]);
+        let outcome = synced_exam.merge("bob", 0, vec![add]);
+        assert!(outcome.applied.is_empty());
+        assert!(!outcome.discarded);
+        assert_eq!(outcome.seq, 1);
+
+        synced_exam.merge("alice", 1, vec![remove.clone()]);
+        let outcome = synced_exam.merge("bob", 1, vec![remove]);
+        assert!(outcome.applied.is_empty());
+        assert!(!outcome.discarded);
+        assert_eq!(synced_exam.exam.question_sets.len(), 1);
+    }
+
+    #[test]
+    fn operations_on_missing_items_are_discarded() {
+        let mut synced_exam = synced_exam();
+
+        let outcome = synced_exam.merge(
+            "alice",
+            0,
+            vec![
+                ExamOperation::RemoveQuestionSet {
+                    question_set_id: ObjectId::new(),
+                },
+                set_name("Kept"),
+            ],
+        );
+
+        assert!(outcome.discarded);
+        assert_eq!(outcome.applied, vec![set_name("Kept")]);
+        assert_eq!(synced_exam.exam.config.name, "Kept");
+    }
+
+    #[test]
+    fn reset_forgets_earlier_writes() {
+        let mut synced_exam = synced_exam();
+        synced_exam.merge("alice", 0, vec![set_name("Before")]);
+
+        let seq = synced_exam.reset(synced_exam.exam.clone());
+        let outcome = synced_exam.merge("bob", seq, vec![set_name("After")]);
+
+        assert_eq!(seq, 2);
+        assert!(outcome.conflicts.is_empty());
+        assert_eq!(outcome.seq, 3);
+    }
+
+    #[test]
+    fn rebase_keeps_earlier_writes() {
+        let mut synced_exam = synced_exam();
+        synced_exam.merge("alice", 0, vec![set_name("Unsaved")]);
+
+        synced_exam.rebase(2);
+        let outcome = synced_exam.merge("bob", 0, vec![set_name("bob")]);
+
+        assert_eq!(synced_exam.exam.version, 2);
+        assert!(synced_exam.draft.updated.is_some());
+        assert_eq!(outcome.conflicts.len(), 1);
+    }
+}
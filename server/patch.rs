@@ -0,0 +1,410 @@
+use mongodb::bson::oid::ObjectId;
+use serde::{Deserialize, Serialize};
+
+use crate::database::prisma::{
+    ExamCreatorExam, ExamEnvironmentAnswer, ExamEnvironmentAudio,
+    ExamEnvironmentMultipleChoiceQuestion, ExamEnvironmentQuestionSet,
+    ExamEnvironmentQuestionSetConfig, ExamEnvironmentQuestionType, ExamEnvironmentTagConfig,
+};
+
+/// A granular change to an exam.
+///
+/// Question sets, questions, and answers are addressed by id, so operations from different
+/// editors can be applied in any order as long as their targets exist.
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq)]
+#[serde(tag = "op", rename_all = "kebab-case", rename_all_fields = "camelCase")]
+pub enum ExamOperation {
+    SetExam {
+        field: ExamField,
+    },
+    SetConfig {
+        field: ConfigField,
+    },
+    AddQuestionSet {
+        question_set: ExamEnvironmentQuestionSet,
+    },
+    RemoveQuestionSet {
+        question_set_id: ObjectId,
+    },
+    SetQuestionSet {
+        question_set_id: ObjectId,
+        field: QuestionSetField,
+    },
+    AddQuestion {
+        question_set_id: ObjectId,
+        question: ExamEnvironmentMultipleChoiceQuestion,
+    },
+    RemoveQuestion {
+        question_set_id: ObjectId,
+        question_id: ObjectId,
+    },
+    SetQuestion {
+        question_set_id: ObjectId,
+        question_id: ObjectId,
+        field: QuestionField,
+    },
+    AddAnswer {
+        question_set_id: ObjectId,
+        question_id: ObjectId,
+        answer: ExamEnvironmentAnswer,
+    },
+    RemoveAnswer {
+        question_set_id: ObjectId,
+        question_id: ObjectId,
+        answer_id: ObjectId,
+    },
+    SetAnswer {
+        question_set_id: ObjectId,
+        question_id: ObjectId,
+        answer_id: ObjectId,
+        field: AnswerField,
+    },
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq)]
+#[serde(tag = "name", content = "value", rename_all = "camelCase")]
+pub enum ExamField {
+    Prerequisites(Vec<ObjectId>),
+    Deprecated(bool),
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq)]
+#[serde(tag = "name", content = "value", rename_all = "camelCase")]
+pub enum ConfigField {
+    Name(String),
+    Note(String),
+    Tags(Vec<ExamEnvironmentTagConfig>),
+    TotalTimeInS(i64),
+    QuestionSets(Vec<ExamEnvironmentQuestionSetConfig>),
+    RetakeTimeInS(i64),
+    PassingPercent(f64),
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq)]
+#[serde(tag = "name", content = "value", rename_all = "camelCase")]
+pub enum QuestionSetField {
+    Type(ExamEnvironmentQuestionType),
+    Context(Option<String>),
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq)]
+#[serde(tag = "name", content = "value", rename_all = "camelCase")]
+pub enum QuestionField {
+    Text(String),
+    Tags(Vec<String>),
+    Audio(Option<ExamEnvironmentAudio>),
+    Deprecated(bool),
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq)]
+#[serde(tag = "name", content = "value", rename_all = "camelCase")]
+pub enum AnswerField {
+    Text(String),
+    IsCorrect(bool),
+}
+
+impl ExamOperation {
+    /// Applies the operation to the exam.
+    ///
+    /// Errors if the target does not exist, or if an added item's id already exists.
+    pub fn apply(&self, exam: &mut ExamCreatorExam) -> Result<(), String> {
+        match self {
+            ExamOperation::SetExam { field } => match field {
+                ExamField::Prerequisites(v) => exam.prerequisites = v.clone(),
+                ExamField::Deprecated(v) => exam.deprecated = *v,
+            },
+            ExamOperation::SetConfig { field } => {
+                let config = &mut exam.config;
+                match field {
+                    ConfigField::Name(v) => config.name = v.clone(),
+                    ConfigField::Note(v) => config.note = v.clone(),
+                    ConfigField::Tags(v) => config.tags = v.clone(),
+                    ConfigField::TotalTimeInS(v) => config.total_time_in_s = *v,
+                    ConfigField::QuestionSets(v) => config.question_sets = v.clone(),
+                    ConfigField::RetakeTimeInS(v) => config.retake_time_in_s = *v,
+                    ConfigField::PassingPercent(v) => config.passing_percent = *v,
+                }
+            }
+            ExamOperation::AddQuestionSet { question_set } => {
+                if exam.question_sets.iter().any(|qs| qs.id == question_set.id) {
+                    return Err(format!("question set already exists: {}", question_set.id));
+                }
+                exam.question_sets.push(question_set.clone());
+            }
+            ExamOperation::RemoveQuestionSet { question_set_id } => {
+                question_set_mut(exam, question_set_id)?;
+                exam.question_sets.retain(|qs| qs.id != *question_set_id);
+            }
+            ExamOperation::SetQuestionSet {
+                question_set_id,
+                field,
+            } => {
+                let question_set = question_set_mut(exam, question_set_id)?;
+                match field {
+                    QuestionSetField::Type(v) => question_set._type = v.clone(),
+                    QuestionSetField::Context(v) => question_set.context = v.clone(),
+                }
+            }
+            ExamOperation::AddQuestion {
+                question_set_id,
+                question,
+            } => {
+                let question_set = question_set_mut(exam, question_set_id)?;
+                if question_set.questions.iter().any(|q| q.id == question.id) {
+                    return Err(format!("question already exists: {}", question.id));
+                }
+                question_set.questions.push(question.clone());
+            }
+            ExamOperation::RemoveQuestion {
+                question_set_id,
+                question_id,
+            } => {
+                let question_set = question_set_mut(exam, question_set_id)?;
+                question_mut(question_set, question_id)?;
+                question_set.questions.retain(|q| q.id != *question_id);
+            }
+            ExamOperation::SetQuestion {
+                question_set_id,
+                question_id,
+                field,
+            } => {
+                let question = question_mut(question_set_mut(exam, question_set_id)?, question_id)?;
+                match field {
+                    QuestionField::Text(v) => question.text = v.clone(),
+                    QuestionField::Tags(v) => question.tags = v.clone(),
+                    QuestionField::Audio(v) => question.audio = v.clone(),
+                    QuestionField::Deprecated(v) => question.deprecated = *v,
+                }
+            }
+            ExamOperation::AddAnswer {
+                question_set_id,
+                question_id,
+                answer,
+            } => {
+                let question = question_mut(question_set_mut(exam, question_set_id)?, question_id)?;
+                if question.answers.iter().any(|a| a.id == answer.id) {
+                    return Err(format!("answer already exists: {}", answer.id));
+                }
+                question.answers.push(answer.clone());
+            }
+            ExamOperation::RemoveAnswer {
+                question_set_id,
+                question_id,
+                answer_id,
+            } => {
+                let question = question_mut(question_set_mut(exam, question_set_id)?, question_id)?;
+                answer_mut(question, answer_id)?;
+                question.answers.retain(|a| a.id != *answer_id);
+            }
+            ExamOperation::SetAnswer {
+                question_set_id,
+                question_id,
+                answer_id,
+                field,
+            } => {
+                let question = question_mut(question_set_mut(exam, question_set_id)?, question_id)?;
+                let answer = answer_mut(question, answer_id)?;
+                match field {
+                    AnswerField::Text(v) => answer.text = v.clone(),
+                    AnswerField::IsCorrect(v) => answer.is_correct = *v,
+                }
+            }
+        }
+
+        Ok(())
+    }
+}
+
+fn question_set_mut<'a>(
+    exam: &'a mut ExamCreatorExam,
+    question_set_id: &ObjectId,
+) -> Result<&'a mut ExamEnvironmentQuestionSet, String> {
+    exam.question_sets
+        .iter_mut()
+        .find(|qs| qs.id == *question_set_id)
+        .ok_or_else(|| format!("question set non-existent: {question_set_id}"))
+}
+
+fn question_mut<'a>(
+    question_set: &'a mut ExamEnvironmentQuestionSet,
+    question_id: &ObjectId,
+) -> Result<&'a mut ExamEnvironmentMultipleChoiceQuestion, String> {
+    question_set
+        .questions
+        .iter_mut()
+        .find(|q| q.id == *question_id)
+        .ok_or_else(|| format!("question non-existent: {question_id}"))
+}
+
+fn answer_mut<'a>(
+    question: &'a mut ExamEnvironmentMultipleChoiceQuestion,
+    answer_id: &ObjectId,
+) -> Result<&'a mut ExamEnvironmentAnswer, String> {
+    question
+        .answers
+        .iter_mut()
+        .find(|a| a.id == *answer_id)
+        .ok_or_else(|| format!("answer non-existent: {answer_id}"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn exam() -> ExamCreatorExam {
+        let mut exam = ExamCreatorExam::default();
+        exam.question_sets.push(ExamEnvironmentQuestionSet {
+            id: ObjectId::new(),
+            _type: ExamEnvironmentQuestionType::MultipleChoice,
+            context: None,
+            questions: vec![ExamEnvironmentMultipleChoiceQuestion {
+                id: ObjectId::new(),
+                text: "Question".to_string(),
+                tags: vec![],
+                audio: None,
+                answers: vec![ExamEnvironmentAnswer {
+                    id: ObjectId::new(),
+                    is_correct: false,
+                    text: "Answer".to_string(),
+                }],
+                deprecated: false,
+            }],
+        });
+        exam
+    }
+
+    /// Ids of the exam's first question set, question, and answer
+    fn ids(exam: &ExamCreatorExam) -> (ObjectId, ObjectId, ObjectId) {
+        let question_set = &exam.question_sets[0];
+        let question = &question_set.questions[0];
+        (question_set.id, question.id, question.answers[0].id)
+    }
+
+    #[test]
+    fn sets_fields_by_id() {
+        let mut exam = exam();
+        let (question_set_id, question_id, answer_id) = ids(&exam);
+
+        for operation in [
+            ExamOperation::SetConfig {
+                field: ConfigField::PassingPercent(50.0),
+            },
+            ExamOperation::SetQuestionSet {
+                question_set_id,
+                field: QuestionSetField::Context(Some("Context".to_string())),
+            },
+            ExamOperation::SetQuestion {
+                question_set_id,
+                question_id,
+                field: QuestionField::Tags(vec!["a".to_string()]),
+            },
+            ExamOperation::SetAnswer {
+                question_set_id,
+                question_id,
+                answer_id,
+                field: AnswerField::IsCorrect(true),
+            },
+        ] {
+            operation.apply(&mut exam).unwrap();
+        }
+
+        let question_set = &exam.question_sets[0];
+        let question = &question_set.questions[0];
+        assert_eq!(exam.config.passing_percent, 50.0);
+        assert_eq!(question_set.context.as_deref(), Some("Context"));
+        assert_eq!(question.tags, vec!["a".to_string()]);
+        assert!(question.answers[0].is_correct);
+    }
+
+    #[test]
+    fn adds_and_removes_items() {
+        let mut exam = exam();
+        let (question_set_id, question_id, answer_id) = ids(&exam);
+        let answer = ExamEnvironmentAnswer {
+            id: ObjectId::new(),
+            is_correct: true,
+            text: "Added".to_string(),
+        };
+
+        ExamOperation::AddAnswer {
+            question_set_id,
+            question_id,
+            answer: answer.clone(),
+        }
+        .apply(&mut exam)
+        .unwrap();
+        ExamOperation::RemoveAnswer {
+            question_set_id,
+            question_id,
+            answer_id,
+        }
+        .apply(&mut exam)
+        .unwrap();
+        assert_eq!(exam.question_sets[0].questions[0].answers, vec![answer]);
+
+        ExamOperation::RemoveQuestion {
+            question_set_id,
+            question_id,
+        }
+        .apply(&mut exam)
+        .unwrap();
+        assert!(exam.question_sets[0].questions.is_empty());
+
+        ExamOperation::RemoveQuestionSet { question_set_id }
+            .apply(&mut exam)
+            .unwrap();
+        assert!(exam.question_sets.is_empty());
+    }
+
+    #[test]
+    fn errors_if_target_is_missing() {
+        let mut exam = exam();
+        let (question_set_id, _, _) = ids(&exam);
+        let original = exam.clone();
+
+        let result = ExamOperation::SetQuestion {
+            question_set_id,
+            question_id: ObjectId::new(),
+            field: QuestionField::Text("Edited".to_string()),
+        }
+        .apply(&mut exam);
+
+        assert!(result.unwrap_err().starts_with("question non-existent"));
+        assert_eq!(exam, original);
+    }
+
+    #[test]
+    fn errors_if_added_item_exists() {
+        let mut exam = exam();
+        let question_set = exam.question_sets[0].clone();
+
+        let result = ExamOperation::AddQuestionSet { question_set }.apply(&mut exam);
+
+        assert!(
+            result
+                .unwrap_err()
+                .starts_with("question set already exists")
+        );
+        assert_eq!(exam.question_sets.len(), 1);
+    }
+
+    #[test]
+    fn deserializes_client_operations() {
+        let question_set_id = ObjectId::new();
+        let json = serde_json::json!({
+            "op": "set-question-set",
+            "questionSetId": question_set_id,
+            "field": { "name": "context", "value": null },
+        });
+
+        let operation: ExamOperation = serde_json::from_value(json).unwrap();
+
+        assert_eq!(
+            operation,
+            ExamOperation::SetQuestionSet {
+                question_set_id,
+                field: QuestionSetField::Context(None),
+            }
+        );
+    }
+}
@@ -0,0 +1,488 @@
+use axum::{
+    Json,
+    extract::{Path, Query, State},
+};
+use futures_util::TryStreamExt;
+use http::StatusCode;
+use mongodb::ClientSession;
+use mongodb::bson::{doc, oid::ObjectId};
+use mongodb::options::ReturnDocument;
+use serde::Deserialize;
+use tracing::{info, instrument};
+
+use crate::{
+    database::{
+        Database,
+        deploy_request::{DeployRequestStatus, DeployRequestTransition, ExamCreatorDeployRequest},
+        prisma, revision,
+    },
+    deployment::{self, DeploymentPlan, DeploymentPreview},
+    errors::Error,
+    routes::{
+        deployments::find_deployment,
+        exams::{PutExamSeedQuery, deploy_exam},
+    },
+    state::ServerState,
+};
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PostDeployRequestBody {
+    /// Revision to deploy. Defaults to the exam's latest revision
+    pub revision_id: Option<ObjectId>,
+    /// Production deployment to roll back to, instead of deploying a revision
+    pub rollback_deployment_id: Option<ObjectId>,
+    /// `deprecated` to set on the production exam, instead of deploying a revision
+    pub deprecated: Option<bool>,
+    pub comment: Option<String>,
+}
+
+/// Request a revision of an exam be deployed to production, production be rolled back to an earlier deployment,
+/// or the production exam be deprecated or undeprecated.
+///
+/// A request to set `deprecated` is recorded at the exam's latest revision, and is run by a schedule.
+#[instrument(skip_all, err(Debug), level = "debug")]
+pub async fn post_deploy_request(
+    auth_user: prisma::ExamCreatorUser,
+    State(state): State<ServerState>,
+    Path(exam_id): Path<ObjectId>,
+    Json(PostDeployRequestBody {
+        revision_id,
+        rollback_deployment_id,
+        deprecated,
+        comment,
+    }): Json<PostDeployRequestBody>,
+) -> Result<Json<ExamCreatorDeployRequest>, Error> {
+    let changes = [
+        revision_id.is_some(),
+        rollback_deployment_id.is_some(),
+        deprecated.is_some(),
+    ];
+    if changes.into_iter().filter(|&change| change).count() > 1 {
+        return Err(Error::Server(
+            StatusCode::BAD_REQUEST,
+            "a deploy request can only deploy a revision, roll back, or set deprecated".to_string(),
+        ));
+    }
+
+    // A rollback is requested as the revision of the deployment rolled back to
+    let revision_id = match rollback_deployment_id {
+        Some(deployment_id) => {
+            let deployment = find_deployment(&state, exam_id, deployment_id).await?;
+            if !matches!(
+                deployment.database_environment,
+                prisma::ExamCreatorDatabaseEnvironment::Production
+            ) {
+                return Err(Error::Server(
+                    StatusCode::BAD_REQUEST,
+                    format!("deployment {deployment_id} is not to production"),
+                ));
+            }
+            Some(deployment.revision_id.ok_or(Error::Server(
+                StatusCode::BAD_REQUEST,
+                format!("deployment {deployment_id} has no revision, so cannot be requested"),
+            ))?)
+        }
+        None => revision_id,
+    };
+
+    let revision = match revision_id {
+        Some(revision_id) => state
+            .production_database
+            .exam_creator_exam_revision
+            .clone_with_type::<revision::ExamCreatorExamRevisionSummary>()
+            .find_one(doc! { "_id": revision_id, "examId": exam_id })
+            .projection(doc! { "exam": false })
+            .await?
+            .ok_or(Error::Server(
+                StatusCode::BAD_REQUEST,
+                format!("revision non-existent: {revision_id}"),
+            ))?,
+        None => revision::latest_revision(&state.production_database, exam_id)
+            .await?
+            .ok_or(Error::Server(
+                StatusCode::BAD_REQUEST,
+                format!("exam {exam_id} has no revisions. Save the exam to deploy it"),
+            ))?,
+    };
+
+    let transition =
+        DeployRequestTransition::new(DeployRequestStatus::Pending, &auth_user, comment);
+    let deploy_request = ExamCreatorDeployRequest {
+        id: ObjectId::new(),
+        exam_id,
+        revision_id: revision.id,
+        revision: revision.revision,
+        status: DeployRequestStatus::Pending,
+        requester_id: auth_user.id,
+        requester_name: auth_user.name,
+        requester_email: auth_user.email,
+        created_at: transition.created_at,
+        updated_at: transition.created_at,
+        transitions: vec![transition],
+        rollback_deployment_id,
+        deprecated,
+        deployment_id: None,
+    };
+
+    state
+        .production_database
+        .exam_creator_deploy_request
+        .insert_one(&deploy_request)
+        .await?;
+    info!(
+        "Requested deploy {} of exam {exam_id}, to {}",
+        deploy_request.id,
+        deploy_request.describe()
+    );
+
+    Ok(Json(deploy_request))
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct GetDeployRequestsQuery {
+    pub exam_id: Option<ObjectId>,
+    pub status: Option<DeployRequestStatus>,
+}
+
+/// Get all deploy requests, most recently created first
+#[instrument(skip_all, err(Debug), level = "debug")]
+pub async fn get_deploy_requests(
+    _: prisma::ExamCreatorUser,
+    State(state): State<ServerState>,
+    Query(GetDeployRequestsQuery { exam_id, status }): Query<GetDeployRequestsQuery>,
+) -> Result<Json<Vec<ExamCreatorDeployRequest>>, Error> {
+    let mut filter = doc! {};
+    if let Some(exam_id) = exam_id {
+        filter.insert("examId", exam_id);
+    }
+    if let Some(status) = status {
+        filter.insert("status", bson::serialize_to_bson(&status)?);
+    }
+
+    let deploy_requests: Vec<ExamCreatorDeployRequest> = state
+        .production_database
+        .exam_creator_deploy_request
+        .find(filter)
+        .sort(doc! { "createdAt": -1 })
+        .await?
+        .try_collect()
+        .await?;
+
+    Ok(Json(deploy_requests))
+}
+
+#[instrument(skip_all, err(Debug), level = "debug")]
+pub async fn get_deploy_request_by_id(
+    _: prisma::ExamCreatorUser,
+    State(state): State<ServerState>,
+    Path(deploy_request_id): Path<ObjectId>,
+) -> Result<Json<ExamCreatorDeployRequest>, Error> {
+    let deploy_request = find_deploy_request(&state, deploy_request_id).await?;
+    Ok(Json(deploy_request))
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PutReviewDeployRequestBody {
+    pub comment: Option<String>,
+}
+
+/// Approve a pending deploy request, so it can be deployed.
+///
+/// The requester cannot approve their own request.
+#[instrument(skip_all, err(Debug), level = "debug")]
+pub async fn put_approve_deploy_request(
+    auth_user: prisma::ExamCreatorUser,
+    State(state): State<ServerState>,
+    Path(deploy_request_id): Path<ObjectId>,
+    Json(PutReviewDeployRequestBody { comment }): Json<PutReviewDeployRequestBody>,
+) -> Result<Json<ExamCreatorDeployRequest>, Error> {
+    review_deploy_request(
+        &state,
+        deploy_request_id,
+        DeployRequestTransition::new(DeployRequestStatus::Approved, &auth_user, comment),
+    )
+    .await
+    .map(Json)
+}
+
+/// Reject a pending deploy request.
+///
+/// The requester cannot reject their own request.
+#[instrument(skip_all, err(Debug), level = "debug")]
+pub async fn put_reject_deploy_request(
+    auth_user: prisma::ExamCreatorUser,
+    State(state): State<ServerState>,
+    Path(deploy_request_id): Path<ObjectId>,
+    Json(PutReviewDeployRequestBody { comment }): Json<PutReviewDeployRequestBody>,
+) -> Result<Json<ExamCreatorDeployRequest>, Error> {
+    review_deploy_request(
+        &state,
+        deploy_request_id,
+        DeployRequestTransition::new(DeployRequestStatus::Rejected, &auth_user, comment),
+    )
+    .await
+    .map(Json)
+}
+
+/// Deploys the requested revision to production, or rolls production back, if the request is approved.
+///
+/// Returns what was changed, or with `dryRun`, what would be changed.
+/// A dry run does not need the request to be approved.
+#[instrument(skip_all, err(Debug), level = "debug")]
+pub async fn put_execute_deploy_request(
+    auth_user: prisma::ExamCreatorUser,
+    State(state): State<ServerState>,
+    Path(deploy_request_id): Path<ObjectId>,
+    Query(query): Query<PutExamSeedQuery>,
+) -> Result<Json<DeploymentPreview>, Error> {
+    let preview = execute_deploy_request(&state, &auth_user, deploy_request_id, query).await?;
+    Ok(Json(preview))
+}
+
+/// Deploys the requested revision to production, or rolls production back, if the request is approved
+pub async fn execute_deploy_request(
+    state: &ServerState,
+    auth_user: &prisma::ExamCreatorUser,
+    deploy_request_id: ObjectId,
+    query: PutExamSeedQuery,
+) -> Result<DeploymentPreview, Error> {
+    let deploy_request = find_deploy_request(state, deploy_request_id).await?;
+
+    if deploy_request.deprecated.is_some() {
+        return Err(Error::Server(
+            StatusCode::BAD_REQUEST,
+            format!("deploy request {deploy_request_id} sets deprecated, so is run by a schedule"),
+        ));
+    }
+    if let Some(deployment_id) = deploy_request.rollback_deployment_id {
+        let deployment = find_deployment(state, deploy_request.exam_id, deployment_id).await?;
+        return deploy_exam(
+            state,
+            auth_user,
+            prisma::ExamCreatorDatabaseEnvironment::Production,
+            deployment::rollback_plan(deployment, Some(deploy_request_id)),
+            query,
+        )
+        .await;
+    }
+
+    let revision = state
+        .production_database
+        .exam_creator_exam_revision
+        .find_one(doc! { "_id": deploy_request.revision_id })
+        .await?
+        .ok_or(Error::Server(
+            StatusCode::BAD_REQUEST,
+            format!("revision non-existent: {}", deploy_request.revision_id),
+        ))?;
+
+    // Production mappings are edited in place, so deploying does not change them
+    deploy_exam(
+        state,
+        auth_user,
+        prisma::ExamCreatorDatabaseEnvironment::Production,
+        DeploymentPlan {
+            exam_creator_exam: revision.exam,
+            exam_environment_challenges: None,
+            generated_exams: vec![],
+            revision_id: Some(revision.id),
+            rolled_back_to: None,
+            deploy_request_id: Some(deploy_request_id),
+        },
+        query,
+    )
+    .await
+}
+
+/// Checks the plan's deploy request is approved for the plan, returning its id.
+///
+/// The request must be for the plan's exam and revision, and roll back to the same deployment, if any.
+pub async fn check_deploy_request(
+    state: &ServerState,
+    plan: &DeploymentPlan,
+) -> Result<ObjectId, Error> {
+    let exam_id = plan.exam_creator_exam.id;
+    let Some(deploy_request_id) = plan.deploy_request_id else {
+        return Err(Error::Server(
+            StatusCode::FORBIDDEN,
+            format!("deploying exam {exam_id} to production requires an approved deploy request"),
+        ));
+    };
+
+    check(state, deploy_request_id, exam_id, |deploy_request| {
+        deploy_request.deprecated.is_none()
+            && Some(deploy_request.revision_id) == plan.revision_id
+            && deploy_request.rollback_deployment_id == plan.rolled_back_to
+    })
+    .await
+}
+
+/// Checks the deploy request is approved to set `deprecated` on the production exam, returning its id.
+pub async fn check_deprecate_request(
+    state: &ServerState,
+    deploy_request_id: Option<ObjectId>,
+    exam_id: ObjectId,
+    deprecated: bool,
+) -> Result<ObjectId, Error> {
+    let Some(deploy_request_id) = deploy_request_id else {
+        return Err(Error::Server(
+            StatusCode::FORBIDDEN,
+            format!(
+                "setting deprecated on exam {exam_id} in production requires an approved deploy request"
+            ),
+        ));
+    };
+
+    check(state, deploy_request_id, exam_id, |deploy_request| {
+        deploy_request.deprecated == Some(deprecated)
+    })
+    .await
+}
+
+/// Checks the deploy request is approved, and is for the exam and the change being made.
+///
+/// Checked before making the change, so an unapproved change is refused without writing anything.
+/// The request is only marked as deployed, by `complete_deploy_request`, in the change's transaction.
+async fn check(
+    state: &ServerState,
+    deploy_request_id: ObjectId,
+    exam_id: ObjectId,
+    is_for_change: impl FnOnce(&ExamCreatorDeployRequest) -> bool,
+) -> Result<ObjectId, Error> {
+    let deploy_request = find_deploy_request(state, deploy_request_id).await?;
+
+    if deploy_request.status != DeployRequestStatus::Approved {
+        return Err(Error::Server(
+            StatusCode::FORBIDDEN,
+            format!(
+                "deploy request {deploy_request_id} is {:?}, not Approved",
+                deploy_request.status
+            ),
+        ));
+    }
+    if deploy_request.exam_id != exam_id || !is_for_change(&deploy_request) {
+        return Err(Error::Server(
+            StatusCode::FORBIDDEN,
+            format!(
+                "deploy request {deploy_request_id} is for exam {}, to {}",
+                deploy_request.exam_id,
+                deploy_request.describe()
+            ),
+        ));
+    }
+
+    Ok(deploy_request_id)
+}
+
+/// Marks the approved deploy request as deployed, by the deployment if it deployed the exam,
+/// in the session's transaction on the production database.
+///
+/// Made in the same transaction as the request's change, so the request is deployed if, and only if, the change is.
+/// A concurrent run of the same request conflicts with the transaction, or finds the request no longer approved.
+pub async fn complete_deploy_request(
+    production_database: &Database,
+    session: &mut ClientSession,
+    deploy_request_id: ObjectId,
+    actor: &prisma::ExamCreatorUser,
+    deployment_id: Option<ObjectId>,
+) -> Result<(), Error> {
+    let transition = DeployRequestTransition::new(DeployRequestStatus::Deployed, actor, None);
+    let update_result = production_database
+        .exam_creator_deploy_request
+        .update_one(
+            doc! {
+                "_id": deploy_request_id,
+                "status": bson::serialize_to_bson(&DeployRequestStatus::Approved)?,
+            },
+            doc! {
+                "$set": {
+                    "status": bson::serialize_to_bson(&transition.status)?,
+                    "deploymentId": deployment_id,
+                    "updatedAt": transition.created_at,
+                },
+                "$push": { "transitions": bson::serialize_to_bson(&transition)? },
+            },
+        )
+        .session(&mut *session)
+        .await
+        .map_err(|e| Error::Deployment("marking deploy request as deployed", e))?;
+
+    // Another run of the same request deployed it first
+    if update_result.matched_count == 0 {
+        return Err(Error::Server(
+            StatusCode::CONFLICT,
+            format!("deploy request {deploy_request_id} is no longer Approved"),
+        ));
+    }
+
+    Ok(())
+}
+
+pub async fn find_deploy_request(
+    state: &ServerState,
+    deploy_request_id: ObjectId,
+) -> Result<ExamCreatorDeployRequest, Error> {
+    state
+        .production_database
+        .exam_creator_deploy_request
+        .find_one(doc! { "_id": deploy_request_id })
+        .await?
+        .ok_or(Error::Server(
+            StatusCode::BAD_REQUEST,
+            format!("deploy request non-existent: {deploy_request_id}"),
+        ))
+}
+
+/// Approves or rejects a pending deploy request, by a user other than the requester
+async fn review_deploy_request(
+    state: &ServerState,
+    deploy_request_id: ObjectId,
+    transition: DeployRequestTransition,
+) -> Result<ExamCreatorDeployRequest, Error> {
+    let deploy_request = find_deploy_request(state, deploy_request_id).await?;
+    if deploy_request.requester_id == transition.actor_id {
+        return Err(Error::Server(
+            StatusCode::FORBIDDEN,
+            format!("deploy request {deploy_request_id} must be reviewed by another user"),
+        ));
+    }
+
+    let deploy_request = state
+        .production_database
+        .exam_creator_deploy_request
+        .find_one_and_update(
+            doc! {
+                "_id": deploy_request_id,
+                "status": bson::serialize_to_bson(&DeployRequestStatus::Pending)?,
+            },
+            doc! {
+                "$set": {
+                    "status": bson::serialize_to_bson(&transition.status)?,
+                    "updatedAt": transition.created_at,
+                },
+                "$push": { "transitions": bson::serialize_to_bson(&transition)? },
+            },
+        )
+        .return_document(ReturnDocument::After)
+        .await?;
+
+    let Some(deploy_request) = deploy_request else {
+        let deploy_request = find_deploy_request(state, deploy_request_id).await?;
+        return Err(Error::Server(
+            StatusCode::CONFLICT,
+            format!(
+                "deploy request {deploy_request_id} cannot be set to {:?} from {:?}",
+                transition.status, deploy_request.status
+            ),
+        ));
+    };
+    info!(
+        "Deploy request {deploy_request_id} set to {:?} by {}",
+        deploy_request.status, transition.actor_email
+    );
+
+    Ok(deploy_request)
+}
@@ -0,0 +1,125 @@
+use axum::{
+    Json,
+    extract::{Path, Query, State},
+};
+use futures_util::TryStreamExt;
+use http::StatusCode;
+use mongodb::bson::{doc, oid::ObjectId};
+use serde::Deserialize;
+use tracing::{info, instrument};
+
+use crate::{
+    database::{
+        deployment::{ExamCreatorExamDeployment, ExamCreatorExamDeploymentSummary},
+        prisma,
+    },
+    deployment::{self, DeploymentPreview},
+    errors::Error,
+    routes::exams::{PutExamSeedQuery, deploy_exam},
+    state::ServerState,
+};
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct GetDeploymentsQuery {
+    pub database_environment: Option<prisma::ExamCreatorDatabaseEnvironment>,
+}
+
+/// Get all deployments of an exam, newest first.
+///
+/// The exam documents and challenge mappings are removed, as they are not needed for listing.
+#[instrument(skip_all, err(Debug), level = "debug")]
+pub async fn get_deployments_by_exam_id(
+    _: prisma::ExamCreatorUser,
+    State(state): State<ServerState>,
+    Path(exam_id): Path<ObjectId>,
+    Query(GetDeploymentsQuery {
+        database_environment,
+    }): Query<GetDeploymentsQuery>,
+) -> Result<Json<Vec<ExamCreatorExamDeploymentSummary>>, Error> {
+    let mut filter = doc! { "examId": exam_id };
+    if let Some(database_environment) = database_environment {
+        filter.insert(
+            "databaseEnvironment",
+            bson::serialize_to_bson(&database_environment)?,
+        );
+    }
+
+    let deployments: Vec<ExamCreatorExamDeploymentSummary> = state
+        .production_database
+        .exam_creator_exam_deployment
+        .clone_with_type::<ExamCreatorExamDeploymentSummary>()
+        .find(filter)
+        .projection(doc! { "exam": false, "challenges": false, "previousExam": false })
+        .sort(doc! { "createdAt": -1 })
+        .await?
+        .try_collect()
+        .await?;
+
+    Ok(Json(deployments))
+}
+
+#[instrument(skip_all, err(Debug), level = "debug")]
+pub async fn get_deployment_by_id(
+    _: prisma::ExamCreatorUser,
+    State(state): State<ServerState>,
+    Path((exam_id, deployment_id)): Path<(ObjectId, ObjectId)>,
+) -> Result<Json<ExamCreatorExamDeployment>, Error> {
+    let deployment = find_deployment(&state, exam_id, deployment_id).await?;
+
+    Ok(Json(deployment))
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PutRollbackDeploymentQuery {
+    /// Approved deploy request to roll back to the deployment, required to roll back production
+    pub deploy_request_id: Option<ObjectId>,
+}
+
+/// Deploys the exam, and challenge mappings, of the given deployment to its database environment again.
+///
+/// The rollback is itself recorded as a new deployment, so it can be rolled back.
+/// Rolling back production requires a deploy request, approved to roll back to the deployment.
+#[instrument(skip_all, err(Debug), level = "debug")]
+pub async fn put_rollback_deployment(
+    auth_user: prisma::ExamCreatorUser,
+    State(state): State<ServerState>,
+    Path((exam_id, deployment_id)): Path<(ObjectId, ObjectId)>,
+    Query(query): Query<PutExamSeedQuery>,
+    Query(PutRollbackDeploymentQuery { deploy_request_id }): Query<PutRollbackDeploymentQuery>,
+) -> Result<Json<DeploymentPreview>, Error> {
+    let deployment = find_deployment(&state, exam_id, deployment_id).await?;
+    let dry_run = query.dry_run;
+
+    let preview = deploy_exam(
+        &state,
+        &auth_user,
+        deployment.database_environment.clone(),
+        deployment::rollback_plan(deployment, deploy_request_id),
+        query,
+    )
+    .await?;
+
+    if !dry_run {
+        info!("Rolled back exam {exam_id} to deployment {deployment_id}");
+    }
+
+    Ok(Json(preview))
+}
+
+pub async fn find_deployment(
+    state: &ServerState,
+    exam_id: ObjectId,
+    deployment_id: ObjectId,
+) -> Result<ExamCreatorExamDeployment, Error> {
+    state
+        .production_database
+        .exam_creator_exam_deployment
+        .find_one(doc! { "_id": deployment_id, "examId": exam_id })
+        .await?
+        .ok_or(Error::Server(
+            StatusCode::BAD_REQUEST,
+            format!("deployment non-existent: {deployment_id}"),
+        ))
+}
@@ -0,0 +1,177 @@
+use axum::{
+    Json,
+    extract::{Path, Query, State},
+};
+use futures_util::TryStreamExt;
+use http::StatusCode;
+use mongodb::bson::oid::ObjectId;
+use mongodb::bson::{DateTime, doc};
+use serde::{Deserialize, Serialize};
+use tracing::{info, instrument};
+
+use crate::{
+    database::{draft::ExamCreatorExamDraft, prisma},
+    errors::Error,
+    routes::websocket::broadcast_to_exam_room,
+    state::{ExamSync, ServerState, SocketEvents},
+};
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct GetDraftsQuery {
+    pub exam_id: Option<ObjectId>,
+    /// Email of a user who edited the draft
+    pub editor: Option<String>,
+}
+
+/// An `ExamCreatorExamDraft` without the exam document
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ExamDraftSummary {
+    pub exam_id: ObjectId,
+    pub exam_name: String,
+    pub editors: Vec<String>,
+    pub updated_by: String,
+    pub updated_at: DateTime,
+}
+
+/// Get all exams with unsaved edits, most recently edited first.
+///
+/// Drafts are read from `ClientSync.exams`, so include edits not yet persisted.
+#[instrument(skip_all, err(Debug), level = "debug")]
+pub async fn get_drafts(
+    _: prisma::ExamCreatorUser,
+    State(state): State<ServerState>,
+    Query(GetDraftsQuery { exam_id, editor }): Query<GetDraftsQuery>,
+) -> Result<Json<Vec<ExamDraftSummary>>, Error> {
+    let mut drafts: Vec<ExamDraftSummary> = {
+        let client_sync = state.client_sync.lock().unwrap();
+        client_sync
+            .exams
+            .iter()
+            .filter(|synced_exam| exam_id.is_none_or(|id| synced_exam.exam.id == id))
+            .filter_map(ExamCreatorExamDraft::new)
+            .filter(|draft| {
+                editor
+                    .as_ref()
+                    .is_none_or(|editor| draft.editors.contains(editor))
+            })
+            .map(|draft| ExamDraftSummary {
+                exam_id: draft.id,
+                exam_name: draft.exam.config.name,
+                editors: draft.editors,
+                updated_by: draft.updated_by,
+                updated_at: draft.updated_at,
+            })
+            .collect()
+    };
+    drafts.sort_by(|a, b| b.updated_at.cmp(&a.updated_at));
+
+    Ok(Json(drafts))
+}
+
+#[instrument(skip_all, err(Debug), level = "debug")]
+pub async fn get_draft_by_exam_id(
+    _: prisma::ExamCreatorUser,
+    State(state): State<ServerState>,
+    Path(exam_id): Path<ObjectId>,
+) -> Result<Json<ExamCreatorExamDraft>, Error> {
+    let draft = {
+        let client_sync = state.client_sync.lock().unwrap();
+        client_sync
+            .exams
+            .iter()
+            .find(|synced_exam| synced_exam.exam.id == exam_id)
+            .and_then(ExamCreatorExamDraft::new)
+    };
+
+    let draft = draft.ok_or(Error::Server(
+        StatusCode::BAD_REQUEST,
+        format!("draft non-existent: {exam_id}"),
+    ))?;
+
+    Ok(Json(draft))
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DiscardedDrafts {
+    /// Exams reset to the saved exam
+    pub exam_ids: Vec<ObjectId>,
+    /// Exams also edited by other users, so left as they are
+    pub shared_exam_ids: Vec<ObjectId>,
+}
+
+/// Discards the drafts only the user has edited, resetting their exams to the saved exam.
+///
+/// Drafts shared with other editors are kept, as discarding them would discard others' edits.
+#[instrument(skip_all, err(Debug), level = "debug")]
+pub async fn delete_drafts_by_editor(
+    auth_user: prisma::ExamCreatorUser,
+    State(state): State<ServerState>,
+) -> Result<Json<DiscardedDrafts>, Error> {
+    let editor = auth_user.email;
+    let is_sole_editor = |editors: &[String]| editors.iter().all(|e| *e == editor);
+
+    let (exam_ids, shared_exam_ids): (Vec<ObjectId>, Vec<ObjectId>) = {
+        let client_sync = state.client_sync.lock().unwrap();
+        let (sole, shared): (Vec<_>, Vec<_>) = client_sync
+            .exams
+            .iter()
+            .filter(|synced_exam| synced_exam.draft.updated.is_some())
+            .filter(|synced_exam| synced_exam.draft.editors.contains(&editor))
+            .partition(|synced_exam| is_sole_editor(&synced_exam.draft.editors));
+        (
+            sole.iter().map(|synced_exam| synced_exam.exam.id).collect(),
+            shared
+                .iter()
+                .map(|synced_exam| synced_exam.exam.id)
+                .collect(),
+        )
+    };
+
+    if exam_ids.is_empty() {
+        return Ok(Json(DiscardedDrafts {
+            exam_ids,
+            shared_exam_ids,
+        }));
+    }
+
+    let saved_exams: Vec<prisma::ExamCreatorExam> = state
+        .production_database
+        .exam_creator_exam
+        .find(doc! { "_id": { "$in": exam_ids } })
+        .await?
+        .try_collect()
+        .await?;
+
+    // Lock must not be held across await, so drafts edited by others since are kept.
+    let exam_ids: Vec<ObjectId> = {
+        let mut client_sync = state.client_sync.lock().unwrap();
+        saved_exams
+            .into_iter()
+            .filter_map(|exam| {
+                let synced_exam = client_sync
+                    .exams
+                    .iter_mut()
+                    .find(|synced_exam| synced_exam.exam.id == exam.id)?;
+                if synced_exam.draft.updated.is_none()
+                    || !is_sole_editor(&synced_exam.draft.editors)
+                {
+                    return None;
+                }
+                let exam_id = exam.id;
+                let seq = synced_exam.reset(exam.clone());
+                // Sent under the lock, so the room receives it in order with merged patches
+                broadcast_to_exam_room(exam_id, &SocketEvents::ExamSync(ExamSync { exam, seq }));
+                Some(exam_id)
+            })
+            .collect()
+    };
+    info!("Discarded {} drafts of {editor}", exam_ids.len());
+
+    Ok(Json(DiscardedDrafts {
+        exam_ids,
+        shared_exam_ids,
+    }))
+}
@@ -1,25 +1,29 @@
 use axum::{
     Json,
-    extract::{Path, State},
-    response::IntoResponse,
+    extract::{Path, Query, State},
+    response::{IntoResponse, Response},
 };
-use axum_streams::StreamBodyAs;
 use bson::Document;
 use futures_util::TryStreamExt;
 use http::StatusCode;
-use mongodb::bson::doc;
+use mongodb::ClientSession;
 use mongodb::bson::oid::ObjectId;
+use mongodb::bson::{DateTime, doc};
 use serde::{Deserialize, Serialize};
-use tokio::sync::mpsc;
-use tokio_stream::wrappers::ReceiverStream;
-use tracing::{info, instrument};
+use std::str::FromStr;
+use tracing::{error, info, instrument, warn};
 
 use crate::{
-    config,
-    database::{Database, prisma},
+    compatibility::{self, OrphanedGenerationsAction},
+    database::{Database, deployment::ExamCreatorExamDeployment, prisma, revision},
+    deployment::{self, DeploymentPlan, DeploymentPreview},
+    diff::{self, ChangeKind},
+    drift,
     errors::Error,
-    generate,
+    generate, jobs, lint,
+    routes::{deploy_requests, websocket},
     state::ServerState,
+    validation,
 };
 
 #[derive(Serialize)]
@@ -83,6 +87,35 @@ pub async fn get_exams(
     Ok(Json(exams))
 }
 
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct GetExamsDriftQuery {
+    /// Create a new report, instead of returning the latest background report
+    #[serde(default)]
+    pub refresh: bool,
+}
+
+/// Get how each exam deployed to staging and production compares to the exam creator.
+#[instrument(skip_all, err(Debug), level = "debug")]
+pub async fn get_exams_drift(
+    _: prisma::ExamCreatorUser,
+    State(state): State<ServerState>,
+    Query(GetExamsDriftQuery { refresh }): Query<GetExamsDriftQuery>,
+) -> Result<Json<drift::DriftReport>, Error> {
+    if !refresh {
+        let drift_report = state.drift_report.lock().unwrap().clone();
+        if let Some(drift_report) = drift_report {
+            return Ok(Json(drift_report));
+        }
+    }
+
+    let drift_report =
+        drift::drift_report(&state.production_database, &state.staging_database).await?;
+    *state.drift_report.lock().unwrap() = Some(drift_report.clone());
+
+    Ok(Json(drift_report))
+}
+
 #[instrument(skip_all, err(Debug), level = "debug")]
 pub async fn get_exam_by_id(
     _auth_user: prisma::ExamCreatorUser,
@@ -118,29 +151,38 @@ pub async fn get_exam_by_id(
 /// Create an exam
 #[instrument(skip_all, err(Debug), level = "debug")]
 pub async fn post_exam(
-    _: prisma::ExamCreatorUser,
+    auth_user: prisma::ExamCreatorUser,
     State(state): State<ServerState>,
 ) -> Result<Json<prisma::ExamCreatorExam>, Error> {
     info!("post_exam");
     let exam = prisma::ExamCreatorExam::default();
 
-    state
-        .production_database
-        .exam_creator_exam
-        .insert_one(&exam)
-        .await?;
+    revision::save_exam(&state.production_database, &exam, None, &auth_user, None).await?;
 
     Ok(Json(exam))
 }
 
+#[derive(Deserialize)]
+pub struct PutExamQuery {
+    /// Sequence number of the last exam room patch included in the exam, if it was edited in the exam room
+    pub seq: Option<u64>,
+}
+
 /// Update an exam
+///
+/// The given exam's `version` must match the saved exam's `version`, otherwise the exam has been saved
+/// by someone else in the meantime, and a `409` is returned with an `ExamConflict` body.
+/// On success, the `version` is incremented.
+///
+/// Every save is recorded as an `ExamCreatorExamRevision`
 #[instrument(skip_all, err(Debug), level = "debug")]
 pub async fn put_exam(
-    _: prisma::ExamCreatorUser,
+    auth_user: prisma::ExamCreatorUser,
     State(state): State<ServerState>,
     Path(exam_id): Path<ObjectId>,
-    Json(exam): Json<prisma::ExamCreatorExam>,
-) -> Result<Json<prisma::ExamCreatorExam>, Error> {
+    Query(PutExamQuery { seq }): Query<PutExamQuery>,
+    Json(mut exam): Json<prisma::ExamCreatorExam>,
+) -> Result<Response, Error> {
     if exam.id != exam_id {
         return Err(Error::Server(
             StatusCode::BAD_REQUEST,
@@ -151,25 +193,193 @@ pub async fn put_exam(
         )
         .into());
     }
-    state
-        .production_database
-        .exam_creator_exam
-        .replace_one(doc! { "_id": exam_id }, &exam)
-        .await?;
 
-    Ok(Json(exam))
+    let base_version = exam.version;
+    exam.version = base_version + 1;
+
+    let saved = revision::save_exam(
+        &state.production_database,
+        &exam,
+        Some(base_version),
+        &auth_user,
+        None,
+    )
+    .await?;
+
+    if saved.is_none() {
+        let current = state
+            .production_database
+            .exam_creator_exam
+            .find_one(doc! { "_id": exam_id })
+            .await?
+            .ok_or(Error::Server(
+                StatusCode::BAD_REQUEST,
+                format!("exam non-existent: {exam_id}"),
+            ))?;
+
+        // The revision the save was based on, if history exists for it
+        let base = state
+            .production_database
+            .exam_creator_exam_revision
+            .find_one(doc! { "examId": exam_id, "exam.version": base_version })
+            .sort(doc! { "revision": -1 })
+            .await?
+            .map(|r| r.exam);
+
+        exam.version = base_version;
+        info!(
+            "Rejecting stale save of exam {exam_id}: version {base_version} != {}",
+            current.version
+        );
+
+        let conflict = ExamConflict::new(base.as_ref(), current, &exam);
+        return Ok((StatusCode::CONFLICT, Json(conflict)).into_response());
+    }
+
+    // Co-editors continue from the saved exam, so later saves are not based on an outdated version
+    websocket::sync_saved_exam(&state, &exam, seq);
+
+    Ok(Json(exam).into_response())
+}
+
+/// Response body for a save which was based on an outdated version of the exam
+#[derive(Debug, Serialize)]
+pub struct ExamConflict {
+    /// The exam as it is currently saved
+    pub current: prisma::ExamCreatorExam,
+    /// Question sets which differ between the saved exam and the submitted exam
+    #[serde(rename = "questionSets")]
+    pub question_sets: Vec<QuestionSetConflict>,
+    /// Whether the config differs between the saved exam and the submitted exam
+    #[serde(rename = "configChanged")]
+    pub config_changed: bool,
+    /// Whether the prerequisites differ between the saved exam and the submitted exam
+    #[serde(rename = "prerequisitesChanged")]
+    pub prerequisites_changed: bool,
+}
+
+#[derive(Debug, Serialize)]
+pub struct QuestionSetConflict {
+    pub id: ObjectId,
+    /// How the saved exam changed the question set since the base version
+    ///
+    /// `None` if unchanged, or if the base version is unknown
+    pub current: Option<ChangeKind>,
+    /// How the submitted exam changed the question set since the base version
+    ///
+    /// `None` if unchanged, or if the base version is unknown
+    pub submitted: Option<ChangeKind>,
+    /// Whether both sides changed the question set differently
+    ///
+    /// If the base version is unknown, every differing question set is conflicting
+    pub conflicting: bool,
+}
+
+impl ExamConflict {
+    fn new(
+        base: Option<&prisma::ExamCreatorExam>,
+        current: prisma::ExamCreatorExam,
+        submitted: &prisma::ExamCreatorExam,
+    ) -> Self {
+        let mut ids: Vec<ObjectId> = vec![];
+        for qs in current
+            .question_sets
+            .iter()
+            .chain(submitted.question_sets.iter())
+        {
+            if !ids.contains(&qs.id) {
+                ids.push(qs.id);
+            }
+        }
+
+        let find = |exam: &prisma::ExamCreatorExam, id: &ObjectId| {
+            exam.question_sets.iter().find(|qs| qs.id == *id).cloned()
+        };
+
+        let question_sets = ids
+            .into_iter()
+            .filter_map(|id| {
+                let current_qs = find(&current, &id);
+                let submitted_qs = find(submitted, &id);
+                if current_qs == submitted_qs {
+                    return None;
+                }
+
+                let Some(base) = base else {
+                    return Some(QuestionSetConflict {
+                        id,
+                        current: None,
+                        submitted: None,
+                        conflicting: true,
+                    });
+                };
+
+                let base_qs = find(base, &id);
+                let current_change = change_kind(&base_qs, &current_qs);
+                let submitted_change = change_kind(&base_qs, &submitted_qs);
+
+                Some(QuestionSetConflict {
+                    id,
+                    current: current_change,
+                    submitted: submitted_change,
+                    conflicting: current_change.is_some() && submitted_change.is_some(),
+                })
+            })
+            .collect();
+
+        Self {
+            config_changed: current.config != submitted.config,
+            prerequisites_changed: current.prerequisites != submitted.prerequisites,
+            question_sets,
+            current,
+        }
+    }
+}
+
+fn change_kind<T: PartialEq>(from: &Option<T>, to: &Option<T>) -> Option<ChangeKind> {
+    match (from, to) {
+        (None, Some(_)) => Some(ChangeKind::Added),
+        (Some(_), None) => Some(ChangeKind::Removed),
+        (Some(f), Some(t)) if f != t => Some(ChangeKind::Modified),
+        _ => None,
+    }
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PutExamSeedQuery {
+    /// What to do with live generations referencing content removed from the exam
+    #[serde(default)]
+    pub orphaned_generations: OrphanedGenerationsAction,
+    /// Only return what would change, without writing anything
+    #[serde(default)]
+    pub dry_run: bool,
 }
 
 /// Finds an exam in `ExamCreatorExam`
 /// Upserts it into staging database `ExamEnvironmentExam`
 ///
+/// Returns what was changed, or with `dryRun`, what would be changed.
+///
 /// NOTE: Staging has a special case where the `ExamEnvironmentChallenge` documents need to be copied over
 #[instrument(skip_all, err(Debug), level = "debug")]
 pub async fn put_exam_by_id_to_staging(
-    _auth_user: prisma::ExamCreatorUser,
+    auth_user: prisma::ExamCreatorUser,
     State(state): State<ServerState>,
     Path(exam_id): Path<ObjectId>,
-) -> Result<(), Error> {
+    Query(query): Query<PutExamSeedQuery>,
+) -> Result<Json<DeploymentPreview>, Error> {
+    let preview = seed_exam_to_staging(&state, &auth_user, exam_id, query).await?;
+    Ok(Json(preview))
+}
+
+/// Seeds the `ExamCreatorExam`, and its production `ExamEnvironmentChallenge` mappings, to staging
+pub async fn seed_exam_to_staging(
+    state: &ServerState,
+    auth_user: &prisma::ExamCreatorUser,
+    exam_id: ObjectId,
+    query: PutExamSeedQuery,
+) -> Result<DeploymentPreview, Error> {
     let exam_creator_exam = state
         .production_database
         .exam_creator_exam
@@ -194,42 +404,47 @@ pub async fn put_exam_by_id_to_staging(
         exam_environment_challenges.len()
     );
 
-    state
-        .staging_database
-        .exam
-        .update_one(
-            doc! {"_id": exam_id},
-            doc! {
-                "$set": bson::serialize_to_document(&exam_creator_exam)?,
-            },
-        )
-        .upsert(true)
-        .await?;
+    let revision_id = revision::latest_revision(&state.production_database, exam_id)
+        .await?
+        .map(|r| r.id);
 
-    state
-        .staging_database
-        .exam_environment_challenge
-        .delete_many(doc! {"examId": exam_id})
-        .await?;
-    if !exam_environment_challenges.is_empty() {
-        state
-            .staging_database
-            .exam_environment_challenge
-            .insert_many(exam_environment_challenges)
-            .await?;
-    }
+    deploy_exam(
+        state,
+        auth_user,
+        prisma::ExamCreatorDatabaseEnvironment::Staging,
+        DeploymentPlan {
+            exam_creator_exam,
+            exam_environment_challenges: Some(exam_environment_challenges),
+            generated_exams: vec![],
+            revision_id,
+            rolled_back_to: None,
+            deploy_request_id: None,
+        },
+        query,
+    )
+    .await
+}
 
-    Ok(())
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PutExamToProductionQuery {
+    /// Approved deploy request of the exam's latest revision, required to deploy it
+    pub deploy_request_id: Option<ObjectId>,
 }
 
 /// Finds an exam in `ExamCreatorExam`
 /// Upserts it into production database `ExamEnvironmentExam`
+///
+/// Returns what would be changed with `dryRun`.
+/// Otherwise, deploys only with `deployRequestId`, a deploy request approved for the exam's latest revision.
 #[instrument(skip_all, err(Debug), level = "debug")]
 pub async fn put_exam_by_id_to_production(
-    _auth_user: prisma::ExamCreatorUser,
+    auth_user: prisma::ExamCreatorUser,
     State(state): State<ServerState>,
     Path(exam_id): Path<ObjectId>,
-) -> Result<(), Error> {
+    Query(query): Query<PutExamSeedQuery>,
+    Query(PutExamToProductionQuery { deploy_request_id }): Query<PutExamToProductionQuery>,
+) -> Result<Json<DeploymentPreview>, Error> {
     let exam_creator_exam = state
         .production_database
         .exam_creator_exam
@@ -241,21 +456,653 @@ pub async fn put_exam_by_id_to_production(
         ))?;
     info!("Found exam {exam_id} in production database");
 
-    state
+    let revision_id = revision::latest_revision(&state.production_database, exam_id)
+        .await?
+        .map(|r| r.id);
+
+    // Production mappings are edited in place, so seeding does not change them
+    let preview = deploy_exam(
+        &state,
+        &auth_user,
+        prisma::ExamCreatorDatabaseEnvironment::Production,
+        DeploymentPlan {
+            exam_creator_exam,
+            exam_environment_challenges: None,
+            generated_exams: vec![],
+            revision_id,
+            rolled_back_to: None,
+            deploy_request_id,
+        },
+        query,
+    )
+    .await?;
+
+    Ok(Json(preview))
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PutPromoteExamBody {
+    /// Approved deploy request of the revision reviewed in staging
+    pub deploy_request_id: ObjectId,
+    /// Staging generations to copy to production
+    #[serde(default)]
+    pub generated_exam_ids: Vec<ObjectId>,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PromotedExam {
+    pub deployment: DeploymentPreview,
+    /// Generations copied to production, excluding those already there
+    pub generated_exam_ids: Vec<ObjectId>,
+}
+
+/// Copies the staging `ExamEnvironmentExam`, its `ExamEnvironmentChallenge` mappings,
+/// and the selected generations, to production.
+///
+/// Refuses to promote if the staging exam differs from the deploy request's revision,
+/// or the deploy request is not approved.
+/// Generation seeds are not copied, as they reference staging's generation jobs,
+/// so promoted generations can only be reproduced in staging.
+#[instrument(skip_all, err(Debug), level = "debug")]
+pub async fn put_promote_exam_by_id(
+    auth_user: prisma::ExamCreatorUser,
+    State(state): State<ServerState>,
+    Path(exam_id): Path<ObjectId>,
+    Query(query): Query<PutExamSeedQuery>,
+    Json(body): Json<PutPromoteExamBody>,
+) -> Result<Json<PromotedExam>, Error> {
+    let staging_exam = state
+        .staging_database
+        .exam
+        .find_one(doc! { "_id": exam_id })
+        .await?
+        .ok_or(Error::Server(
+            StatusCode::BAD_REQUEST,
+            format!("exam non-existent in staging: {exam_id}"),
+        ))?;
+
+    let deploy_request = state
+        .production_database
+        .exam_creator_deploy_request
+        .find_one(doc! { "_id": body.deploy_request_id, "examId": exam_id })
+        .await?
+        .ok_or(Error::Server(
+            StatusCode::BAD_REQUEST,
+            format!("deploy request non-existent: {}", body.deploy_request_id),
+        ))?;
+    let revision = state
         .production_database
+        .exam_creator_exam_revision
+        .find_one(doc! { "_id": deploy_request.revision_id })
+        .await?
+        .ok_or(Error::Server(
+            StatusCode::BAD_REQUEST,
+            format!("revision non-existent: {}", deploy_request.revision_id),
+        ))?;
+    // Only content is compared, as `deprecated` and `version` are changed by deploying
+    let staging_differs = revision.exam.question_sets != staging_exam.question_sets
+        || revision.exam.config != staging_exam.config
+        || revision.exam.prerequisites != staging_exam.prerequisites;
+    if staging_differs {
+        return Err(Error::Server(
+            StatusCode::CONFLICT,
+            format!(
+                "staging exam {exam_id} differs from revision {}. Seed the revision to staging, and review it again, to promote",
+                revision.revision
+            ),
+        ));
+    }
+    let exam_creator_exam: prisma::ExamCreatorExam = staging_exam.into();
+
+    let exam_environment_challenges: Vec<prisma::ExamEnvironmentChallenge> = state
+        .staging_database
+        .exam_environment_challenge
+        .find(doc! {"examId": exam_id})
+        .await?
+        .try_collect()
+        .await?;
+
+    let generated_exams: Vec<prisma::ExamEnvironmentGeneratedExam> = state
+        .staging_database
+        .generated_exam
+        .find(doc! { "_id": { "$in": body.generated_exam_ids.clone() }, "examId": exam_id })
+        .await?
+        .try_collect()
+        .await?;
+    if let Some(missing) = body
+        .generated_exam_ids
+        .iter()
+        .find(|id| !generated_exams.iter().any(|g| g.id == **id))
+    {
+        return Err(Error::Server(
+            StatusCode::BAD_REQUEST,
+            format!("generation non-existent in staging: {missing}"),
+        ));
+    }
+    let orphaned_generations =
+        compatibility::orphaned_generations(&exam_creator_exam, &generated_exams);
+    if !orphaned_generations.is_empty() {
+        return Err(Error::Server(
+            StatusCode::CONFLICT,
+            format!(
+                "{} selected generations reference content not in exam {exam_id}",
+                orphaned_generations.len()
+            ),
+        ));
+    }
+
+    // Generations already in production are left as they are
+    let existing: Vec<Document> = state
+        .production_database
+        .generated_exam
+        .clone_with_type::<Document>()
+        .find(doc! { "_id": { "$in": body.generated_exam_ids } })
+        .projection(doc! { "_id": true })
+        .await?
+        .try_collect()
+        .await?;
+    let existing = existing
+        .iter()
+        .map(|generated_exam| generated_exam.get_object_id("_id"))
+        .collect::<Result<Vec<ObjectId>, _>>()?;
+    let generated_exams: Vec<prisma::ExamEnvironmentGeneratedExam> = generated_exams
+        .into_iter()
+        .filter(|generated_exam| !existing.contains(&generated_exam.id))
+        .collect();
+    let generated_exam_ids: Vec<ObjectId> = generated_exams.iter().map(|g| g.id).collect();
+
+    let dry_run = query.dry_run;
+    let deployment = deploy_exam(
+        &state,
+        &auth_user,
+        prisma::ExamCreatorDatabaseEnvironment::Production,
+        DeploymentPlan {
+            exam_creator_exam,
+            exam_environment_challenges: Some(exam_environment_challenges),
+            generated_exams,
+            revision_id: Some(revision.id),
+            rolled_back_to: None,
+            deploy_request_id: Some(body.deploy_request_id),
+        },
+        query,
+    )
+    .await?;
+
+    if !dry_run {
+        info!(
+            "Promoted exam {exam_id} at revision {} with {} generations",
+            revision.revision,
+            generated_exam_ids.len()
+        );
+    }
+
+    Ok(Json(PromotedExam {
+        deployment,
+        generated_exam_ids,
+    }))
+}
+
+/// Upserts the exam into the database environment's `ExamEnvironmentExam`,
+/// replaces its `ExamEnvironmentChallenge` mappings if given, and records the deployment.
+///
+/// Every write to the database environment is made in one transaction, so a failed deployment changes nothing.
+/// Deploying to production requires the plan's approved deploy request, which is marked as deployed,
+/// and the deployment recorded, in the same transaction.
+/// Production is not deployed an earlier version of the exam, except by a rollback.
+///
+/// Returns what was changed, or with `dry_run`, what would be changed without writing anything.
+pub async fn deploy_exam(
+    state: &ServerState,
+    auth_user: &prisma::ExamCreatorUser,
+    database_environment: prisma::ExamCreatorDatabaseEnvironment,
+    plan: DeploymentPlan,
+    PutExamSeedQuery {
+        orphaned_generations,
+        dry_run,
+    }: PutExamSeedQuery,
+) -> Result<DeploymentPreview, Error> {
+    let database = match database_environment {
+        prisma::ExamCreatorDatabaseEnvironment::Staging => &state.staging_database,
+        prisma::ExamCreatorDatabaseEnvironment::Production => &state.production_database,
+    };
+    let exam_id = plan.exam_creator_exam.id;
+
+    let deployed_exam = database.exam.find_one(doc! { "_id": exam_id }).await?;
+    check_deployed_version(&database_environment, &plan, deployed_exam.as_ref())?;
+    let preview = preview_seed(
+        database,
+        &plan.exam_creator_exam,
+        deployed_exam.clone(),
+        plan.exam_environment_challenges.as_deref(),
+    )
+    .await?;
+    if dry_run {
+        return Ok(preview);
+    }
+
+    let deprecated_generations =
+        handle_orphaned_generations(exam_id, &preview.orphaned_generations, orphaned_generations)?;
+
+    let deploy_request_id = match database_environment {
+        prisma::ExamCreatorDatabaseEnvironment::Staging => None,
+        prisma::ExamCreatorDatabaseEnvironment::Production => {
+            Some(deploy_requests::check_deploy_request(state, &plan).await?)
+        }
+    };
+    let deployment = ExamCreatorExamDeployment {
+        id: ObjectId::new(),
+        exam_id,
+        database_environment,
+        actor_id: auth_user.id,
+        actor_name: auth_user.name.clone(),
+        actor_email: auth_user.email.clone(),
+        created_at: DateTime::now(),
+        revision_id: plan.revision_id,
+        rolled_back_to: plan.rolled_back_to,
+        deploy_request_id,
+        exam: plan.exam_creator_exam.clone(),
+        challenges: plan.exam_environment_challenges.clone(),
+        previous_exam: deployed_exam,
+    };
+    write_and_record_deployment(
+        state,
+        auth_user,
+        database,
+        &deployment,
+        &plan,
+        deprecated_generations,
+    )
+    .await?;
+    // Report no longer reflects the deployed exam, so is created again on next request
+    *state.drift_report.lock().unwrap() = None;
+
+    Ok(preview)
+}
+
+/// Writes the deployment to `database` in one transaction, and records it.
+///
+/// Production deployments are recorded, and complete their deploy request, in the same transaction.
+/// Staging deployments are recorded in production once the transaction commits.
+/// A failure to record one is logged rather than returned, as the exam is deployed either way.
+async fn write_and_record_deployment(
+    state: &ServerState,
+    auth_user: &prisma::ExamCreatorUser,
+    database: &Database,
+    deployment: &ExamCreatorExamDeployment,
+    plan: &DeploymentPlan,
+    deprecated_generations: Vec<ObjectId>,
+) -> Result<(), Error> {
+    let exam_id = deployment.exam_id;
+
+    let mut session = database
+        .client
+        .start_session()
+        .await
+        .map_err(|e| Error::Deployment("starting transaction", e))?;
+    session
+        .start_transaction()
+        .await
+        .map_err(|e| Error::Deployment("starting transaction", e))?;
+    let written = async {
+        write_deployment(
+            database,
+            &mut session,
+            &deployment.database_environment,
+            plan,
+            deprecated_generations,
+        )
+        .await?;
+        // Only production deployments have a deploy request, so are recorded in the database deployed to
+        if let Some(deploy_request_id) = deployment.deploy_request_id {
+            database
+                .exam_creator_exam_deployment
+                .insert_one(deployment)
+                .session(&mut session)
+                .await
+                .map_err(|e| Error::Deployment("recording deployment", e))?;
+            deploy_requests::complete_deploy_request(
+                database,
+                &mut session,
+                deploy_request_id,
+                auth_user,
+                Some(deployment.id),
+            )
+            .await?;
+        }
+        Ok::<_, Error>(())
+    };
+    if let Err(e) = written.await {
+        // Aborting discards the writes already made. If aborting fails, the server aborts the
+        // transaction once it times out, so the error is not reported over the step which failed.
+        if let Err(abort_error) = session.abort_transaction().await {
+            warn!("Failed to abort deployment of exam {exam_id}: {abort_error}");
+        }
+        return Err(e);
+    }
+    session
+        .commit_transaction()
+        .await
+        .map_err(|e| Error::Deployment("committing transaction", e))?;
+    info!(
+        "Deployed exam {exam_id} to {:?} as deployment {}",
+        deployment.database_environment, deployment.id
+    );
+
+    // Staging deployments are recorded in production, so outside the transaction
+    if deployment.deploy_request_id.is_none()
+        && let Err(e) = state
+            .production_database
+            .exam_creator_exam_deployment
+            .insert_one(deployment)
+            .await
+    {
+        error!(
+            "Failed to record deployment {} of exam {exam_id}: {e}",
+            deployment.id
+        );
+    }
+
+    Ok(())
+}
+
+/// Rejects deploying an earlier version of the exam over a later one in production, unless rolling back,
+/// so a request approved before a later deploy cannot undo it
+fn check_deployed_version(
+    database_environment: &prisma::ExamCreatorDatabaseEnvironment,
+    plan: &DeploymentPlan,
+    deployed_exam: Option<&prisma::ExamEnvironmentExam>,
+) -> Result<(), Error> {
+    let (prisma::ExamCreatorDatabaseEnvironment::Production, Some(deployed_exam), None) =
+        (database_environment, deployed_exam, plan.rolled_back_to)
+    else {
+        return Ok(());
+    };
+    if deployed_exam.version > plan.exam_creator_exam.version {
+        return Err(Error::Server(
+            StatusCode::CONFLICT,
+            format!(
+                "production holds version {} of exam {}, which is newer than version {}",
+                deployed_exam.version, plan.exam_creator_exam.id, plan.exam_creator_exam.version
+            ),
+        ));
+    }
+
+    Ok(())
+}
+
+/// Makes the deployment's writes to `database` in the session's transaction
+async fn write_deployment(
+    database: &Database,
+    session: &mut ClientSession,
+    database_environment: &prisma::ExamCreatorDatabaseEnvironment,
+    plan: &DeploymentPlan,
+    deprecated_generations: Vec<ObjectId>,
+) -> Result<(), Error> {
+    let exam_id = plan.exam_creator_exam.id;
+
+    // Checked again in the transaction, which conflicts with any deployment of the exam since
+    let deployed_exam = database
+        .exam
+        .find_one(doc! { "_id": exam_id })
+        .session(&mut *session)
+        .await
+        .map_err(|e| Error::Deployment("finding deployed exam", e))?;
+    check_deployed_version(database_environment, plan, deployed_exam.as_ref())?;
+
+    if !deprecated_generations.is_empty() {
+        let count = deprecated_generations.len();
+        database
+            .generated_exam
+            .update_many(
+                doc! { "_id": { "$in": deprecated_generations } },
+                doc! { "$set": { "deprecated": true } },
+            )
+            .session(&mut *session)
+            .await
+            .map_err(|e| Error::Deployment("deprecating orphaned generations", e))?;
+        info!("Deprecating {count} orphaned generations of exam {exam_id}");
+    }
+
+    database
         .exam
         .update_one(
             doc! {"_id": exam_id},
             doc! {
-                "$set": bson::serialize_to_document(&exam_creator_exam)?,
+                "$set": bson::serialize_to_document(&plan.exam_creator_exam)?,
             },
         )
         .upsert(true)
-        .await?;
+        .session(&mut *session)
+        .await
+        .map_err(|e| Error::Deployment("upserting exam", e))?;
+
+    if let Some(exam_environment_challenges) = &plan.exam_environment_challenges {
+        database
+            .exam_environment_challenge
+            .delete_many(doc! {"examId": exam_id})
+            .session(&mut *session)
+            .await
+            .map_err(|e| Error::Deployment("deleting challenge mappings", e))?;
+        if !exam_environment_challenges.is_empty() {
+            database
+                .exam_environment_challenge
+                .insert_many(exam_environment_challenges)
+                .session(&mut *session)
+                .await
+                .map_err(|e| Error::Deployment("inserting challenge mappings", e))?;
+        }
+    }
+
+    if !plan.generated_exams.is_empty() {
+        database
+            .generated_exam
+            .insert_many(&plan.generated_exams)
+            .session(&mut *session)
+            .await
+            .map_err(|e| Error::Deployment("inserting generations", e))?;
+    }
 
     Ok(())
 }
 
+/// Compares the exam, and the challenge mappings to deploy with it, against those deployed to `database`.
+///
+/// Without challenge mappings, the deployed mappings are kept.
+async fn preview_seed(
+    database: &Database,
+    exam_creator_exam: &prisma::ExamCreatorExam,
+    deployed_exam: Option<prisma::ExamEnvironmentExam>,
+    exam_environment_challenges: Option<&[prisma::ExamEnvironmentChallenge]>,
+) -> Result<DeploymentPreview, Error> {
+    let exam_id = exam_creator_exam.id;
+    let deployed_challenges: Vec<prisma::ExamEnvironmentChallenge> = database
+        .exam_environment_challenge
+        .find(doc! {"examId": exam_id})
+        .await?
+        .try_collect()
+        .await?;
+    let generated_exams: Vec<prisma::ExamEnvironmentGeneratedExam> = database
+        .generated_exam
+        .find(doc! { "examId": exam_id, "deprecated": false })
+        .await?
+        .try_collect()
+        .await?;
+
+    Ok(deployment::preview_deployment(
+        exam_creator_exam,
+        deployed_exam,
+        exam_environment_challenges.unwrap_or(&deployed_challenges),
+        &deployed_challenges,
+        &generated_exams,
+    ))
+}
+
+/// Applies `action` to the exam's live generations orphaned by the exam about to be deployed.
+///
+/// Returns the generations to deprecate with the deployment.
+fn handle_orphaned_generations(
+    exam_id: ObjectId,
+    orphaned_generations: &[compatibility::OrphanedGeneration],
+    action: OrphanedGenerationsAction,
+) -> Result<Vec<ObjectId>, Error> {
+    if orphaned_generations.is_empty() {
+        return Ok(vec![]);
+    }
+
+    match action {
+        OrphanedGenerationsAction::Block => Err(Error::Server(
+            StatusCode::CONFLICT,
+            format!(
+                "{} live generations of exam {exam_id} reference content removed from the exam. Deprecate them, or ignore them, to deploy",
+                orphaned_generations.len()
+            ),
+        )),
+        OrphanedGenerationsAction::Deprecate => Ok(orphaned_generations
+            .iter()
+            .map(|orphaned| orphaned.generated_exam_id)
+            .collect()),
+        OrphanedGenerationsAction::Ignore => {
+            warn!(
+                "Deploying exam {exam_id} with {} orphaned generations",
+                orphaned_generations.len()
+            );
+            Ok(vec![])
+        }
+    }
+}
+
+/// Get the exam's live generations which reference content no longer in the exam
+#[instrument(skip_all, err(Debug), level = "debug")]
+pub async fn get_orphaned_generations_by_exam_id(
+    _auth_user: prisma::ExamCreatorUser,
+    State(state): State<ServerState>,
+    Path((exam_id, database_environment)): Path<(ObjectId, prisma::ExamCreatorDatabaseEnvironment)>,
+) -> Result<Json<Vec<compatibility::OrphanedGeneration>>, Error> {
+    let database = match database_environment {
+        prisma::ExamCreatorDatabaseEnvironment::Staging => &state.staging_database,
+        prisma::ExamCreatorDatabaseEnvironment::Production => &state.production_database,
+    };
+
+    let exam_creator_exam = state
+        .production_database
+        .exam_creator_exam
+        .find_one(doc! { "_id": exam_id })
+        .await?
+        .ok_or(Error::Server(
+            StatusCode::BAD_REQUEST,
+            format!("exam non-existent: {exam_id}"),
+        ))?;
+
+    let generated_exams: Vec<prisma::ExamEnvironmentGeneratedExam> = database
+        .generated_exam
+        .find(doc! { "examId": exam_id, "deprecated": false })
+        .await?
+        .try_collect()
+        .await?;
+
+    Ok(Json(compatibility::orphaned_generations(
+        &exam_creator_exam,
+        &generated_exams,
+    )))
+}
+
+/// An exam document which can be compared against another
+#[derive(Debug, Clone, Copy, PartialEq, serde_with::DeserializeFromStr)]
+pub enum ExamSource {
+    /// `ExamCreatorExam`
+    Creator,
+    /// `ExamEnvironmentExam` in the staging database
+    Staging,
+    /// `ExamEnvironmentExam` in the production database
+    Production,
+    /// `ExamCreatorExamRevision` by id
+    Revision(ObjectId),
+}
+
+impl FromStr for ExamSource {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "creator" => Ok(ExamSource::Creator),
+            "staging" => Ok(ExamSource::Staging),
+            "production" => Ok(ExamSource::Production),
+            _ => ObjectId::parse_str(s)
+                .map(ExamSource::Revision)
+                .map_err(|_| {
+                    format!(
+                        "invalid exam source: {s}. Expected creator, staging, production, or a revision id"
+                    )
+                }),
+        }
+    }
+}
+
+/// Finds the exam document for the given source
+pub async fn find_exam_by_source(
+    state: &ServerState,
+    exam_id: ObjectId,
+    source: ExamSource,
+) -> Result<prisma::ExamEnvironmentExam, Error> {
+    let exam = match source {
+        ExamSource::Creator => state
+            .production_database
+            .exam_creator_exam
+            .find_one(doc! { "_id": exam_id })
+            .await?
+            .map(Into::into),
+        ExamSource::Staging => {
+            state
+                .staging_database
+                .exam
+                .find_one(doc! { "_id": exam_id })
+                .await?
+        }
+        ExamSource::Production => {
+            state
+                .production_database
+                .exam
+                .find_one(doc! { "_id": exam_id })
+                .await?
+        }
+        ExamSource::Revision(revision_id) => state
+            .production_database
+            .exam_creator_exam_revision
+            .find_one(doc! { "_id": revision_id, "examId": exam_id })
+            .await?
+            .map(|r| r.exam.into()),
+    };
+
+    exam.ok_or(Error::Server(
+        StatusCode::BAD_REQUEST,
+        format!("exam non-existent in {source:?}: {exam_id}"),
+    ))
+}
+
+#[derive(Deserialize)]
+pub struct GetExamDiffQuery {
+    pub from: ExamSource,
+    pub to: ExamSource,
+}
+
+/// Compares two versions of an exam
+///
+/// Each of `from` and `to` is one of `creator`, `staging`, `production`, or a revision id.
+#[instrument(skip_all, err(Debug), level = "debug")]
+pub async fn get_exam_diff(
+    _auth_user: prisma::ExamCreatorUser,
+    State(state): State<ServerState>,
+    Path(exam_id): Path<ObjectId>,
+    Query(GetExamDiffQuery { from, to }): Query<GetExamDiffQuery>,
+) -> Result<Json<diff::ExamDiff>, Error> {
+    let from_exam = find_exam_by_source(&state, exam_id, from).await?;
+    let to_exam = find_exam_by_source(&state, exam_id, to).await?;
+
+    Ok(Json(diff::diff_exams(&from_exam, &to_exam)))
+}
+
 pub async fn get_generations_by_exam_id_with_database_environment(
     _auth_user: prisma::ExamCreatorUser,
     State(state): State<ServerState>,
@@ -278,140 +1125,236 @@ pub async fn get_generations_by_exam_id_with_database_environment(
     Ok(Json(generated_exams))
 }
 
-#[derive(Deserialize)]
-pub struct PutGenerateExamBody {
-    pub count: i16,
-}
-
 #[derive(Serialize)]
-pub struct PutGenerateExamResponse {
-    pub count: i16,
-    #[serde(rename = "examId")]
-    pub exam_id: ObjectId,
-    pub error: Option<String>,
+#[serde(rename_all = "camelCase")]
+pub struct ReproducedGeneration {
+    pub seed: u32,
+    pub revision: i64,
+    /// Whether the reproduced generation has the same question sets, questions, and answers as the recorded one
+    pub matches: bool,
+    pub generated_exam: prisma::ExamEnvironmentGeneratedExam,
 }
 
-/// Generate an exam based on the exam configuration
+/// Generates an exam again, from the revision and seed recorded for an existing generation
+///
+/// Generations balanced against exposure are only reproducible while the generations they were balanced against exist.
 #[instrument(skip_all, err(Debug), level = "debug")]
-pub async fn put_generations_by_exam_id_with_database_environment(
+pub async fn get_reproduced_generation_by_id(
     _auth_user: prisma::ExamCreatorUser,
     State(state): State<ServerState>,
-    Path((exam_id, database_environment)): Path<(ObjectId, prisma::ExamCreatorDatabaseEnvironment)>,
-    Json(body): Json<PutGenerateExamBody>,
-) -> Result<impl IntoResponse, Error> {
+    Path((exam_id, database_environment, generated_exam_id)): Path<(
+        ObjectId,
+        prisma::ExamCreatorDatabaseEnvironment,
+        ObjectId,
+    )>,
+) -> Result<Json<ReproducedGeneration>, Error> {
     let database = match database_environment {
-        prisma::ExamCreatorDatabaseEnvironment::Staging => state.staging_database.clone(),
-        prisma::ExamCreatorDatabaseEnvironment::Production => state.production_database.clone(),
+        prisma::ExamCreatorDatabaseEnvironment::Staging => &state.staging_database,
+        prisma::ExamCreatorDatabaseEnvironment::Production => &state.production_database,
     };
 
-    // Fetch the exam from the source
-    let exam_creator_exam = state
+    let generated_exam = database
+        .generated_exam
+        .find_one(doc! { "_id": generated_exam_id, "examId": exam_id })
+        .await?
+        .ok_or(Error::Server(
+            StatusCode::BAD_REQUEST,
+            format!("generated exam non-existent: {generated_exam_id}"),
+        ))?;
+
+    let generated_exam_seed = database
+        .generated_exam_seed
+        .find_one(doc! { "_id": generated_exam_id })
+        .await?
+        .ok_or(Error::Server(
+            StatusCode::BAD_REQUEST,
+            format!("generated exam seed non-existent: {generated_exam_id}"),
+        ))?;
+
+    let Some(revision_number) = generated_exam_seed.revision else {
+        return Err(Error::Server(
+            StatusCode::BAD_REQUEST,
+            format!("generated exam {generated_exam_id} was not made from an exam revision"),
+        ));
+    };
+
+    let revision = state
         .production_database
-        .exam_creator_exam
-        .find_one(doc! { "_id": exam_id })
+        .exam_creator_exam_revision
+        .find_one(doc! { "examId": exam_id, "revision": revision_number })
         .await?
         .ok_or(Error::Server(
             StatusCode::BAD_REQUEST,
-            format!("exam non-existent: {exam_id}"),
+            format!("revision non-existent: {revision_number}"),
         ))?;
-    put_generations_by_exam_id(body.count, database, exam_id, exam_creator_exam).await
-}
 
-async fn put_generations_by_exam_id(
-    count: i16,
-    database: Database,
-    exam_id: ObjectId,
-    exam_creator_exam: prisma::ExamCreatorExam,
-) -> Result<impl IntoResponse, Error> {
-    // Convert to ExamInput for generation
+    let exposure = match (
+        generated_exam_seed.job_id,
+        generated_exam_seed.balanced_before,
+    ) {
+        (Some(job_id), Some(balanced_before)) => {
+            let job = state
+                .production_database
+                .generation_job
+                .find_one(doc! { "_id": job_id })
+                .await?
+                .ok_or(Error::Server(
+                    StatusCode::BAD_REQUEST,
+                    format!("generation job non-existent: {job_id}"),
+                ))?;
+            jobs::job_exposure(database, &job, balanced_before).await?
+        }
+        _ => generate::Exposure::default(),
+    };
+
     let exam_input = generate::ExamInput {
-        id: exam_creator_exam.id,
-        question_sets: exam_creator_exam.question_sets,
-        config: exam_creator_exam.config,
+        id: revision.exam.id,
+        question_sets: revision.exam.question_sets,
+        config: revision.exam.config,
     };
+    let mut reproduced = generate::generate_exam(exam_input, generated_exam_seed.seed, &exposure)?;
+    reproduced.id = generated_exam.id;
 
-    // 1. Create a channel
-    let (tx, rx) = mpsc::channel::<PutGenerateExamResponse>(16); // Buffer of 16
-
-    // 2. Spawn a background task to do the generation, with a 10s timeout
-    tokio::spawn(async move {
-        let generation_start = std::time::Instant::now();
-        let generation_timeout = std::time::Duration::from_secs(10);
-
-        let generation_future = async {
-            for i in 0..count {
-                loop {
-                    // Check timeout within the retry loop
-                    if generation_start.elapsed() > generation_timeout {
-                        tracing::warn!("Exam generation timed out after 10 seconds.");
-                        return;
-                    }
-
-                    match generate::generate_exam(exam_input.clone()) {
-                        Ok(generated_exam) => {
-                            if let Err(e) =
-                                database.generated_exam.insert_one(&generated_exam).await
-                            {
-                                tracing::error!(
-                                    "Failed to insert generated exam: {}, stopping stream.",
-                                    e
-                                );
-                                // The sender `tx` is dropped here, closing the channel and ending the stream.
-                                return;
-                            }
-
-                            info!("Successfully generated exam: {}", generated_exam.id);
-
-                            let res = PutGenerateExamResponse {
-                                count: i + 1,
-                                exam_id,
-                                error: None,
-                            };
-
-                            // 3. Send the result through the channel.
-                            // If the client disconnects, `send` will fail, and we break the loop.
-                            if tx.send(res).await.is_err() {
-                                tracing::warn!("Client disconnected, stopping exam generation.");
-                                break;
-                            }
-
-                            break;
-                        }
-                        Err(e) => {
-                            tracing::debug!("Failed to generate exam: {:?}", e);
-                            let res = PutGenerateExamResponse {
-                                count: i,
-                                exam_id,
-                                error: Some(e.to_string()),
-                            };
-
-                            if tx.send(res).await.is_err() {
-                                tracing::warn!("Client disconnected, stopping exam generation.");
-                                break;
-                            }
-                        }
-                    }
-                }
-            }
-        };
+    Ok(Json(ReproducedGeneration {
+        seed: generated_exam_seed.seed,
+        revision: revision_number,
+        matches: reproduced.question_sets == generated_exam.question_sets,
+        generated_exam: reproduced,
+    }))
+}
+
+#[serde_with::serde_as]
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PutDeprecateGenerationsBody {
+    /// Generations with any of these ids
+    pub generated_exam_ids: Option<Vec<ObjectId>>,
+    /// Generations created before this time
+    #[serde(default)]
+    #[serde_as(as = "Option<bson::serde_helpers::datetime::AsRfc3339String>")]
+    pub created_before: Option<mongodb::bson::DateTime>,
+    /// Generations containing any of these questions
+    pub question_ids: Option<Vec<ObjectId>>,
+    /// Report the generations which would be deprecated, without deprecating them
+    #[serde(default)]
+    pub dry_run: bool,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DeprecatedGenerations {
+    /// Generations deprecated, or which would be deprecated in a dry run
+    pub generated_exam_ids: Vec<ObjectId>,
+    /// Number of attempts already made with the generations
+    pub attempts: u64,
+}
+
+/// Deprecates the exam's live generations matching every given criterion
+///
+/// At least one criterion is required, so all generations cannot be deprecated by accident.
+#[instrument(skip_all, err(Debug), level = "debug")]
+pub async fn put_deprecate_generations_by_exam_id(
+    _auth_user: prisma::ExamCreatorUser,
+    State(state): State<ServerState>,
+    Path((exam_id, database_environment)): Path<(ObjectId, prisma::ExamCreatorDatabaseEnvironment)>,
+    Json(body): Json<PutDeprecateGenerationsBody>,
+) -> Result<Json<DeprecatedGenerations>, Error> {
+    if body.generated_exam_ids.is_none()
+        && body.created_before.is_none()
+        && body.question_ids.is_none()
+    {
+        return Err(Error::Server(
+            StatusCode::BAD_REQUEST,
+            "one of generatedExamIds, createdBefore, or questionIds is required".to_string(),
+        ));
+    }
 
-        generation_future.await;
-        // The sender `tx` is dropped when the task finishes or times out, closing the stream.
-    });
+    let database = match database_environment {
+        prisma::ExamCreatorDatabaseEnvironment::Staging => &state.staging_database,
+        prisma::ExamCreatorDatabaseEnvironment::Production => &state.production_database,
+    };
 
-    // 4. Create a stream from the receiver
-    let stream = ReceiverStream::new(rx);
+    let mut filter = doc! { "examId": exam_id, "deprecated": false };
+    let mut id_filter = doc! {};
+    if let Some(generated_exam_ids) = body.generated_exam_ids {
+        id_filter.insert("$in", generated_exam_ids);
+    }
+    // Generations have no creation time, other than the one in their id
+    if let Some(created_before) = body.created_before {
+        id_filter.insert("$lt", ObjectId::from_time(created_before));
+    }
+    if !id_filter.is_empty() {
+        filter.insert("_id", id_filter);
+    }
+    if let Some(question_ids) = body.question_ids {
+        filter.insert("questionSets.questions.id", doc! { "$in": question_ids });
+    }
 
-    // 5. Return the stream as the response body immediately.
-    Ok(StreamBodyAs::json_nl(stream))
+    let generated_exams: Vec<Document> = database
+        .generated_exam
+        .clone_with_type::<Document>()
+        .find(filter)
+        .projection(doc! { "_id": true })
+        .await?
+        .try_collect()
+        .await?;
+    let generated_exam_ids = generated_exams
+        .iter()
+        .map(|generated_exam| generated_exam.get_object_id("_id"))
+        .collect::<Result<Vec<ObjectId>, _>>()?;
+
+    if generated_exam_ids.is_empty() {
+        return Ok(Json(DeprecatedGenerations {
+            generated_exam_ids,
+            attempts: 0,
+        }));
+    }
+
+    let attempts = database
+        .exam_attempt
+        .count_documents(doc! { "generatedExamId": { "$in": generated_exam_ids.clone() } })
+        .await?;
+
+    if !body.dry_run {
+        // Only the generations found are deprecated, so the response matches what was modified,
+        // even if generations matching the filter were created since
+        let result = database
+            .generated_exam
+            .update_many(
+                doc! { "_id": { "$in": generated_exam_ids.clone() }, "deprecated": false },
+                doc! { "$set": { "deprecated": true } },
+            )
+            .await?;
+        info!(
+            "Deprecated {} generations of exam {exam_id}, used by {attempts} attempts",
+            result.modified_count
+        );
+    }
+
+    Ok(Json(DeprecatedGenerations {
+        generated_exam_ids,
+        attempts,
+    }))
 }
 
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ConfigValidation {
+    /// Every problem found with the exam's config and content
+    pub issues: Vec<validation::ValidationIssue>,
+    /// Whether the exam config can be generated
+    pub feasibility: generate::FeasibilityReport,
+}
+
+/// Checks the exam's config and content, and whether the config can be generated
+///
+/// Responds with every issue found, and a report naming the constraints which prevent generation, if any.
 #[instrument(skip_all, err(Debug), level = "debug")]
 pub async fn post_validate_config_by_exam_id(
     _auth_user: prisma::ExamCreatorUser,
     State(state): State<ServerState>,
     Path(exam_id): Path<ObjectId>,
-) -> Result<(), Error> {
+) -> Result<Json<ConfigValidation>, Error> {
     let exam_creator_exam = state
         .production_database
         .exam_creator_exam
@@ -422,10 +1365,18 @@ pub async fn post_validate_config_by_exam_id(
             format!("exam non-existent: {exam_id}"),
         ))?;
 
-    let res = config::validate_config(&exam_creator_exam);
+    let mut issues = validation::validate_exam(&exam_creator_exam);
+    issues.extend(lint::lint_exam(&exam_creator_exam));
 
-    match res {
-        Ok(_) => Ok(()),
-        Err(e) => Err(Error::InvalidConfig(StatusCode::BAD_REQUEST, e)),
-    }
+    let exam_input = generate::ExamInput {
+        id: exam_creator_exam.id,
+        question_sets: exam_creator_exam.question_sets,
+        config: exam_creator_exam.config,
+    };
+    let feasibility = generate::check_feasibility(exam_input);
+
+    Ok(Json(ConfigValidation {
+        issues,
+        feasibility,
+    }))
 }
@@ -0,0 +1,302 @@
+use axum::{
+    Json,
+    extract::{Path, Query, State},
+    response::IntoResponse,
+};
+use axum_streams::StreamBodyAs;
+use futures_util::TryStreamExt;
+use http::StatusCode;
+use mongodb::bson::{DateTime, Document, doc, oid::ObjectId};
+use mongodb::options::ReturnDocument;
+use serde::Deserialize;
+use tokio::sync::{broadcast, mpsc};
+use tokio_stream::wrappers::ReceiverStream;
+use tracing::{instrument, warn};
+
+use crate::{
+    database::{
+        generation::{ExamCreatorGenerationJob, GenerationJobStatus},
+        prisma, revision,
+    },
+    errors::Error,
+    state::ServerState,
+};
+
+/// Most exams a single job can generate
+const MAX_GENERATION_JOB_COUNT: u32 = 5000;
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PostGenerationJobBody {
+    pub exam_id: ObjectId,
+    pub database_environment: prisma::ExamCreatorDatabaseEnvironment,
+    pub count: u32,
+    /// Seed of the first generation, incremented for each subsequent generation
+    ///
+    /// Each generation is given a random seed, if not provided.
+    pub seed: Option<u32>,
+    /// Prefer the question sets, questions, and answers least exposed by the exam's existing generations
+    #[serde(default)]
+    pub balance_exposure: bool,
+}
+
+/// Queue a job to generate exams from the exam's latest revision
+#[instrument(skip_all, err(Debug), level = "debug")]
+pub async fn post_generation_job(
+    auth_user: prisma::ExamCreatorUser,
+    State(state): State<ServerState>,
+    Json(body): Json<PostGenerationJobBody>,
+) -> Result<Json<ExamCreatorGenerationJob>, Error> {
+    if body.count == 0 || body.count > MAX_GENERATION_JOB_COUNT {
+        return Err(Error::Server(
+            StatusCode::BAD_REQUEST,
+            format!("count must be between 1 and {MAX_GENERATION_JOB_COUNT}"),
+        ));
+    }
+
+    let exam_exists = state
+        .production_database
+        .exam_creator_exam
+        .count_documents(doc! { "_id": body.exam_id })
+        .await?
+        > 0;
+    if !exam_exists {
+        return Err(Error::Server(
+            StatusCode::BAD_REQUEST,
+            format!("exam non-existent: {}", body.exam_id),
+        ));
+    }
+
+    // The saved exam is the same as its latest revision
+    let revision =
+        match revision::latest_revision_number(&state.production_database, body.exam_id).await? {
+            0 => None,
+            revision => Some(revision),
+        };
+
+    let balanced_against = if body.balance_exposure {
+        let database = match body.database_environment {
+            prisma::ExamCreatorDatabaseEnvironment::Staging => &state.staging_database,
+            prisma::ExamCreatorDatabaseEnvironment::Production => &state.production_database,
+        };
+        let generated_exams: Vec<Document> = database
+            .generated_exam
+            .clone_with_type::<Document>()
+            .find(doc! { "examId": body.exam_id, "deprecated": false })
+            .projection(doc! { "_id": true })
+            .await?
+            .try_collect()
+            .await?;
+        generated_exams
+            .iter()
+            .map(|generated_exam| generated_exam.get_object_id("_id"))
+            .collect::<Result<Vec<ObjectId>, _>>()?
+    } else {
+        vec![]
+    };
+
+    let now = DateTime::now();
+    let job = ExamCreatorGenerationJob {
+        id: ObjectId::new(),
+        exam_id: body.exam_id,
+        database_environment: body.database_environment,
+        revision,
+        count: body.count,
+        generated: 0,
+        seed: body.seed,
+        balance_exposure: body.balance_exposure,
+        balanced_against,
+        status: GenerationJobStatus::Queued,
+        error: None,
+        exposure: None,
+        created_by: auth_user.email,
+        created_at: now,
+        updated_at: now,
+    };
+
+    state
+        .production_database
+        .generation_job
+        .insert_one(&job)
+        .await?;
+    let _ = state.generation_jobs.send(job.clone());
+
+    Ok(Json(job))
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct GetGenerationJobsQuery {
+    pub exam_id: Option<ObjectId>,
+}
+
+/// Get all generation jobs, most recently created first
+#[instrument(skip_all, err(Debug), level = "debug")]
+pub async fn get_generation_jobs(
+    _: prisma::ExamCreatorUser,
+    State(state): State<ServerState>,
+    Query(GetGenerationJobsQuery { exam_id }): Query<GetGenerationJobsQuery>,
+) -> Result<Json<Vec<ExamCreatorGenerationJob>>, Error> {
+    let mut filter = doc! {};
+    if let Some(exam_id) = exam_id {
+        filter.insert("examId", exam_id);
+    }
+
+    let jobs: Vec<ExamCreatorGenerationJob> = state
+        .production_database
+        .generation_job
+        .find(filter)
+        .sort(doc! { "createdAt": -1 })
+        .await?
+        .try_collect()
+        .await?;
+
+    Ok(Json(jobs))
+}
+
+#[instrument(skip_all, err(Debug), level = "debug")]
+pub async fn get_generation_job_by_id(
+    _: prisma::ExamCreatorUser,
+    State(state): State<ServerState>,
+    Path(job_id): Path<ObjectId>,
+) -> Result<Json<ExamCreatorGenerationJob>, Error> {
+    let job = find_generation_job(&state, job_id).await?;
+    Ok(Json(job))
+}
+
+/// Stop a queued or running job. Exams already generated are kept.
+#[instrument(skip_all, err(Debug), level = "debug")]
+pub async fn put_cancel_generation_job(
+    _: prisma::ExamCreatorUser,
+    State(state): State<ServerState>,
+    Path(job_id): Path<ObjectId>,
+) -> Result<Json<ExamCreatorGenerationJob>, Error> {
+    let job = set_generation_job_status(
+        &state,
+        job_id,
+        &["Queued", "Running"],
+        GenerationJobStatus::Cancelled,
+    )
+    .await?;
+    Ok(Json(job))
+}
+
+/// Queue a cancelled or failed job again, to generate its remaining exams
+#[instrument(skip_all, err(Debug), level = "debug")]
+pub async fn put_resume_generation_job(
+    _: prisma::ExamCreatorUser,
+    State(state): State<ServerState>,
+    Path(job_id): Path<ObjectId>,
+) -> Result<Json<ExamCreatorGenerationJob>, Error> {
+    let job = set_generation_job_status(
+        &state,
+        job_id,
+        &["Cancelled", "Failed"],
+        GenerationJobStatus::Queued,
+    )
+    .await?;
+    Ok(Json(job))
+}
+
+/// Stream the job each time it is updated, as newline-delimited JSON, until it finishes
+#[instrument(skip_all, err(Debug), level = "debug")]
+pub async fn get_generation_job_progress(
+    _: prisma::ExamCreatorUser,
+    State(state): State<ServerState>,
+    Path(job_id): Path<ObjectId>,
+) -> Result<impl IntoResponse, Error> {
+    // Subscribe before reading the job, so no update is missed in between
+    let mut job_updates = state.generation_jobs.subscribe();
+    let job = find_generation_job(&state, job_id).await?;
+
+    let (tx, rx) = mpsc::channel::<ExamCreatorGenerationJob>(16);
+    let database = state.production_database.clone();
+
+    tokio::spawn(async move {
+        let mut finished = job.status.is_finished();
+        if tx.send(job).await.is_err() {
+            return;
+        }
+
+        while !finished {
+            let job = match job_updates.recv().await {
+                Ok(job) if job.id == job_id => job,
+                Ok(_) => continue,
+                Err(broadcast::error::RecvError::Lagged(skipped)) => {
+                    // The skipped updates may include this job finishing
+                    warn!("Progress of generation job {job_id} skipped {skipped} updates");
+                    match database
+                        .generation_job
+                        .find_one(doc! { "_id": job_id })
+                        .await
+                    {
+                        Ok(Some(job)) => job,
+                        _ => break,
+                    }
+                }
+                Err(broadcast::error::RecvError::Closed) => break,
+            };
+
+            finished = job.status.is_finished();
+            // If the client disconnects, `send` will fail. The job itself carries on.
+            if tx.send(job).await.is_err() {
+                break;
+            }
+        }
+    });
+
+    Ok(StreamBodyAs::json_nl(ReceiverStream::new(rx)))
+}
+
+async fn find_generation_job(
+    state: &ServerState,
+    job_id: ObjectId,
+) -> Result<ExamCreatorGenerationJob, Error> {
+    state
+        .production_database
+        .generation_job
+        .find_one(doc! { "_id": job_id })
+        .await?
+        .ok_or(Error::Server(
+            StatusCode::BAD_REQUEST,
+            format!("generation job non-existent: {job_id}"),
+        ))
+}
+
+/// Sets the job's status, if its current status is one of `from`
+async fn set_generation_job_status(
+    state: &ServerState,
+    job_id: ObjectId,
+    from: &[&str],
+    to: GenerationJobStatus,
+) -> Result<ExamCreatorGenerationJob, Error> {
+    let job = state
+        .production_database
+        .generation_job
+        .find_one_and_update(
+            doc! { "_id": job_id, "status": { "$in": from.to_vec() } },
+            doc! {
+                "$set": {
+                    "status": mongodb::bson::serialize_to_bson(&to)?,
+                    "error": null,
+                    "updatedAt": DateTime::now(),
+                }
+            },
+        )
+        .return_document(ReturnDocument::After)
+        .await?;
+
+    let Some(job) = job else {
+        let job = find_generation_job(state, job_id).await?;
+        return Err(Error::Server(
+            StatusCode::CONFLICT,
+            format!(
+                "generation job {job_id} cannot be set to {to:?} from {:?}",
+                job.status
+            ),
+        ));
+    };
+
+    let _ = state.generation_jobs.send(job.clone());
+    Ok(job)
+}
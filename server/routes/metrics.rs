@@ -1,18 +1,23 @@
 use axum::{
     Json,
     extract::{Path, State},
+    response::IntoResponse,
 };
+use axum_streams::StreamBodyAs;
 use futures_util::TryStreamExt;
 use http::StatusCode;
 use mongodb::bson::doc;
 use mongodb::bson::oid::ObjectId;
 use serde::{Deserialize, Serialize};
+use tokio::sync::mpsc;
+use tokio_stream::wrappers::ReceiverStream;
 use tracing::instrument;
 
 use crate::{
     database::{database_environment, prisma},
     errors::Error,
     state::ServerState,
+    variability::{self, GenerationOverlap, GenerationOverlaps},
 };
 
 #[derive(Serialize)]
@@ -155,6 +160,77 @@ pub async fn get_exam_metrics_by_exam_id(
     Ok(Json(response))
 }
 
+/// Get the variability of an exam's generations, and how evenly they use its questions and answers
+#[instrument(skip_all, err(Debug))]
+pub async fn get_generation_metrics_by_exam_id(
+    _: prisma::ExamCreatorUser,
+    State(state): State<ServerState>,
+    Path((exam_id, database_environment)): Path<(ObjectId, prisma::ExamCreatorDatabaseEnvironment)>,
+) -> Result<Json<variability::GenerationMetrics>, Error> {
+    let database = match database_environment {
+        prisma::ExamCreatorDatabaseEnvironment::Staging => &state.staging_database,
+        prisma::ExamCreatorDatabaseEnvironment::Production => &state.production_database,
+    };
+
+    let exam = state
+        .production_database
+        .exam_creator_exam
+        .find_one(doc! { "_id": exam_id })
+        .await?
+        .ok_or(Error::Server(
+            StatusCode::BAD_REQUEST,
+            format!("exam non-existent: {exam_id}"),
+        ))?;
+
+    let generated_exams: Vec<prisma::ExamEnvironmentGeneratedExam> = database
+        .generated_exam
+        .find(doc! { "examId": exam_id })
+        .await?
+        .try_collect()
+        .await?;
+
+    // Comparing every pair of generations is too slow to run on the async runtime
+    let metrics = tokio::task::spawn_blocking(move || {
+        variability::generation_metrics(&exam, &generated_exams)
+    })
+    .await?;
+
+    Ok(Json(metrics))
+}
+
+/// Stream the overlap between each pair of an exam's live generations, as newline-delimited JSON
+#[instrument(skip_all, err(Debug))]
+pub async fn get_generation_overlaps_by_exam_id(
+    _: prisma::ExamCreatorUser,
+    State(state): State<ServerState>,
+    Path((exam_id, database_environment)): Path<(ObjectId, prisma::ExamCreatorDatabaseEnvironment)>,
+) -> Result<impl IntoResponse, Error> {
+    let database = match database_environment {
+        prisma::ExamCreatorDatabaseEnvironment::Staging => &state.staging_database,
+        prisma::ExamCreatorDatabaseEnvironment::Production => &state.production_database,
+    };
+
+    let generated_exams: Vec<prisma::ExamEnvironmentGeneratedExam> = database
+        .generated_exam
+        .find(doc! { "examId": exam_id, "deprecated": false })
+        .await?
+        .try_collect()
+        .await?;
+
+    let (tx, rx) = mpsc::channel::<GenerationOverlap>(256);
+
+    tokio::task::spawn_blocking(move || {
+        for overlap in GenerationOverlaps::new(&generated_exams) {
+            // If the client disconnects, `send` will fail, and there is no one to compare for.
+            if tx.blocking_send(overlap).is_err() {
+                break;
+            }
+        }
+    });
+
+    Ok(StreamBodyAs::json_nl(ReceiverStream::new(rx)))
+}
+
 #[serde_with::serde_as]
 #[derive(Serialize, Deserialize, Clone)]
 pub struct GetAttemptsMetrics {
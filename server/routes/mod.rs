@@ -13,14 +13,22 @@ use crate::{database::prisma, errors::Error, state::ServerState};
 
 pub mod attempts;
 pub mod auth;
+pub mod deploy_requests;
+pub mod deployments;
+pub mod drafts;
 pub mod events;
 pub mod exam_challenge;
 pub mod exams;
+pub mod generation_jobs;
 pub mod metrics;
 pub mod moderations;
+pub mod revisions;
+pub mod schedules;
+pub mod tags;
 pub mod users;
 pub mod websocket;
 
+/// Resets the exam being edited to the saved exam, discarding its draft
 #[instrument(skip_all, err(Debug), level = "debug")]
 pub async fn discard_exam_state_by_id(
     _: prisma::ExamCreatorUser,
@@ -39,10 +47,7 @@ pub async fn discard_exam_state_by_id(
             format!("No exam {exam_id} found"),
         ))?;
 
-    let client_sync = &mut state.client_sync.lock().unwrap();
-    if let Some(exam) = client_sync.exams.iter_mut().find(|e| e.id == exam_id) {
-        *exam = original_exam.clone();
-    } else {
+    if !websocket::reset_synced_exam(&state, &original_exam) {
         info!("No exam in client sync state: {}", exam_id)
     }
 
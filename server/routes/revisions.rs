@@ -0,0 +1,117 @@
+use axum::{
+    Json,
+    extract::{Path, State},
+};
+use futures_util::TryStreamExt;
+use http::StatusCode;
+use mongodb::bson::doc;
+use mongodb::bson::oid::ObjectId;
+use tracing::{info, instrument};
+
+use crate::{
+    database::{
+        prisma,
+        revision::{self, ExamCreatorExamRevision, ExamCreatorExamRevisionSummary},
+    },
+    errors::Error,
+    routes::websocket,
+    state::ServerState,
+};
+
+/// Get all revisions for an exam, newest first.
+///
+/// The `exam` field is removed, as it is not needed for listing.
+#[instrument(skip_all, err(Debug), level = "debug")]
+pub async fn get_revisions_by_exam_id(
+    _: prisma::ExamCreatorUser,
+    State(state): State<ServerState>,
+    Path(exam_id): Path<ObjectId>,
+) -> Result<Json<Vec<ExamCreatorExamRevisionSummary>>, Error> {
+    let revisions: Vec<ExamCreatorExamRevisionSummary> = state
+        .production_database
+        .exam_creator_exam_revision
+        .clone_with_type::<ExamCreatorExamRevisionSummary>()
+        .find(doc! { "examId": exam_id })
+        .projection(doc! { "exam": false })
+        .sort(doc! { "revision": -1 })
+        .await?
+        .try_collect()
+        .await?;
+
+    Ok(Json(revisions))
+}
+
+#[instrument(skip_all, err(Debug), level = "debug")]
+pub async fn get_revision_by_id(
+    _: prisma::ExamCreatorUser,
+    State(state): State<ServerState>,
+    Path((exam_id, revision_id)): Path<(ObjectId, ObjectId)>,
+) -> Result<Json<ExamCreatorExamRevision>, Error> {
+    let revision = state
+        .production_database
+        .exam_creator_exam_revision
+        .find_one(doc! { "_id": revision_id, "examId": exam_id })
+        .await?
+        .ok_or(Error::Server(
+            StatusCode::BAD_REQUEST,
+            format!("revision non-existent: {revision_id}"),
+        ))?;
+
+    Ok(Json(revision))
+}
+
+/// Restores an exam to the state of the given revision.
+///
+/// The restore is itself recorded as a new revision, so it can be undone.
+#[instrument(skip_all, err(Debug), level = "debug")]
+pub async fn put_restore_revision(
+    auth_user: prisma::ExamCreatorUser,
+    State(state): State<ServerState>,
+    Path((exam_id, revision_id)): Path<(ObjectId, ObjectId)>,
+) -> Result<Json<prisma::ExamCreatorExam>, Error> {
+    let revision = state
+        .production_database
+        .exam_creator_exam_revision
+        .find_one(doc! { "_id": revision_id, "examId": exam_id })
+        .await?
+        .ok_or(Error::Server(
+            StatusCode::BAD_REQUEST,
+            format!("revision non-existent: {revision_id}"),
+        ))?;
+
+    let current = state
+        .production_database
+        .exam_creator_exam
+        .find_one(doc! { "_id": exam_id })
+        .await?
+        .ok_or(Error::Server(
+            StatusCode::BAD_REQUEST,
+            format!("exam non-existent: {exam_id}"),
+        ))?;
+
+    let mut exam = revision.exam;
+    // Restoring is a save on top of the current exam
+    exam.version = current.version + 1;
+
+    let saved = revision::save_exam(
+        &state.production_database,
+        &exam,
+        Some(current.version),
+        &auth_user,
+        Some(revision_id),
+    )
+    .await?;
+
+    if saved.is_none() {
+        return Err(Error::Server(
+            StatusCode::CONFLICT,
+            format!("exam {exam_id} was saved during restore"),
+        ));
+    }
+    info!("Restored exam {exam_id} to revision {}", revision.revision);
+
+    // Co-editors' unsaved edits are kept rather than lost, so saving them replaces the restored exam
+    websocket::sync_saved_exam(&state, &exam, None);
+
+    Ok(Json(exam))
+}
@@ -0,0 +1,237 @@
+use axum::{
+    Json,
+    extract::{Path, Query, State},
+};
+use futures_util::TryStreamExt;
+use http::StatusCode;
+use mongodb::bson::{DateTime, doc, oid::ObjectId};
+use mongodb::options::ReturnDocument;
+use serde::Deserialize;
+use tracing::{info, instrument};
+
+use crate::{
+    compatibility::OrphanedGenerationsAction,
+    database::{
+        deploy_request::DeployRequestStatus,
+        prisma,
+        schedule::{ExamCreatorSchedule, ScheduleAction, ScheduleStatus},
+    },
+    errors::Error,
+    routes::deploy_requests::find_deploy_request,
+    state::ServerState,
+};
+
+#[serde_with::serde_as]
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PostScheduleBody {
+    pub exam_id: ObjectId,
+    pub database_environment: prisma::ExamCreatorDatabaseEnvironment,
+    pub action: ScheduleAction,
+    #[serde_as(as = "bson::serde_helpers::datetime::AsRfc3339String")]
+    pub run_at: DateTime,
+    /// What to do with live generations orphaned by seeding the exam
+    #[serde(default)]
+    pub orphaned_generations: OrphanedGenerationsAction,
+    /// Approved deploy request for the action, required to change production.
+    ///
+    /// Seeding deploys the request. Deprecating, or undeprecating, needs a request to set `deprecated`.
+    pub deploy_request_id: Option<ObjectId>,
+}
+
+/// Schedule an exam to be seeded, deprecated, or undeprecated at a future time
+#[instrument(skip_all, err(Debug), level = "debug")]
+pub async fn post_schedule(
+    auth_user: prisma::ExamCreatorUser,
+    State(state): State<ServerState>,
+    Json(body): Json<PostScheduleBody>,
+) -> Result<Json<ExamCreatorSchedule>, Error> {
+    if body.run_at <= DateTime::now() {
+        return Err(Error::Server(
+            StatusCode::BAD_REQUEST,
+            "runAt must be in the future".to_string(),
+        ));
+    }
+
+    let exam_exists = state
+        .production_database
+        .exam_creator_exam
+        .count_documents(doc! { "_id": body.exam_id })
+        .await?
+        > 0;
+    if !exam_exists {
+        return Err(Error::Server(
+            StatusCode::BAD_REQUEST,
+            format!("exam non-existent: {}", body.exam_id),
+        ));
+    }
+
+    // Every change to production needs an approved deploy request, which is checked again when run
+    let deploy_request_id = match &body.database_environment {
+        prisma::ExamCreatorDatabaseEnvironment::Staging => None,
+        prisma::ExamCreatorDatabaseEnvironment::Production => {
+            let deploy_request_id = body.deploy_request_id.ok_or(Error::Server(
+                StatusCode::BAD_REQUEST,
+                format!(
+                    "{:?} in production requires an approved deploy request",
+                    body.action
+                ),
+            ))?;
+            let deploy_request = find_deploy_request(&state, deploy_request_id).await?;
+            if deploy_request.exam_id != body.exam_id {
+                return Err(Error::Server(
+                    StatusCode::BAD_REQUEST,
+                    format!(
+                        "deploy request {deploy_request_id} is for exam {}",
+                        deploy_request.exam_id
+                    ),
+                ));
+            }
+            if deploy_request.status != DeployRequestStatus::Approved {
+                return Err(Error::Server(
+                    StatusCode::BAD_REQUEST,
+                    format!(
+                        "deploy request {deploy_request_id} is {:?}, not Approved",
+                        deploy_request.status
+                    ),
+                ));
+            }
+            if deploy_request.deprecated != body.action.deprecated() {
+                return Err(Error::Server(
+                    StatusCode::BAD_REQUEST,
+                    format!(
+                        "deploy request {deploy_request_id} is to {}, so cannot {:?} the exam",
+                        deploy_request.describe(),
+                        body.action
+                    ),
+                ));
+            }
+            Some(deploy_request_id)
+        }
+    };
+
+    let now = DateTime::now();
+    let schedule = ExamCreatorSchedule {
+        id: ObjectId::new(),
+        exam_id: body.exam_id,
+        database_environment: body.database_environment,
+        action: body.action,
+        orphaned_generations: body.orphaned_generations,
+        deploy_request_id,
+        run_at: body.run_at,
+        status: ScheduleStatus::Scheduled,
+        attempts: 0,
+        error: None,
+        created_by_id: auth_user.id,
+        created_by: auth_user.email,
+        created_at: now,
+        updated_at: now,
+    };
+
+    state
+        .production_database
+        .schedule
+        .insert_one(&schedule)
+        .await?;
+    info!(
+        "Scheduled {:?} of exam {} in {:?} at {}",
+        schedule.action, schedule.exam_id, schedule.database_environment, schedule.run_at
+    );
+
+    Ok(Json(schedule))
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct GetSchedulesQuery {
+    pub exam_id: Option<ObjectId>,
+    pub status: Option<ScheduleStatus>,
+}
+
+/// Get all schedules, soonest first
+#[instrument(skip_all, err(Debug), level = "debug")]
+pub async fn get_schedules(
+    _: prisma::ExamCreatorUser,
+    State(state): State<ServerState>,
+    Query(GetSchedulesQuery { exam_id, status }): Query<GetSchedulesQuery>,
+) -> Result<Json<Vec<ExamCreatorSchedule>>, Error> {
+    let mut filter = doc! {};
+    if let Some(exam_id) = exam_id {
+        filter.insert("examId", exam_id);
+    }
+    if let Some(status) = status {
+        filter.insert("status", bson::serialize_to_bson(&status)?);
+    }
+
+    let schedules: Vec<ExamCreatorSchedule> = state
+        .production_database
+        .schedule
+        .find(filter)
+        .sort(doc! { "runAt": 1 })
+        .await?
+        .try_collect()
+        .await?;
+
+    Ok(Json(schedules))
+}
+
+#[instrument(skip_all, err(Debug), level = "debug")]
+pub async fn get_schedule_by_id(
+    _: prisma::ExamCreatorUser,
+    State(state): State<ServerState>,
+    Path(schedule_id): Path<ObjectId>,
+) -> Result<Json<ExamCreatorSchedule>, Error> {
+    let schedule = find_schedule(&state, schedule_id).await?;
+    Ok(Json(schedule))
+}
+
+/// Stop a schedule from being run, including being retried
+#[instrument(skip_all, err(Debug), level = "debug")]
+pub async fn put_cancel_schedule(
+    _: prisma::ExamCreatorUser,
+    State(state): State<ServerState>,
+    Path(schedule_id): Path<ObjectId>,
+) -> Result<Json<ExamCreatorSchedule>, Error> {
+    let schedule = state
+        .production_database
+        .schedule
+        .find_one_and_update(
+            doc! { "_id": schedule_id, "status": "Scheduled" },
+            doc! {
+                "$set": {
+                    "status": bson::serialize_to_bson(&ScheduleStatus::Cancelled)?,
+                    "updatedAt": DateTime::now(),
+                }
+            },
+        )
+        .return_document(ReturnDocument::After)
+        .await?;
+
+    let Some(schedule) = schedule else {
+        let schedule = find_schedule(&state, schedule_id).await?;
+        return Err(Error::Server(
+            StatusCode::CONFLICT,
+            format!(
+                "schedule {schedule_id} cannot be cancelled from {:?}",
+                schedule.status
+            ),
+        ));
+    };
+
+    Ok(Json(schedule))
+}
+
+async fn find_schedule(
+    state: &ServerState,
+    schedule_id: ObjectId,
+) -> Result<ExamCreatorSchedule, Error> {
+    state
+        .production_database
+        .schedule
+        .find_one(doc! { "_id": schedule_id })
+        .await?
+        .ok_or(Error::Server(
+            StatusCode::BAD_REQUEST,
+            format!("schedule non-existent: {schedule_id}"),
+        ))
+}
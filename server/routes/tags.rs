@@ -0,0 +1,313 @@
+use axum::{
+    Json,
+    extract::{Path, Query, State},
+};
+use futures_util::TryStreamExt;
+use http::StatusCode;
+use mongodb::bson::{DateTime, doc, oid::ObjectId};
+use serde::{Deserialize, Serialize};
+use tracing::{info, instrument};
+
+use crate::{
+    database::{Database, prisma, revision, tag::ExamCreatorTag},
+    errors::Error,
+    routes::websocket,
+    state::ServerState,
+    tags::{self, TagUsage},
+};
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct GetTagsQuery {
+    /// Only count usage in this exam
+    pub exam_id: Option<ObjectId>,
+}
+
+/// Get every tag used by exam questions or tag configs, and every registered tag, with how much it is used
+#[instrument(skip_all, err(Debug), level = "debug")]
+pub async fn get_tags(
+    _: prisma::ExamCreatorUser,
+    State(state): State<ServerState>,
+    Query(GetTagsQuery { exam_id }): Query<GetTagsQuery>,
+) -> Result<Json<Vec<TagUsage>>, Error> {
+    let mut filter = doc! {};
+    if let Some(exam_id) = exam_id {
+        filter.insert("_id", exam_id);
+    }
+
+    let exams: Vec<prisma::ExamCreatorExam> = state
+        .production_database
+        .exam_creator_exam
+        .find(filter)
+        .await?
+        .try_collect()
+        .await?;
+    let registered: Vec<String> = registered_tags(&state.production_database)
+        .await?
+        .into_iter()
+        .map(|tag| tag.name)
+        .collect();
+
+    Ok(Json(tags::tag_usage(&exams, &registered)))
+}
+
+async fn registered_tags(database: &Database) -> Result<Vec<ExamCreatorTag>, Error> {
+    let tags = database
+        .exam_creator_tag
+        .find(doc! {})
+        .await?
+        .try_collect()
+        .await?;
+    Ok(tags)
+}
+
+#[derive(Deserialize)]
+pub struct PostTagBody {
+    pub name: String,
+    pub description: Option<String>,
+}
+
+/// Register a tag
+///
+/// A tag which is already registered, or is a near-duplicate of a registered tag, such as a typo of it,
+/// is rejected with a `409`.
+#[instrument(skip_all, err(Debug), level = "debug")]
+pub async fn post_tag(
+    auth_user: prisma::ExamCreatorUser,
+    State(state): State<ServerState>,
+    Json(body): Json<PostTagBody>,
+) -> Result<Json<ExamCreatorTag>, Error> {
+    let name = body.name.trim();
+    if name.is_empty() {
+        return Err(Error::Server(
+            StatusCode::BAD_REQUEST,
+            "tag name is empty".to_string(),
+        ));
+    }
+
+    let registered = registered_tags(&state.production_database).await?;
+    if let Some(similar) = registered
+        .iter()
+        .find(|tag| tags::near_duplicate(name, &tag.name))
+    {
+        return Err(Error::Server(
+            StatusCode::CONFLICT,
+            format!(
+                "tag {name:?} is similar to registered tag {:?}",
+                similar.name
+            ),
+        ));
+    }
+
+    let tag = ExamCreatorTag {
+        id: ObjectId::new(),
+        name: name.to_string(),
+        description: body.description,
+        created_by: auth_user.email,
+        created_at: DateTime::now(),
+    };
+    if !register_tag(&state.production_database, &tag).await? {
+        return Err(Error::Server(
+            StatusCode::CONFLICT,
+            format!("tag already registered: {name}"),
+        ));
+    }
+    info!("Registered tag {name:?}");
+
+    Ok(Json(tag))
+}
+
+/// Returns whether the tag was registered, or a tag with its name already was.
+///
+/// Upserted by name, so a tag cannot be registered twice by concurrent requests.
+async fn register_tag(database: &Database, tag: &ExamCreatorTag) -> Result<bool, Error> {
+    let result = database
+        .exam_creator_tag
+        .update_one(
+            doc! { "name": &tag.name },
+            doc! { "$setOnInsert": bson::serialize_to_document(tag)? },
+        )
+        .upsert(true)
+        .await?;
+    Ok(result.upserted_id.is_some())
+}
+
+/// Unregister a tag
+///
+/// Exams using the tag are not changed.
+#[instrument(skip_all, err(Debug), level = "debug")]
+pub async fn delete_tag(
+    _: prisma::ExamCreatorUser,
+    State(state): State<ServerState>,
+    Path(tag_id): Path<ObjectId>,
+) -> Result<Json<ExamCreatorTag>, Error> {
+    let tag = state
+        .production_database
+        .exam_creator_tag
+        .find_one_and_delete(doc! { "_id": tag_id })
+        .await?
+        .ok_or(Error::Server(
+            StatusCode::BAD_REQUEST,
+            format!("tag non-existent: {tag_id}"),
+        ))?;
+    info!("Unregistered tag {:?}", tag.name);
+
+    Ok(Json(tag))
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PutRenameTagsBody {
+    /// Tags to rename. More than one merges them into `to`.
+    pub from: Vec<String>,
+    pub to: String,
+    /// Only rename tags in this exam
+    pub exam_id: Option<ObjectId>,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RenamedTags {
+    /// Exams saved with the tags renamed
+    pub exams: Vec<ObjectId>,
+    pub questions: usize,
+    pub tag_configs: usize,
+    /// Exams using the tags, which were not renamed because they have unsaved edits,
+    /// or were saved during the rename
+    pub skipped: Vec<ObjectId>,
+}
+
+/// Rename or merge tags in the questions and tag configs of every exam using them.
+///
+/// Each renamed exam is saved as a new revision.
+/// Unless only one exam is renamed, registered tags are renamed too.
+#[instrument(skip_all, err(Debug), level = "debug")]
+pub async fn put_rename_tags(
+    auth_user: prisma::ExamCreatorUser,
+    State(state): State<ServerState>,
+    Json(body): Json<PutRenameTagsBody>,
+) -> Result<Json<RenamedTags>, Error> {
+    let to = body.to.trim();
+    if to.is_empty() {
+        return Err(Error::Server(
+            StatusCode::BAD_REQUEST,
+            "tag to rename to is empty".to_string(),
+        ));
+    }
+    let from: Vec<String> = body.from.into_iter().filter(|tag| tag != to).collect();
+    if from.is_empty() {
+        return Err(Error::Server(
+            StatusCode::BAD_REQUEST,
+            "no tags to rename".to_string(),
+        ));
+    }
+
+    let mut filter = doc! {
+        "$or": [
+            { "questionSets.questions.tags": { "$in": from.clone() } },
+            { "config.tags.group": { "$in": from.clone() } },
+        ]
+    };
+    if let Some(exam_id) = body.exam_id {
+        filter.insert("_id", exam_id);
+    }
+    let exams: Vec<prisma::ExamCreatorExam> = state
+        .production_database
+        .exam_creator_exam
+        .find(filter)
+        .await?
+        .try_collect()
+        .await?;
+
+    let mut renamed = RenamedTags {
+        exams: vec![],
+        questions: 0,
+        tag_configs: 0,
+        skipped: vec![],
+    };
+    for mut exam in exams {
+        let has_unsaved_edits = state
+            .client_sync
+            .lock()
+            .unwrap()
+            .exams
+            .iter()
+            .any(|synced_exam| {
+                synced_exam.exam.id == exam.id && synced_exam.draft.updated.is_some()
+            });
+        if has_unsaved_edits {
+            renamed.skipped.push(exam.id);
+            continue;
+        }
+
+        let count = tags::rename_tags(&mut exam, &from, to);
+        let base_version = exam.version;
+        exam.version = base_version + 1;
+
+        let saved = revision::save_exam(
+            &state.production_database,
+            &exam,
+            Some(base_version),
+            &auth_user,
+            None,
+        )
+        .await?;
+        if saved.is_none() {
+            renamed.skipped.push(exam.id);
+            continue;
+        }
+
+        // Checks for unsaved edits again, under the same lock as the reset,
+        // as they may have been made while the exam was saved
+        websocket::sync_saved_exam(&state, &exam, None);
+
+        renamed.exams.push(exam.id);
+        renamed.questions += count.questions;
+        renamed.tag_configs += count.tag_configs;
+    }
+
+    info!(
+        "Renamed tags {from:?} to {to:?} in {} exams, skipping {}",
+        renamed.exams.len(),
+        renamed.skipped.len()
+    );
+
+    if body.exam_id.is_none() {
+        rename_registered_tags(&state.production_database, &auth_user, &from, to).await?;
+    }
+
+    Ok(Json(renamed))
+}
+
+/// Unregisters each of `from`, registering `to` in their place if any was registered
+async fn rename_registered_tags(
+    database: &Database,
+    auth_user: &prisma::ExamCreatorUser,
+    from: &[String],
+    to: &str,
+) -> Result<(), Error> {
+    let Some(renamed) = database
+        .exam_creator_tag
+        .find_one(doc! { "name": { "$in": from } })
+        .await?
+    else {
+        return Ok(());
+    };
+    database
+        .exam_creator_tag
+        .delete_many(doc! { "name": { "$in": from } })
+        .await?;
+
+    let tag = ExamCreatorTag {
+        id: ObjectId::new(),
+        name: to.to_string(),
+        description: renamed.description,
+        created_by: auth_user.email.clone(),
+        created_at: DateTime::now(),
+    };
+    // `to` may already be registered
+    register_tag(database, &tag).await?;
+    info!("Renamed registered tags {from:?} to {to:?}");
+
+    Ok(())
+}
@@ -1,43 +1,206 @@
 use axum::extract::ws::{Message, WebSocket};
 use futures_util::{SinkExt, StreamExt};
+use http::StatusCode;
+use mongodb::bson::doc;
 use mongodb::bson::oid::ObjectId;
 use once_cell::sync::Lazy;
 use std::collections::HashMap;
 use std::sync::Mutex;
-use tokio::sync::broadcast;
-use tracing::info;
+use tokio::sync::{broadcast, mpsc};
+use tracing::{error, info, warn};
 
 use crate::{
     database::prisma,
-    state::{ServerState, SocketEvents, remove_user, set_user_activity},
+    errors::Error,
+    merge::SyncedExam,
+    state::{ExamPatch, ExamSync, ServerState, SocketEvents, remove_user, set_user_activity},
 };
 
 // Shared state for WebSocket exam rooms
-static _EXAM_ROOMS: Lazy<Mutex<HashMap<String, broadcast::Sender<String>>>> =
+static EXAM_ROOMS: Lazy<Mutex<HashMap<ObjectId, broadcast::Sender<String>>>> =
     Lazy::new(|| Mutex::new(HashMap::new()));
 
-pub async fn _handle_exam_ws(
+/// Sends an event to all clients in the exam room, if anyone is in the room
+pub fn broadcast_to_exam_room(exam_id: ObjectId, event: &SocketEvents) {
+    let rooms = EXAM_ROOMS.lock().unwrap();
+    let Some(tx) = rooms.get(&exam_id) else {
+        return;
+    };
+
+    match serde_json::to_string(event) {
+        Ok(msg) => {
+            // Only errors if there are no receivers
+            let _ = tx.send(msg);
+        }
+        Err(e) => error!("Failed to serialize exam room event: {e}"),
+    }
+}
+
+/// Loads the exam into client sync from the database, if no one is editing it yet
+pub async fn load_synced_exam(state: &ServerState, exam_id: ObjectId) -> Result<(), Error> {
+    {
+        let client_sync = state.client_sync.lock().unwrap();
+        if client_sync.exams.iter().any(|e| e.exam.id == exam_id) {
+            return Ok(());
+        }
+    }
+
+    let exam = state
+        .production_database
+        .exam_creator_exam
+        .find_one(doc! { "_id": exam_id })
+        .await?
+        .ok_or(Error::Server(
+            StatusCode::BAD_REQUEST,
+            format!("exam non-existent: {exam_id}"),
+        ))?;
+
+    let client_sync = &mut state.client_sync.lock().unwrap();
+    // Another connection may have loaded the exam while the database was queried
+    if !client_sync.exams.iter().any(|e| e.exam.id == exam_id) {
+        client_sync.exams.push(SyncedExam::new(exam));
+    }
+
+    Ok(())
+}
+
+/// Replaces the exam being edited, if anyone is editing it, and sends it to everyone in the exam room.
+///
+/// Returns whether the exam was being edited.
+pub fn reset_synced_exam(state: &ServerState, exam: &prisma::ExamCreatorExam) -> bool {
+    let client_sync = &mut state.client_sync.lock().unwrap();
+    let Some(synced_exam) = client_sync.exams.iter_mut().find(|e| e.exam.id == exam.id) else {
+        return false;
+    };
+
+    let sync = ExamSync {
+        exam: exam.clone(),
+        seq: synced_exam.reset(exam.clone()),
+    };
+    // Sent under the lock, so the room receives it in order with merged patches
+    broadcast_to_exam_room(exam.id, &SocketEvents::ExamSync(sync));
+    true
+}
+
+/// Updates the exam being edited after the exam is saved, if anyone is editing it,
+/// and sends it to everyone in the exam room.
+///
+/// `seq` is the sequence number of the last patch included in the saved exam, if it was saved from the exam room.
+/// If the saved exam includes every patch merged into the exam being edited, the exam is replaced with the saved exam.
+/// Otherwise, co-editors' patches are kept, and rebased onto the saved exam's `version`.
+///
+/// Returns whether the exam was being edited.
+pub fn sync_saved_exam(
+    state: &ServerState,
+    exam: &prisma::ExamCreatorExam,
+    seq: Option<u64>,
+) -> bool {
+    let client_sync = &mut state.client_sync.lock().unwrap();
+    let Some(synced_exam) = client_sync.exams.iter_mut().find(|e| e.exam.id == exam.id) else {
+        return false;
+    };
+
+    let sync = if seq == Some(synced_exam.seq()) || synced_exam.draft.updated.is_none() {
+        ExamSync {
+            exam: exam.clone(),
+            seq: synced_exam.reset(exam.clone()),
+        }
+    } else {
+        info!(
+            "Keeping unsaved edits of exam {} on top of version {}",
+            exam.id, exam.version
+        );
+        synced_exam.rebase(exam.version);
+        exam_sync(synced_exam)
+    };
+    // Sent under the lock, so the room receives it in order with merged patches
+    broadcast_to_exam_room(exam.id, &SocketEvents::ExamSync(sync));
+    true
+}
+
+fn exam_sync(synced_exam: &SyncedExam) -> ExamSync {
+    ExamSync {
+        exam: synced_exam.exam.clone(),
+        seq: synced_exam.seq(),
+    }
+}
+
+/// Handles a connection to an exam room.
+///
+/// The client is sent the current exam state, then receives every patch merged into the exam,
+/// including its own, in the order they were merged into `ClientSync.exams`.
+/// Patches merged before the exam state was read may also be received, and are to be ignored
+/// by their `seq`. A client which misses a patch can send `ExamResync` to be sent the exam state again.
+/// See `SyncedExam::merge` for how concurrent patches are merged.
+/// If any of a patch's operations are discarded, the merged exam state is re-sent to its author.
+///
+/// The exam must already be loaded with `load_synced_exam`.
+pub async fn handle_exam_ws(
     socket: WebSocket,
     auth_user: prisma::ExamCreatorUser,
     state: ServerState,
-    exam_id: String,
+    exam_id: ObjectId,
 ) {
     // By splitting, tasks can be sent and received at the same time.
     let (mut sender, mut receiver) = socket.split();
 
     let tx = {
-        let mut rooms = _EXAM_ROOMS.lock().unwrap();
+        let mut rooms = EXAM_ROOMS.lock().unwrap();
         rooms
-            .entry(exam_id.clone())
+            .entry(exam_id)
             .or_insert_with(|| broadcast::channel(32).0)
             .clone()
     };
+    // Subscribed before the exam state is read, so no patch merged after it is missed.
+    // Patches are broadcast under the `client_sync` lock, so the state read matches a patch's `seq`.
     let mut rx = tx.subscribe();
+    let sync = {
+        let client_sync = state.client_sync.lock().unwrap();
+        client_sync
+            .exams
+            .iter()
+            .find(|e| e.exam.id == exam_id)
+            .map(exam_sync)
+    };
+    let Some(sync) = sync else {
+        error!("Exam {exam_id} missing from client sync");
+        drop(rx);
+        leave_exam_room(exam_id);
+        return;
+    };
 
-    // Receives messages from other clients in the same exam room, and sends it to the current client.
+    // Messages only for the current client
+    let (direct_tx, mut direct_rx) = mpsc::channel::<String>(8);
+    let _ = direct_tx
+        .send(serialize_event(&SocketEvents::ExamSync(sync)))
+        .await;
+
+    let client_sync = state.client_sync.clone();
+    // Receives messages from the exam room and current client, and sends them to the current client.
     let mut send_task = tokio::spawn(async move {
-        while let Ok(msg) = rx.recv().await {
-            // info!("msg: {}", msg);
+        loop {
+            let msg = tokio::select! {
+                msg = rx.recv() => match msg {
+                    Ok(msg) => msg,
+                    Err(broadcast::error::RecvError::Lagged(skipped)) => {
+                        warn!("Exam WebSocket client lagged by {skipped} messages, resyncing");
+                        let sync = {
+                            let client_sync = client_sync.lock().unwrap();
+                            client_sync.exams.iter().find(|e| e.exam.id == exam_id).map(exam_sync)
+                        };
+                        let Some(sync) = sync else {
+                            break;
+                        };
+                        serialize_event(&SocketEvents::ExamSync(sync))
+                    }
+                    Err(broadcast::error::RecvError::Closed) => break,
+                },
+                msg = direct_rx.recv() => match msg {
+                    Some(msg) => msg,
+                    None => break,
+                },
+            };
+
             if sender.send(Message::Text(msg.into())).await.is_err() {
                 break;
             }
@@ -45,43 +208,127 @@ pub async fn _handle_exam_ws(
     });
 
     let client_sync = state.client_sync.clone();
-    let id = exam_id.clone();
-    // Receives messages from current client, and sends it to all other client in the same exam room.
+    let author = auth_user.email.clone();
+    // Receives patches from the current client, merges them, and sends them to all clients in the exam room.
     let mut recv_task = tokio::spawn(async move {
-        while let Some(Ok(Message::Text(text))) = receiver.next().await {
-            let msg = text.to_string();
-            let sock: SocketEvents =
-                serde_json::from_str(&msg).expect("Failed to deserialize message from WebSocket");
-            let SocketEvents::ExamUpdate(new_exam) = sock else {
-                continue; // Ignore other message types
+        while let Some(Ok(message)) = receiver.next().await {
+            let text = match message {
+                Message::Text(text) => text,
+                Message::Close(_) => break,
+                _ => continue,
             };
-            {
+            let event: SocketEvents = match serde_json::from_str(&text) {
+                Ok(event) => event,
+                Err(e) => {
+                    warn!("Invalid message from exam WebSocket: {e}");
+                    continue;
+                }
+            };
+            let (operations, base) = match event {
+                SocketEvents::ExamPatch(ExamPatch {
+                    operations, base, ..
+                }) => (operations, base),
+                SocketEvents::ExamResync => {
+                    let sync = {
+                        let client_sync = client_sync.lock().unwrap();
+                        client_sync
+                            .exams
+                            .iter()
+                            .find(|e| e.exam.id == exam_id)
+                            .map(exam_sync)
+                    };
+                    let Some(sync) = sync else {
+                        error!("Exam {exam_id} missing from client sync");
+                        break;
+                    };
+                    if direct_tx
+                        .send(serialize_event(&SocketEvents::ExamSync(sync)))
+                        .await
+                        .is_err()
+                    {
+                        break;
+                    }
+                    continue;
+                }
+                _ => continue, // Ignore other message types
+            };
+
+            // Lock must not be held across await.
+            let resync = {
                 let client_sync = &mut client_sync.lock().unwrap();
-                if let Some(exam) = client_sync.exams.iter_mut().find(|e| {
-                    e.id == ObjectId::parse_str(&id).expect("Unreachable. Invalid exam id.")
-                }) {
-                    *exam = new_exam.clone();
-                } else {
-                    client_sync.exams.push(new_exam.clone());
+                let Some(synced_exam) = client_sync.exams.iter_mut().find(|e| e.exam.id == exam_id)
+                else {
+                    error!("Exam {exam_id} missing from client sync");
+                    break;
+                };
+
+                let outcome = synced_exam.merge(&author, base, operations);
+                if !outcome.applied.is_empty() {
+                    let patch = SocketEvents::ExamPatch(ExamPatch {
+                        operations: outcome.applied,
+                        author: Some(author.clone()),
+                        base,
+                        seq: outcome.seq,
+                    });
+                    let _ = tx.send(serialize_event(&patch));
+                }
+                for conflict in outcome.conflicts {
+                    info!("Merge conflict in exam {exam_id}: {conflict:?}");
+                    let _ = tx.send(serialize_event(&SocketEvents::ExamMergeConflict(conflict)));
                 }
-            }
 
-            let _ = tx.send(msg);
+                outcome.discarded.then(|| exam_sync(synced_exam))
+            };
+
+            let Some(sync) = resync else {
+                continue;
+            };
+            if direct_tx
+                .send(serialize_event(&SocketEvents::ExamSync(sync)))
+                .await
+                .is_err()
+            {
+                break;
+            }
         }
     });
 
     // If any one of the tasks exit, abort the other.
+    // The aborted task is awaited, so its room subscription is dropped before the room is cleaned up.
     tokio::select! {
-        _ = (&mut send_task) => recv_task.abort(),
-        _ = (&mut recv_task) => send_task.abort(),
+        _ = (&mut send_task) => {
+            recv_task.abort();
+            let _ = recv_task.await;
+        },
+        _ = (&mut recv_task) => {
+            send_task.abort();
+            let _ = send_task.await;
+        },
     };
 
+    leave_exam_room(exam_id);
+
     info!(
         "Exam WebSocket connection closed: {}, {}",
         auth_user.email, exam_id
     );
 }
 
+/// Removes the exam room, once its last subscriber has left
+fn leave_exam_room(exam_id: ObjectId) {
+    let mut rooms = EXAM_ROOMS.lock().unwrap();
+    if rooms
+        .get(&exam_id)
+        .is_some_and(|tx| tx.receiver_count() == 0)
+    {
+        rooms.remove(&exam_id);
+    }
+}
+
+fn serialize_event(event: &SocketEvents) -> String {
+    serde_json::to_string(event).expect("Unreachable. SocketEvents is serializable")
+}
+
 pub async fn handle_users_ws(
     socket: WebSocket,
     auth_user: prisma::ExamCreatorUser,
@@ -116,9 +363,13 @@ pub async fn handle_users_ws(
     let user = auth_user.clone();
     let mut recv_task = tokio::spawn(async move {
         while let Some(Ok(Message::Text(text))) = receiver.next().await {
-            let msg = text.to_string();
-            let sock: SocketEvents =
-                serde_json::from_str(&msg).expect("Failed to deserialize message from WebSocket");
+            let sock: SocketEvents = match serde_json::from_str(&text) {
+                Ok(sock) => sock,
+                Err(e) => {
+                    warn!("Invalid message from users WebSocket: {e}");
+                    continue;
+                }
+            };
             let SocketEvents::ActivityUpdate(activity) = sock else {
                 continue; // Ignore other message types
             };
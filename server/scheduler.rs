@@ -0,0 +1,232 @@
+use http::StatusCode;
+use mongodb::{
+    bson::{DateTime, doc, oid::ObjectId},
+    options::ReturnDocument,
+};
+use tracing::{error, info, warn};
+
+use crate::{
+    database::{Database, prisma, schedule::ExamCreatorSchedule},
+    errors::Error,
+    routes::{
+        deploy_requests::{
+            check_deprecate_request, complete_deploy_request, execute_deploy_request,
+        },
+        exams::{PutExamSeedQuery, seed_exam_to_staging},
+    },
+    state::ServerState,
+};
+
+/// Most times a schedule is run before it is failed, if it keeps failing with a server or database error
+const MAX_SCHEDULE_ATTEMPTS: u32 = 5;
+/// Delay before a failed schedule is run again, multiplied by the number of attempts so far
+const SCHEDULE_RETRY_DELAY: std::time::Duration = std::time::Duration::from_secs(5 * 60);
+
+/// Runs due schedules, earliest first, one at a time.
+///
+/// Schedules which were running when the server stopped are run again,
+/// which is safe as seeding is done in a transaction.
+pub async fn run_schedules(
+    state: ServerState,
+    // How often to check for due schedules
+    interval: std::time::Duration,
+) {
+    loop {
+        tokio::time::sleep(interval).await;
+
+        let schedule = match state
+            .production_database
+            .schedule
+            .find_one_and_update(
+                doc! {
+                    "status": { "$in": ["Scheduled", "Running"] },
+                    "runAt": { "$lte": DateTime::now() },
+                },
+                doc! {
+                    "$set": { "status": "Running", "updatedAt": DateTime::now() },
+                    "$inc": { "attempts": 1 },
+                },
+            )
+            .sort(doc! { "runAt": 1 })
+            .return_document(ReturnDocument::After)
+            .await
+        {
+            Ok(Some(schedule)) => schedule,
+            Ok(None) => continue,
+            Err(e) => {
+                error!("Failed to find due schedules: {e}");
+                continue;
+            }
+        };
+
+        let schedule_id = schedule.id;
+        let mut update = match run_schedule(&state, &schedule).await {
+            Ok(()) => {
+                info!(
+                    "Ran schedule {schedule_id} to {:?} exam {} in {:?}",
+                    schedule.action, schedule.exam_id, schedule.database_environment
+                );
+                doc! { "status": "Completed", "error": null }
+            }
+            Err(e) if !is_retried(&e) => {
+                error!("Schedule {schedule_id} failed, and would fail again if retried: {e}");
+                doc! { "status": "Failed", "error": e.to_string() }
+            }
+            Err(e) if schedule.attempts >= MAX_SCHEDULE_ATTEMPTS => {
+                error!(
+                    "Schedule {schedule_id} failed after {} attempts: {e}",
+                    schedule.attempts
+                );
+                doc! { "status": "Failed", "error": e.to_string() }
+            }
+            Err(e) => {
+                warn!(
+                    "Schedule {schedule_id} failed on attempt {}, so is retried: {e}",
+                    schedule.attempts
+                );
+                let delay = SCHEDULE_RETRY_DELAY * schedule.attempts;
+                let run_at = DateTime::from_millis(
+                    DateTime::now().timestamp_millis() + delay.as_millis() as i64,
+                );
+                doc! { "status": "Scheduled", "error": e.to_string(), "runAt": run_at }
+            }
+        };
+
+        update.insert("updatedAt", DateTime::now());
+        if let Err(e) = state
+            .production_database
+            .schedule
+            .update_one(
+                doc! { "_id": schedule_id, "status": "Running" },
+                doc! { "$set": update },
+            )
+            .await
+        {
+            // The schedule is still running, so is run again on the next interval
+            error!("Failed to record result of schedule {schedule_id}: {e}");
+        }
+    }
+}
+
+/// Whether a schedule which failed with the error is run again.
+///
+/// Client errors, such as a missing exam or a deploy request which is not approved, fail the same way every time,
+/// so only server and database errors are retried.
+fn is_retried(error: &Error) -> bool {
+    match error {
+        Error::Server(status, _) => !status.is_client_error(),
+        _ => true,
+    }
+}
+
+/// Runs the schedule's action.
+///
+/// Changes to production are checked against the schedule's deploy request again, which must still be approved.
+async fn run_schedule(state: &ServerState, schedule: &ExamCreatorSchedule) -> Result<(), Error> {
+    // Changes are recorded as made by the user who created the schedule
+    let actor = state
+        .production_database
+        .exam_creator_user
+        .find_one(doc! { "_id": schedule.created_by_id })
+        .await?
+        .ok_or(Error::Server(
+            StatusCode::BAD_REQUEST,
+            format!("user non-existent: {}", schedule.created_by_id),
+        ))?;
+
+    let Some(deprecated) = schedule.action.deprecated() else {
+        let query = PutExamSeedQuery {
+            orphaned_generations: schedule.orphaned_generations,
+            dry_run: false,
+        };
+
+        match schedule.database_environment {
+            prisma::ExamCreatorDatabaseEnvironment::Staging => {
+                seed_exam_to_staging(state, &actor, schedule.exam_id, query).await?;
+            }
+            prisma::ExamCreatorDatabaseEnvironment::Production => {
+                let deploy_request_id = schedule.deploy_request_id.ok_or(Error::Server(
+                    StatusCode::BAD_REQUEST,
+                    format!("schedule {} has no deploy request", schedule.id),
+                ))?;
+                execute_deploy_request(state, &actor, deploy_request_id, query).await?;
+            }
+        }
+        return Ok(());
+    };
+
+    let (database, deploy_request_id) = match schedule.database_environment {
+        prisma::ExamCreatorDatabaseEnvironment::Staging => (&state.staging_database, None),
+        prisma::ExamCreatorDatabaseEnvironment::Production => {
+            let deploy_request_id = check_deprecate_request(
+                state,
+                schedule.deploy_request_id,
+                schedule.exam_id,
+                deprecated,
+            )
+            .await?;
+            (&state.production_database, Some(deploy_request_id))
+        }
+    };
+
+    set_deprecated(database, &actor, deploy_request_id, schedule, deprecated).await
+}
+
+/// Sets `deprecated` on the exam deployed to `database`.
+///
+/// In production, the deploy request is marked as deployed in the same transaction.
+async fn set_deprecated(
+    database: &Database,
+    actor: &prisma::ExamCreatorUser,
+    deploy_request_id: Option<ObjectId>,
+    schedule: &ExamCreatorSchedule,
+    deprecated: bool,
+) -> Result<(), Error> {
+    let mut session = database
+        .client
+        .start_session()
+        .await
+        .map_err(|e| Error::Deployment("starting transaction", e))?;
+    session
+        .start_transaction()
+        .await
+        .map_err(|e| Error::Deployment("starting transaction", e))?;
+    let written = async {
+        let update_result = database
+            .exam
+            .update_one(
+                doc! { "_id": schedule.exam_id },
+                doc! { "$set": { "deprecated": deprecated } },
+            )
+            .session(&mut session)
+            .await?;
+        if update_result.matched_count == 0 {
+            return Err(Error::Server(
+                StatusCode::BAD_REQUEST,
+                format!(
+                    "exam non-existent in {:?}: {}",
+                    schedule.database_environment, schedule.exam_id
+                ),
+            ));
+        }
+        if let Some(deploy_request_id) = deploy_request_id {
+            complete_deploy_request(database, &mut session, deploy_request_id, actor, None).await?;
+        }
+        Ok::<_, Error>(())
+    };
+    if let Err(e) = written.await {
+        if let Err(abort_error) = session.abort_transaction().await {
+            warn!(
+                "Failed to abort schedule {} of exam {}: {abort_error}",
+                schedule.id, schedule.exam_id
+            );
+        }
+        return Err(e);
+    }
+    session
+        .commit_transaction()
+        .await
+        .map_err(|e| Error::Deployment("committing transaction", e))?;
+
+    Ok(())
+}
@@ -4,11 +4,15 @@ use axum::extract::FromRef;
 use axum_extra::extract::cookie::Key;
 use serde::{Deserialize, Serialize};
 use supabase_rs::SupabaseClient;
+use tokio::sync::broadcast;
 use tracing::error;
 
 use crate::{
     config::EnvVars,
-    database::{Database, prisma},
+    database::{Database, generation::ExamCreatorGenerationJob, prisma},
+    drift::DriftReport,
+    merge::{MergeConflict, SyncedExam},
+    patch,
     routes::metrics::{GetAttemptsMetrics, GetExamMetricsById},
 };
 
@@ -22,6 +26,10 @@ pub struct ServerState {
     pub env_vars: EnvVars,
     pub exam_metrics_by_id_cache: Arc<Mutex<Vec<GetExamMetricsById>>>,
     pub attempt_metrics_cache: Arc<Mutex<Cache<Vec<GetAttemptsMetrics>>>>,
+    /// Progress of generation jobs, as they are updated
+    pub generation_jobs: broadcast::Sender<ExamCreatorGenerationJob>,
+    /// Latest drift report, replaced in the background
+    pub drift_report: Arc<Mutex<Option<DriftReport>>>,
 }
 
 impl FromRef<ServerState> for Key {
@@ -36,7 +44,7 @@ pub struct ClientSync {
     /// Used to store online users' activity
     pub users: Vec<User>,
     /// Updated exams yet to be saved to the database
-    pub exams: Vec<prisma::ExamCreatorExam>,
+    pub exams: Vec<SyncedExam>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -108,11 +116,52 @@ pub async fn cleanup_online_users(
 #[derive(Serialize, Deserialize, Debug, Clone)]
 #[serde(rename_all = "kebab-case", tag = "type", content = "data")]
 pub enum SocketEvents {
-    ExamUpdate(prisma::ExamCreatorExam),
+    /// Full state of an exam being edited
+    ///
+    /// Sent by the server when joining an exam room, and whenever the exam is reset
+    ExamSync(ExamSync),
+    /// Granular changes to an exam being edited
+    ExamPatch(ExamPatch),
+    /// Concurrent edits which could not both be kept
+    ///
+    /// Sent to the whole exam room, for clients to show to the users involved
+    #[serde(skip_deserializing)]
+    ExamMergeConflict(MergeConflict),
+    /// Request for the full state of an exam being edited, answered with `ExamSync`
+    ///
+    /// Sent by a client which missed a patch
+    ExamResync,
     UsersUpdate(Vec<User>),
     ActivityUpdate(Activity),
 }
 
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct ExamSync {
+    pub exam: prisma::ExamCreatorExam,
+    /// Sequence number of the last patch applied to the exam
+    pub seq: u64,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct ExamPatch {
+    pub operations: Vec<patch::ExamOperation>,
+    /// Email of the user who made the changes
+    ///
+    /// Set by the server when broadcasting
+    #[serde(default)]
+    pub author: Option<String>,
+    /// Sequence number of the last sync or patch the author had applied when making the changes
+    ///
+    /// Set by the client
+    #[serde(default)]
+    pub base: u64,
+    /// Sequence number of the patch
+    ///
+    /// Set by the server when broadcasting
+    #[serde(default)]
+    pub seq: u64,
+}
+
 /// Sets the user's activity
 /// If the user is not found, does nothing but logs an error
 pub fn set_user_activity(client_sync: &mut ClientSync, email: &str, page: String) {
@@ -0,0 +1,302 @@
+use std::collections::{BTreeMap, HashSet};
+
+use serde::Serialize;
+
+use crate::database::prisma::ExamCreatorExam;
+
+/// How much a tag is used
+#[derive(Debug, Clone, Default, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TagUsage {
+    pub tag: String,
+    /// Number of questions with the tag, including deprecated questions
+    pub questions: usize,
+    /// Number of tag configs with the tag in their group
+    pub tag_configs: usize,
+    /// Number of exams using the tag in a question or tag config
+    pub exams: usize,
+    /// Whether the tag is an `ExamCreatorTag`
+    pub registered: bool,
+}
+
+/// Usage of every tag in the given exams, and of every registered tag, sorted by tag
+pub fn tag_usage(exams: &[ExamCreatorExam], registered: &[String]) -> Vec<TagUsage> {
+    let mut usage: BTreeMap<&str, TagUsage> = BTreeMap::new();
+    for tag in registered {
+        usage_of(&mut usage, tag).registered = true;
+    }
+
+    for exam in exams {
+        let mut exam_tags: HashSet<&str> = HashSet::new();
+        for question in exam.question_sets.iter().flat_map(|qs| &qs.questions) {
+            for tag in &question.tags {
+                usage_of(&mut usage, tag).questions += 1;
+                exam_tags.insert(tag);
+            }
+        }
+        for tag in exam.config.tags.iter().flat_map(|tc| &tc.group) {
+            usage_of(&mut usage, tag).tag_configs += 1;
+            exam_tags.insert(tag);
+        }
+        for tag in exam_tags {
+            usage_of(&mut usage, tag).exams += 1;
+        }
+    }
+
+    usage.into_values().collect()
+}
+
+fn usage_of<'a, 'b>(usage: &'b mut BTreeMap<&'a str, TagUsage>, tag: &'a str) -> &'b mut TagUsage {
+    usage.entry(tag).or_insert_with(|| TagUsage {
+        tag: tag.to_string(),
+        ..Default::default()
+    })
+}
+
+/// Number of places tags were renamed in an exam
+#[derive(Debug, Clone, Copy, Default, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RenameCount {
+    pub questions: usize,
+    pub tag_configs: usize,
+}
+
+/// Replaces each of `from` with `to`, in the exam's questions and tag configs.
+///
+/// Renaming several tags to the same tag merges them. A question or tag config is left with
+/// one `to`, even if it had more than one of `from`, or already had `to`.
+pub fn rename_tags(exam: &mut ExamCreatorExam, from: &[String], to: &str) -> RenameCount {
+    let mut count = RenameCount::default();
+    for question in exam
+        .question_sets
+        .iter_mut()
+        .flat_map(|qs| &mut qs.questions)
+    {
+        if rename_in(&mut question.tags, from, to) {
+            count.questions += 1;
+        }
+    }
+    for tag_config in &mut exam.config.tags {
+        if rename_in(&mut tag_config.group, from, to) {
+            count.tag_configs += 1;
+        }
+    }
+    count
+}
+
+/// Returns whether any tag was renamed
+fn rename_in(tags: &mut Vec<String>, from: &[String], to: &str) -> bool {
+    if !tags.iter().any(|tag| from.contains(tag)) {
+        return false;
+    }
+
+    let mut renamed: Vec<String> = vec![];
+    for tag in tags.drain(..) {
+        let tag = if from.contains(&tag) {
+            to.to_string()
+        } else {
+            tag
+        };
+        if !renamed.contains(&tag) {
+            renamed.push(tag);
+        }
+    }
+    *tags = renamed;
+    true
+}
+
+/// Whether two different tags are likely meant to be the same, such as `JavaScript` and `javascript`,
+/// `front-end` and `frontend`, or `React` and `Recat`.
+pub fn near_duplicate(a: &str, b: &str) -> bool {
+    if a == b {
+        return false;
+    }
+
+    let a: Vec<char> = simplify(a).chars().collect();
+    let b: Vec<char> = simplify(b).chars().collect();
+    if a == b {
+        return true;
+    }
+
+    // Short tags are too easily one edit apart, such as `css` and `js`
+    a.len().min(b.len()) >= 4 && edit_distance(&a, &b) <= 1
+}
+
+/// Lowercase, without separators
+fn simplify(tag: &str) -> String {
+    tag.chars()
+        .filter(|c| c.is_alphanumeric())
+        .flat_map(char::to_lowercase)
+        .collect()
+}
+
+/// Levenshtein distance, where swapping two adjacent characters is one edit
+fn edit_distance(a: &[char], b: &[char]) -> usize {
+    // Rows of the distance matrix: two back, previous, and current
+    let mut two_back: Vec<usize> = vec![0; b.len() + 1];
+    let mut previous: Vec<usize> = (0..=b.len()).collect();
+    let mut current: Vec<usize> = vec![0; b.len() + 1];
+
+    for i in 1..=a.len() {
+        current[0] = i;
+        for j in 1..=b.len() {
+            let substitution = usize::from(a[i - 1] != b[j - 1]);
+            current[j] = (previous[j] + 1)
+                .min(current[j - 1] + 1)
+                .min(previous[j - 1] + substitution);
+            if i > 1 && j > 1 && a[i - 1] == b[j - 2] && a[i - 2] == b[j - 1] {
+                current[j] = current[j].min(two_back[j - 2] + 1);
+            }
+        }
+        std::mem::swap(&mut two_back, &mut previous);
+        std::mem::swap(&mut previous, &mut current);
+    }
+
+    previous[b.len()]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::database::prisma::{
+        ExamEnvironmentConfig, ExamEnvironmentMultipleChoiceQuestion, ExamEnvironmentQuestionSet,
+        ExamEnvironmentQuestionType, ExamEnvironmentTagConfig,
+    };
+    use mongodb::bson::oid::ObjectId;
+
+    fn distance(a: &str, b: &str) -> usize {
+        let a: Vec<char> = a.chars().collect();
+        let b: Vec<char> = b.chars().collect();
+        edit_distance(&a, &b)
+    }
+
+    fn strings(tags: &[&str]) -> Vec<String> {
+        tags.iter().map(|t| t.to_string()).collect()
+    }
+
+    fn exam(question_tags: &[&[&str]], tag_config_groups: &[&[&str]]) -> ExamCreatorExam {
+        ExamCreatorExam {
+            question_sets: vec![ExamEnvironmentQuestionSet {
+                id: ObjectId::new(),
+                _type: ExamEnvironmentQuestionType::MultipleChoice,
+                context: None,
+                questions: question_tags
+                    .iter()
+                    .map(|tags| ExamEnvironmentMultipleChoiceQuestion {
+                        id: ObjectId::new(),
+                        text: "Question".to_string(),
+                        tags: strings(tags),
+                        audio: None,
+                        answers: vec![],
+                        deprecated: false,
+                    })
+                    .collect(),
+            }],
+            config: ExamEnvironmentConfig {
+                tags: tag_config_groups
+                    .iter()
+                    .map(|group| ExamEnvironmentTagConfig {
+                        group: strings(group),
+                        number_of_questions: 1,
+                    })
+                    .collect(),
+                ..Default::default()
+            },
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn edit_distance_counts_single_character_edits() {
+        assert_eq!(distance("react", "react"), 0);
+        assert_eq!(distance("react", "reacts"), 1);
+        assert_eq!(distance("react", "rect"), 1);
+        assert_eq!(distance("react", "reakt"), 1);
+        assert_eq!(distance("", "css"), 3);
+        assert_eq!(distance("kitten", "sitting"), 3);
+    }
+
+    #[test]
+    fn edit_distance_counts_adjacent_swap_as_one_edit() {
+        assert_eq!(distance("react", "recat"), 1);
+        assert_eq!(distance("ab", "ba"), 1);
+        // Swapped characters are not edited again
+        assert_eq!(distance("ca", "abc"), 3);
+    }
+
+    #[test]
+    fn near_duplicates_differ_by_case_separators_or_one_edit() {
+        assert!(near_duplicate("JavaScript", "javascript"));
+        assert!(near_duplicate("front-end", "frontend"));
+        assert!(near_duplicate("front end", "Front_End"));
+        assert!(near_duplicate("React", "Recat"));
+        assert!(near_duplicate("React", "Reacts"));
+    }
+
+    #[test]
+    fn different_tags_are_not_near_duplicates() {
+        assert!(!near_duplicate("React", "React"));
+        assert!(!near_duplicate("css", "js"));
+        assert!(!near_duplicate("html", "xml"));
+        assert!(!near_duplicate("React", "Redux"));
+    }
+
+    #[test]
+    fn rename_merges_tags() {
+        let mut exam = exam(
+            &[&["js", "javascript"], &["JS", "react"], &["css"]],
+            &[&["js", "react"], &["css"]],
+        );
+
+        let count = rename_tags(&mut exam, &strings(&["js", "JS"]), "javascript");
+
+        let question_tags: Vec<&Vec<String>> = exam.question_sets[0]
+            .questions
+            .iter()
+            .map(|q| &q.tags)
+            .collect();
+        assert_eq!(
+            question_tags,
+            [
+                &strings(&["javascript"]),
+                &strings(&["javascript", "react"]),
+                &strings(&["css"])
+            ]
+        );
+        assert_eq!(exam.config.tags[0].group, strings(&["javascript", "react"]));
+        assert_eq!(exam.config.tags[1].group, strings(&["css"]));
+        assert_eq!(count.questions, 2);
+        assert_eq!(count.tag_configs, 1);
+    }
+
+    #[test]
+    fn usage_counts_questions_tag_configs_and_exams() {
+        let exams = [
+            exam(&[&["js"], &["js", "css"]], &[&["js"]]),
+            exam(&[&["css"]], &[]),
+        ];
+
+        let usage = tag_usage(&exams, &strings(&["html", "js"]));
+
+        let tags: Vec<(&str, usize, usize, usize, bool)> = usage
+            .iter()
+            .map(|u| {
+                (
+                    u.tag.as_str(),
+                    u.questions,
+                    u.tag_configs,
+                    u.exams,
+                    u.registered,
+                )
+            })
+            .collect();
+        assert_eq!(
+            tags,
+            [
+                ("css", 2, 0, 2, false),
+                ("html", 0, 0, 0, true),
+                ("js", 2, 1, 1, true),
+            ]
+        );
+    }
+}
@@ -0,0 +1,497 @@
+use std::collections::BTreeSet;
+
+use serde::Serialize;
+
+use crate::{database::prisma, tags};
+
+#[derive(Debug, Clone, Copy, Serialize, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum Severity {
+    /// The exam cannot be generated, or generates broken attempts
+    Error,
+    /// The exam can be generated, but is likely not as intended
+    Warning,
+}
+
+/// Stable identifier of a kind of issue, for clients to match on instead of the message
+#[derive(Debug, Clone, Copy, Serialize, PartialEq, Eq)]
+#[serde(rename_all = "SCREAMING_SNAKE_CASE")]
+pub enum IssueCode {
+    EmptyName,
+    PassingPercentOutOfRange,
+    NotEnoughTaggedQuestions,
+    NotEnoughQuestionSets,
+    NotEnoughQuestions,
+    NoQuestionSetLargeEnough,
+    NotEnoughCorrectAnswers,
+    NotEnoughIncorrectAnswers,
+    EmptyQuestionText,
+    NoCorrectAnswer,
+    EmptyAnswerText,
+    EmptyQuestionSet,
+    UnconfiguredQuestionSetType,
+    UnknownTag,
+    NearDuplicateTag,
+    // Lints
+    DuplicateAnswerText,
+    DuplicateQuestionText,
+    AnswerContainsQuestion,
+    CorrectAnswerReused,
+    CorrectAnswerLongest,
+    UnclosedCode,
+}
+
+impl IssueCode {
+    pub fn severity(&self) -> Severity {
+        match self {
+            IssueCode::EmptyName
+            | IssueCode::PassingPercentOutOfRange
+            | IssueCode::NotEnoughTaggedQuestions
+            | IssueCode::NotEnoughQuestionSets
+            | IssueCode::NotEnoughQuestions
+            | IssueCode::NoQuestionSetLargeEnough
+            | IssueCode::NotEnoughCorrectAnswers
+            | IssueCode::NotEnoughIncorrectAnswers
+            | IssueCode::EmptyQuestionText
+            | IssueCode::NoCorrectAnswer
+            | IssueCode::EmptyAnswerText => Severity::Error,
+            _ => Severity::Warning,
+        }
+    }
+}
+
+/// A problem with an exam, located by a JSON path into the `ExamCreatorExam`.
+///
+/// For example, `$.questionSets[0].questions[1].answers[2].text`.
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ValidationIssue {
+    pub severity: Severity,
+    pub code: IssueCode,
+    pub path: String,
+    pub message: String,
+}
+
+impl ValidationIssue {
+    pub fn new(code: IssueCode, path: String, message: String) -> Self {
+        Self {
+            severity: code.severity(),
+            code,
+            path,
+            message,
+        }
+    }
+}
+
+pub fn question_set_path(question_set: usize) -> String {
+    format!("$.questionSets[{question_set}]")
+}
+
+pub fn question_path(question_set: usize, question: usize) -> String {
+    format!("$.questionSets[{question_set}].questions[{question}]")
+}
+
+pub fn answer_path(question_set: usize, question: usize, answer: usize) -> String {
+    format!("$.questionSets[{question_set}].questions[{question}].answers[{answer}]")
+}
+
+/// Checks the exam's config and content, returning every issue found.
+///
+/// Issues are ordered config first, then by position in the exam.
+pub fn validate_exam(exam: &prisma::ExamCreatorExam) -> Vec<ValidationIssue> {
+    let config = &exam.config;
+    let question_sets = &exam.question_sets;
+    let mut issues = vec![];
+
+    if config.name.is_empty() {
+        issues.push(ValidationIssue::new(
+            IssueCode::EmptyName,
+            "$.config.name".to_string(),
+            "Config name is empty".to_string(),
+        ));
+    }
+
+    if config.passing_percent < 0.0 || config.passing_percent > 100.0 {
+        issues.push(ValidationIssue::new(
+            IssueCode::PassingPercentOutOfRange,
+            "$.config.passingPercent".to_string(),
+            "Config passing percent must be between 0.0 and 100.0".to_string(),
+        ));
+    }
+
+    // Sorted, so suggestions are stable
+    let question_tags: BTreeSet<&str> = question_sets
+        .iter()
+        .flat_map(|qs| &qs.questions)
+        .flat_map(|q| &q.tags)
+        .map(String::as_str)
+        .collect();
+
+    // A question satisfies a tag config, if its tags include all of the config's group
+    for (i, tag_config) in config.tags.iter().enumerate() {
+        for (k, tag) in tag_config.group.iter().enumerate() {
+            let similar = question_tags
+                .iter()
+                .find(|question_tag| tags::near_duplicate(tag, question_tag));
+            let path = format!("$.config.tags[{i}].group[{k}]");
+            if !question_tags.contains(tag.as_str()) {
+                let suggestion = similar
+                    .map(|similar| format!(" Did you mean {similar:?}?"))
+                    .unwrap_or_default();
+                issues.push(ValidationIssue::new(
+                    IssueCode::UnknownTag,
+                    path,
+                    format!("Tag {tag:?} is on no question.{suggestion}"),
+                ));
+            } else if let Some(similar) = similar {
+                issues.push(ValidationIssue::new(
+                    IssueCode::NearDuplicateTag,
+                    path,
+                    format!("Tag {tag:?} is similar to {similar:?}, which is also on questions"),
+                ));
+            }
+        }
+
+        let available_questions = question_sets
+            .iter()
+            .flat_map(|qs| &qs.questions)
+            .filter(|q| tag_config.group.iter().all(|tag| q.tags.contains(tag)))
+            .count();
+        if available_questions < tag_config.number_of_questions as usize {
+            issues.push(ValidationIssue::new(
+                IssueCode::NotEnoughTaggedQuestions,
+                format!("$.config.tags[{i}]"),
+                format!(
+                    "Not enough questions for tag group {:?}. Available: {}, Required: {}",
+                    tag_config.group, available_questions, tag_config.number_of_questions
+                ),
+            ));
+        }
+    }
+
+    for (i, qs_config) in config.question_sets.iter().enumerate() {
+        let path = format!("$.config.questionSets[{i}]");
+        let sets_of_type: Vec<&prisma::ExamEnvironmentQuestionSet> = question_sets
+            .iter()
+            .filter(|qs| qs._type == qs_config._type)
+            .collect();
+
+        if sets_of_type.len() < qs_config.number_of_set as usize {
+            issues.push(ValidationIssue::new(
+                IssueCode::NotEnoughQuestionSets,
+                path.clone(),
+                format!(
+                    "Not enough {:?} question sets. Available: {}, Required: {}",
+                    qs_config._type,
+                    sets_of_type.len(),
+                    qs_config.number_of_set
+                ),
+            ));
+        }
+
+        let total_questions: usize = sets_of_type.iter().map(|qs| qs.questions.len()).sum();
+        let required_questions =
+            qs_config.number_of_set as usize * qs_config.number_of_questions as usize;
+        if total_questions < required_questions {
+            issues.push(ValidationIssue::new(
+                IssueCode::NotEnoughQuestions,
+                path.clone(),
+                format!(
+                    "Not enough {:?} questions overall. Available: {}, Required: {}",
+                    qs_config._type, total_questions, required_questions
+                ),
+            ));
+        }
+
+        let has_enough_in_single_set = sets_of_type
+            .iter()
+            .any(|qs| qs.questions.len() >= qs_config.number_of_questions as usize);
+        if !has_enough_in_single_set {
+            issues.push(ValidationIssue::new(
+                IssueCode::NoQuestionSetLargeEnough,
+                path,
+                format!(
+                    "No single {:?} question set has {} questions",
+                    qs_config._type, qs_config.number_of_questions
+                ),
+            ));
+        }
+    }
+
+    for (i, question_set) in question_sets.iter().enumerate() {
+        if question_set.questions.is_empty() {
+            issues.push(ValidationIssue::new(
+                IssueCode::EmptyQuestionSet,
+                question_set_path(i),
+                "Question set has no questions".to_string(),
+            ));
+        }
+
+        let qs_configs: Vec<&prisma::ExamEnvironmentQuestionSetConfig> = config
+            .question_sets
+            .iter()
+            .filter(|qs_config| qs_config._type == question_set._type)
+            .collect();
+        if qs_configs.is_empty() {
+            issues.push(ValidationIssue::new(
+                IssueCode::UnconfiguredQuestionSetType,
+                question_set_path(i),
+                format!(
+                    "No question set config is for {:?} question sets, so this question set is never generated",
+                    question_set._type
+                ),
+            ));
+        }
+
+        for (j, question) in question_set.questions.iter().enumerate() {
+            if question.text.trim().is_empty() {
+                issues.push(ValidationIssue::new(
+                    IssueCode::EmptyQuestionText,
+                    format!("{}.text", question_path(i, j)),
+                    "Question has empty text".to_string(),
+                ));
+            }
+
+            let num_correct_answers = question.answers.iter().filter(|a| a.is_correct).count();
+            let num_incorrect_answers = question.answers.len() - num_correct_answers;
+            if num_correct_answers == 0 {
+                issues.push(ValidationIssue::new(
+                    IssueCode::NoCorrectAnswer,
+                    question_path(i, j),
+                    "Question has no correct answers".to_string(),
+                ));
+            }
+
+            for qs_config in &qs_configs {
+                if num_correct_answers < qs_config.number_of_correct_answers as usize {
+                    issues.push(ValidationIssue::new(
+                        IssueCode::NotEnoughCorrectAnswers,
+                        question_path(i, j),
+                        format!(
+                            "Not enough correct answers. Available: {}, Required: {}",
+                            num_correct_answers, qs_config.number_of_correct_answers
+                        ),
+                    ));
+                }
+                if num_incorrect_answers < qs_config.number_of_incorrect_answers as usize {
+                    issues.push(ValidationIssue::new(
+                        IssueCode::NotEnoughIncorrectAnswers,
+                        question_path(i, j),
+                        format!(
+                            "Not enough incorrect answers. Available: {}, Required: {}",
+                            num_incorrect_answers, qs_config.number_of_incorrect_answers
+                        ),
+                    ));
+                }
+            }
+
+            for (k, answer) in question.answers.iter().enumerate() {
+                if answer.text.trim().is_empty() {
+                    issues.push(ValidationIssue::new(
+                        IssueCode::EmptyAnswerText,
+                        format!("{}.text", answer_path(i, j, k)),
+                        "Answer has empty text".to_string(),
+                    ));
+                }
+            }
+        }
+    }
+
+    issues
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use mongodb::bson::oid::ObjectId;
+
+    fn answer(is_correct: bool) -> prisma::ExamEnvironmentAnswer {
+        prisma::ExamEnvironmentAnswer {
+            id: ObjectId::new(),
+            is_correct,
+            text: if is_correct { "Right" } else { "Wrong" }.to_string(),
+        }
+    }
+
+    fn question(text: &str) -> prisma::ExamEnvironmentMultipleChoiceQuestion {
+        prisma::ExamEnvironmentMultipleChoiceQuestion {
+            id: ObjectId::new(),
+            text: text.to_string(),
+            tags: vec!["a".to_string()],
+            audio: None,
+            answers: vec![answer(true), answer(false)],
+            deprecated: false,
+        }
+    }
+
+    /// Exam with one question set of two questions, which has no issues
+    fn exam() -> prisma::ExamCreatorExam {
+        prisma::ExamCreatorExam {
+            question_sets: vec![prisma::ExamEnvironmentQuestionSet {
+                id: ObjectId::new(),
+                _type: prisma::ExamEnvironmentQuestionType::MultipleChoice,
+                context: None,
+                questions: vec![question("First"), question("Second")],
+            }],
+            config: prisma::ExamEnvironmentConfig {
+                name: "Exam".to_string(),
+                tags: vec![prisma::ExamEnvironmentTagConfig {
+                    group: vec!["a".to_string()],
+                    number_of_questions: 1,
+                }],
+                question_sets: vec![prisma::ExamEnvironmentQuestionSetConfig {
+                    _type: prisma::ExamEnvironmentQuestionType::MultipleChoice,
+                    number_of_set: 1,
+                    number_of_questions: 2,
+                    number_of_correct_answers: 1,
+                    number_of_incorrect_answers: 1,
+                }],
+                ..Default::default()
+            },
+            ..Default::default()
+        }
+    }
+
+    /// Paths of the issues with `code`, which are all errors
+    fn error_paths(exam: &prisma::ExamCreatorExam, code: IssueCode) -> Vec<String> {
+        validate_exam(exam)
+            .into_iter()
+            .filter(|issue| issue.code == code)
+            .inspect(|issue| assert_eq!(issue.severity, Severity::Error))
+            .map(|issue| issue.path)
+            .collect()
+    }
+
+    #[test]
+    fn valid_exam_has_no_issues() {
+        assert!(validate_exam(&exam()).is_empty());
+    }
+
+    #[test]
+    fn empty_name() {
+        let mut exam = exam();
+        exam.config.name = String::new();
+
+        assert_eq!(error_paths(&exam, IssueCode::EmptyName), ["$.config.name"]);
+    }
+
+    #[test]
+    fn passing_percent_out_of_range() {
+        let mut exam = exam();
+        exam.config.passing_percent = 100.5;
+
+        assert_eq!(
+            error_paths(&exam, IssueCode::PassingPercentOutOfRange),
+            ["$.config.passingPercent"]
+        );
+    }
+
+    #[test]
+    fn not_enough_tagged_questions() {
+        let mut exam = exam();
+        exam.config.tags[0].number_of_questions = 3;
+
+        assert_eq!(
+            error_paths(&exam, IssueCode::NotEnoughTaggedQuestions),
+            ["$.config.tags[0]"]
+        );
+    }
+
+    #[test]
+    fn not_enough_question_sets() {
+        let mut exam = exam();
+        exam.config.question_sets[0].number_of_set = 2;
+
+        assert_eq!(
+            error_paths(&exam, IssueCode::NotEnoughQuestionSets),
+            ["$.config.questionSets[0]"]
+        );
+    }
+
+    #[test]
+    fn not_enough_questions() {
+        let mut exam = exam();
+        exam.config.question_sets[0].number_of_questions = 3;
+
+        assert_eq!(
+            error_paths(&exam, IssueCode::NotEnoughQuestions),
+            ["$.config.questionSets[0]"]
+        );
+    }
+
+    #[test]
+    fn no_question_set_large_enough() {
+        let mut exam = exam();
+        let mut question_set = exam.question_sets[0].clone();
+        question_set.id = ObjectId::new();
+        question_set.questions.remove(0);
+        exam.question_sets[0].questions.remove(1);
+        exam.question_sets.push(question_set);
+
+        assert_eq!(
+            error_paths(&exam, IssueCode::NoQuestionSetLargeEnough),
+            ["$.config.questionSets[0]"]
+        );
+        assert!(error_paths(&exam, IssueCode::NotEnoughQuestions).is_empty());
+    }
+
+    #[test]
+    fn not_enough_correct_answers() {
+        let mut exam = exam();
+        exam.config.question_sets[0].number_of_correct_answers = 2;
+
+        assert_eq!(
+            error_paths(&exam, IssueCode::NotEnoughCorrectAnswers),
+            [
+                "$.questionSets[0].questions[0]",
+                "$.questionSets[0].questions[1]"
+            ]
+        );
+    }
+
+    #[test]
+    fn not_enough_incorrect_answers() {
+        let mut exam = exam();
+        exam.question_sets[0].questions[1]
+            .answers
+            .retain(|a| a.is_correct);
+
+        assert_eq!(
+            error_paths(&exam, IssueCode::NotEnoughIncorrectAnswers),
+            ["$.questionSets[0].questions[1]"]
+        );
+    }
+
+    #[test]
+    fn empty_question_text() {
+        let mut exam = exam();
+        exam.question_sets[0].questions[1].text = " ".to_string();
+
+        assert_eq!(
+            error_paths(&exam, IssueCode::EmptyQuestionText),
+            ["$.questionSets[0].questions[1].text"]
+        );
+    }
+
+    #[test]
+    fn no_correct_answer() {
+        let mut exam = exam();
+        exam.question_sets[0].questions[0].answers[0].is_correct = false;
+
+        assert_eq!(
+            error_paths(&exam, IssueCode::NoCorrectAnswer),
+            ["$.questionSets[0].questions[0]"]
+        );
+    }
+
+    #[test]
+    fn empty_answer_text() {
+        let mut exam = exam();
+        exam.question_sets[0].questions[0].answers[1].text = String::new();
+
+        assert_eq!(
+            error_paths(&exam, IssueCode::EmptyAnswerText),
+            ["$.questionSets[0].questions[0].answers[1].text"]
+        );
+    }
+}
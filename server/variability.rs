@@ -0,0 +1,265 @@
+use std::collections::HashMap;
+
+use mongodb::bson::oid::ObjectId;
+use serde::Serialize;
+
+use crate::{
+    database::prisma::{ExamCreatorExam, ExamEnvironmentGeneratedExam},
+    generate::Exposure,
+};
+
+/// How much the live generations of an exam differ, and how evenly they use the exam's questions.
+///
+/// Deprecated generations are only counted in `deprecated_generations`.
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct GenerationMetrics {
+    pub total_generations: usize,
+    pub deprecated_generations: usize,
+    /// Variability of the questions between each pair of generations
+    ///
+    /// `None` if there are fewer than two generations to compare.
+    pub question_variability: Option<Variability>,
+    /// Variability of the answers between each pair of generations
+    pub answer_variability: Option<Variability>,
+    /// Exposure of every question in the exam, including deprecated questions
+    pub questions: Vec<QuestionExposure>,
+    /// Non-deprecated questions which appear in no generation
+    pub unused_questions: Vec<ObjectId>,
+}
+
+/// Summary of `(number of items in A, but not in B) / (number of items in A)` over every pair of generations
+#[derive(Debug, Clone, Serialize)]
+pub struct Variability {
+    pub mean: f64,
+    pub min: f64,
+    pub max: f64,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct QuestionExposure {
+    pub question_set_id: ObjectId,
+    pub question_id: ObjectId,
+    pub deprecated: bool,
+    /// Number of generations the question appears in
+    pub generations: u32,
+    pub answers: Vec<AnswerExposure>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AnswerExposure {
+    pub answer_id: ObjectId,
+    pub is_correct: bool,
+    /// Number of generations the answer appears in
+    pub generations: u32,
+    /// Number of generations the answer appears at each position of its question
+    pub positions: Vec<u32>,
+}
+
+/// Overlap between two generations
+#[derive(Debug, Clone, Serialize)]
+pub struct GenerationOverlap {
+    pub generations: [ObjectId; 2],
+    /// Fraction of the first generation's questions also in the second
+    pub questions: f64,
+    /// Fraction of the first generation's answers also in the second
+    pub answers: f64,
+}
+
+/// Question and answer ids of a generation, sorted for comparison
+struct GenerationIds {
+    id: ObjectId,
+    questions: Vec<ObjectId>,
+    answers: Vec<ObjectId>,
+}
+
+impl GenerationIds {
+    fn new(generated_exam: &ExamEnvironmentGeneratedExam) -> Self {
+        let mut questions: Vec<ObjectId> = generated_exam
+            .question_sets
+            .iter()
+            .flat_map(|qs| qs.questions.iter().map(|q| q.id))
+            .collect();
+        let mut answers: Vec<ObjectId> = generated_exam
+            .question_sets
+            .iter()
+            .flat_map(|qs| &qs.questions)
+            .flat_map(|q| q.answers.iter().copied())
+            .collect();
+        questions.sort();
+        answers.sort();
+        Self {
+            id: generated_exam.id,
+            questions,
+            answers,
+        }
+    }
+}
+
+pub fn generation_metrics(
+    exam: &ExamCreatorExam,
+    generated_exams: &[ExamEnvironmentGeneratedExam],
+) -> GenerationMetrics {
+    let (deprecated, live): (Vec<_>, Vec<_>) = generated_exams.iter().partition(|g| g.deprecated);
+    let exposure = Exposure::new(live.iter().copied());
+
+    // Number of generations each answer appears at each position, by question and answer id
+    let mut positions: HashMap<(ObjectId, ObjectId), Vec<u32>> = HashMap::new();
+    for question in live
+        .iter()
+        .flat_map(|g| &g.question_sets)
+        .flat_map(|qs| &qs.questions)
+    {
+        for (position, answer_id) in question.answers.iter().enumerate() {
+            let counts = positions.entry((question.id, *answer_id)).or_default();
+            if counts.len() <= position {
+                counts.resize(position + 1, 0);
+            }
+            counts[position] += 1;
+        }
+    }
+
+    let mut questions = vec![];
+    for question_set in &exam.question_sets {
+        for question in &question_set.questions {
+            let answers = question
+                .answers
+                .iter()
+                .map(|answer| AnswerExposure {
+                    answer_id: answer.id,
+                    is_correct: answer.is_correct,
+                    generations: exposure.answer(answer.id),
+                    positions: positions
+                        .remove(&(question.id, answer.id))
+                        .unwrap_or_default(),
+                })
+                .collect();
+
+            questions.push(QuestionExposure {
+                question_set_id: question_set.id,
+                question_id: question.id,
+                deprecated: question.deprecated,
+                generations: exposure.question(question.id),
+                answers,
+            });
+        }
+    }
+
+    let unused_questions = questions
+        .iter()
+        .filter(|q| !q.deprecated && q.generations == 0)
+        .map(|q| q.question_id)
+        .collect();
+
+    let mut question_variability = VariabilityAccumulator::default();
+    let mut answer_variability = VariabilityAccumulator::default();
+    for overlap in GenerationOverlaps::new(generated_exams) {
+        question_variability.add(1.0 - overlap.questions);
+        answer_variability.add(1.0 - overlap.answers);
+    }
+
+    GenerationMetrics {
+        total_generations: live.len(),
+        deprecated_generations: deprecated.len(),
+        question_variability: question_variability.finish(),
+        answer_variability: answer_variability.finish(),
+        questions,
+        unused_questions,
+    }
+}
+
+/// Overlap between each pair of live generations
+pub struct GenerationOverlaps {
+    ids: Vec<GenerationIds>,
+    i: usize,
+    j: usize,
+}
+
+impl GenerationOverlaps {
+    pub fn new(generated_exams: &[ExamEnvironmentGeneratedExam]) -> Self {
+        let ids = generated_exams
+            .iter()
+            .filter(|g| !g.deprecated)
+            .map(GenerationIds::new)
+            .collect();
+        Self { ids, i: 0, j: 1 }
+    }
+}
+
+impl Iterator for GenerationOverlaps {
+    type Item = GenerationOverlap;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.j >= self.ids.len() {
+            return None;
+        }
+
+        let overlap = overlap(&self.ids[self.i], &self.ids[self.j]);
+        self.j += 1;
+        if self.j == self.ids.len() {
+            self.i += 1;
+            self.j = self.i + 1;
+        }
+        Some(overlap)
+    }
+}
+
+fn overlap(a: &GenerationIds, b: &GenerationIds) -> GenerationOverlap {
+    GenerationOverlap {
+        generations: [a.id, b.id],
+        questions: shared_fraction(&a.questions, &b.questions),
+        answers: shared_fraction(&a.answers, &b.answers),
+    }
+}
+
+/// Fraction of `a` which is also in `b`. Both must be sorted.
+fn shared_fraction(a: &[ObjectId], b: &[ObjectId]) -> f64 {
+    if a.is_empty() {
+        return 0.0;
+    }
+
+    let mut shared = 0;
+    let mut j = 0;
+    for id in a {
+        while j < b.len() && b[j] < *id {
+            j += 1;
+        }
+        if j < b.len() && b[j] == *id {
+            shared += 1;
+            j += 1;
+        }
+    }
+
+    shared as f64 / a.len() as f64
+}
+
+#[derive(Default)]
+struct VariabilityAccumulator {
+    n: usize,
+    sum: f64,
+    min: f64,
+    max: f64,
+}
+
+impl VariabilityAccumulator {
+    fn add(&mut self, x: f64) {
+        if self.n == 0 {
+            self.min = x;
+            self.max = x;
+        }
+        self.n += 1;
+        self.sum += x;
+        self.min = self.min.min(x);
+        self.max = self.max.max(x);
+    }
+
+    fn finish(self) -> Option<Variability> {
+        (self.n > 0).then(|| Variability {
+            mean: self.sum / self.n as f64,
+            min: self.min,
+            max: self.max,
+        })
+    }
+}